@@ -5,7 +5,6 @@ use concordium_base::{
     common::{
         self, c_char,
         types::{Amount, KeyIndex, KeyPair, TransactionSignature, TransactionTime},
-        Deserial,
     },
     contracts_common::{self, schema::VersionedModuleSchema, AccountAddress, Address, Cursor},
     encrypted_transfers,
@@ -31,8 +30,9 @@ use concordium_base::{
 use dodis_yampolskiy_prf as prf;
 use ed25519_hd_key_derivation::DeriveError;
 use either::Either::{Left, Right};
-use elgamal::BabyStepGiantStep;
+use elgamal::MappedBabyStepGiantStep;
 use key_derivation::{ConcordiumHdWallet, Net};
+use lazy_static::lazy_static;
 use pairing::bls12_381::Bls12;
 use rand::thread_rng;
 use serde_json::{from_str, from_value, to_string, Value};
@@ -1102,25 +1102,26 @@ fn generate_accounts_aux(input: &str) -> anyhow::Result<String> {
     Ok(to_string(&response)?)
 }
 
-/// Embed the precomputed table for decryption.
-/// It is unfortunate that this is pure bytes, but not enough of data is marked
-/// as const, and in any case a HashMap relies on an allocator, so will never be
-/// const.
+/// Embed the precomputed table for decryption. The table is in the format of
+/// [`MappedBabyStepGiantStep`], so lookups are done directly in the embedded
+/// bytes, and only the header is checked when the table is first used.
 static TABLE_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/table_bytes.bin"));
 
+lazy_static! {
+    static ref TABLE: MappedBabyStepGiantStep<ArCurve> =
+        MappedBabyStepGiantStep::from_static_bytes(TABLE_BYTES)
+            .expect("The embedded table is generated by the build script.");
+}
+
 fn decrypt_encrypted_amount_aux(input: &str) -> anyhow::Result<Amount> {
     let v: Value = from_str(input)?;
     let encrypted_amount = try_get(&v, "encryptedAmount")?;
     let secret = try_get(&v, "encryptionSecretKey")?;
 
-    let table = BabyStepGiantStep::deserial(&mut std::io::Cursor::new(TABLE_BYTES))?;
-    Ok(
-        encrypted_transfers::decrypt_amount::<id::constants::ArCurve>(
-            &table,
-            &secret,
-            &encrypted_amount,
-        ),
-    )
+    Ok(encrypted_transfers::decrypt_amount::<
+        id::constants::ArCurve,
+        _,
+    >(&*TABLE, &secret, &encrypted_amount))
 }
 
 fn parse_wallet_input(v: &Value) -> anyhow::Result<ConcordiumHdWallet> {
@@ -75,6 +75,19 @@ struct Combine {
         help = "Files with the JSON encoded decrypted shares."
     )]
     shares:     Vec<PathBuf>,
+    #[structopt(
+        long = "ars",
+        help = "File with the anonymity revokers. The proofs of the decrypted shares are checked \
+                against their public keys before the shares are combined.",
+        default_value = "database/anonymity_revokers.json"
+    )]
+    ars:        PathBuf,
+    #[structopt(
+        long = "no-verify",
+        help = "Combine the decrypted shares without checking their proofs. A wrong share leads \
+                to a wrong idCredPub without any error."
+    )]
+    no_verify:  bool,
     #[structopt(long = "out", help = "File to output the decryption to.")]
     out:        PathBuf,
 }
@@ -293,13 +306,17 @@ fn handle_decrypt_id(dcr: Decrypt) -> Result<(), String> {
         ar_data.get(&ar.public_ar_info.ar_identity),
         "Supplied AR is not part of the credential."
     );
-    let m = ar
-        .ar_secret_key
-        .decrypt(&single_ar_data.enc_id_cred_pub_share);
-    let share = ChainArDecryptedData {
-        ar_identity:       ar.public_ar_info.ar_identity,
-        id_cred_pub_share: m,
-    };
+    // The share includes a proof of correct decryption, so that the shares can
+    // be checked when they are combined.
+    let share = succeed_or_die!(
+        make_id_cred_pub_decryption_share(
+            &mut rand::thread_rng(),
+            ar.public_ar_info.ar_identity,
+            &ar.ar_secret_key,
+            &single_ar_data.enc_id_cred_pub_share,
+        ),
+        "Could not prove correctness of the decryption."
+    );
     match write_json_to_file(&dcr.out, &share) {
         Ok(_) => println!("Wrote decryption to {}", dcr.out.display()),
         Err(e) => {
@@ -367,6 +384,33 @@ fn handle_combine_id(cmb: Combine) -> Result<(), String> {
 
     let shares_values: Vec<_> = cmb.shares;
 
+    if !cmb.no_verify {
+        let ars = succeed_or_die!(read_anonymity_revokers(cmb.ars), e => "Could not read anonymity revokers due to {}");
+        let mut shares: Vec<IdCredPubDecryptionShare<ExampleCurve>> =
+            Vec::with_capacity(shares_values.len());
+        for share_value in shares_values.iter() {
+            let share = read_json_from_file(&share_value).map_err(|e| {
+                format!(
+                    "Could not read from ar file {}, error: {}",
+                    share_value.display(),
+                    e
+                )
+            })?;
+            shares.push(share);
+        }
+        let id_cred_pub = succeed_or_die!(combine_id_cred_pub_shares(
+            &credential.ar_data,
+            revocation_threshold,
+            &ars.anonymity_revokers,
+            &shares,
+        ), e => "Could not combine the shares: {}");
+        return write_id_cred_pub(&cmb.out, &id_cred_pub);
+    }
+    eprintln!(
+        "Warning: The decrypted shares are combined without checking their proofs. If any of them \
+         is wrong the resulting idCredPub is wrong."
+    );
+
     let number_of_ars = shares_values.len();
     let number_of_ars =
         u8::try_from(number_of_ars).expect("Number of anonymity revokers should not exceed 2^8-1");
@@ -410,11 +454,15 @@ fn handle_combine_id(cmb: Combine) -> Result<(), String> {
     }
 
     let id_cred_pub = reveal_id_cred_pub(&shares);
-    let id_cred_pub_string = base16_encode_string(&id_cred_pub);
+    write_id_cred_pub(&cmb.out, &id_cred_pub)
+}
+
+fn write_id_cred_pub(out: &Path, id_cred_pub: &ExampleCurve) -> Result<(), String> {
+    let id_cred_pub_string = base16_encode_string(id_cred_pub);
 
     let json = json!({ "idCredPub": id_cred_pub_string });
-    match write_json_to_file(&cmb.out, &json) {
-        Ok(_) => println!("Wrote idCredPub to {}.", cmb.out.display()),
+    match write_json_to_file(out, &json) {
+        Ok(_) => println!("Wrote idCredPub to {}.", out.display()),
         Err(e) => {
             eprintln!("Could not write to file because {}", e);
         }
@@ -263,10 +263,57 @@ struct Trace {
         default_value = "https://wallet-proxy.eu.staging.concordium.com"
     )]
     source: url::Url,
+    #[structopt(
+        long = "table",
+        help = "File with a precomputed table for decrypting amounts. If the file does not exist \
+                the table is computed and stored in it."
+    )]
+    table:  Option<PathBuf>,
     #[structopt(subcommand)]
     mode:   Mode,
 }
 
+type DecryptionTable = elgamal::MappedBabyStepGiantStep<id::constants::ArCurve>;
+
+/// Load the table for decrypting amounts from the file, computing and storing
+/// it first if the file does not exist. If no file is given the table is only
+/// computed in memory.
+fn get_table(
+    global: &GlobalContext<id::constants::ArCurve>,
+    file: Option<PathBuf>,
+) -> anyhow::Result<DecryptionTable> {
+    let generator = global.encryption_in_exponent_generator();
+    let chunk_size = encrypted_transfers::types::CHUNK_SIZE;
+    match file {
+        Some(file) => {
+            if !file.exists() {
+                // Write the table to a temporary file first, so that an
+                // interrupted run does not leave a truncated table behind.
+                let mut tmp_name = file.clone().into_os_string();
+                tmp_name.push(format!(".{}.tmp", std::process::id()));
+                let tmp_file = PathBuf::from(tmp_name);
+                let mut out = std::io::BufWriter::new(std::fs::File::create(&tmp_file)?);
+                DecryptionTable::write_table(&mut out, generator, 1 << 16, chunk_size)?;
+                out.into_inner()?.sync_all()?;
+                std::fs::rename(&tmp_file, &file)?;
+            }
+            // The table file is not modified while the tool runs.
+            let table = unsafe { DecryptionTable::map(&file)? };
+            anyhow::ensure!(
+                table.generator() == generator,
+                "The table in {} is for a different generator.",
+                file.display()
+            );
+            Ok(table)
+        }
+        None => {
+            let mut bytes = Vec::new();
+            DecryptionTable::write_table(&mut bytes, generator, 1 << 16, chunk_size)?;
+            Ok(DecryptionTable::from_bytes(bytes)?)
+        }
+    }
+}
+
 fn main() {
     let app = Trace::clap()
         .setting(AppSettings::ArgRequiredElseHelp)
@@ -288,7 +335,13 @@ fn main() {
                 return;
             }
         };
-    let table = elgamal::BabyStepGiantStep::new(global.encryption_in_exponent_generator(), 1 << 16);
+    let table = match get_table(&global, tr.table) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Could not get the decryption table {}", e);
+            return;
+        }
+    };
     let mut writer: Box<dyn std::io::Write> = if let Some(file) = tr.out {
         Box::new(std::fs::File::create(file).expect("Cannot create output file"))
     } else {
@@ -340,7 +393,7 @@ fn main() {
 }
 
 fn trace_single_account(
-    table: &elgamal::BabyStepGiantStep<id::constants::ArCurve>,
+    table: &DecryptionTable,
     source: &url::Url,
     input: &RetrievalInput,
     writer: &mut impl std::io::Write,
@@ -1,11 +1,15 @@
 //! Some command line auxiliary utilities.
 //! At the moment we have encryption and decryption in the formats used by other
-//! parts of the Concordium project.
+//! parts of the Concordium project, and decoding of serialized block items.
 
 use anyhow::Context;
 use clap::AppSettings;
 use client_server_helpers::*;
-use std::path::PathBuf;
+use concordium_base::{
+    contracts_common::{self, schema},
+    transactions::explain::explain_serialized_block_item,
+};
+use std::{io::Read, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -27,6 +31,21 @@ struct ConfigDecrypt {
     output: Option<PathBuf>,
 }
 
+#[derive(StructOpt)]
+struct ConfigExplain {
+    #[structopt(
+        long = "in",
+        help = "File with the hex-encoded block item. Defaults to standard input."
+    )]
+    input:          Option<PathBuf>,
+    #[structopt(
+        long = "parameter-type",
+        help = "File with a serialized schema type used to decode the parameter of contract \
+                initializations and updates."
+    )]
+    parameter_type: Option<PathBuf>,
+}
+
 #[derive(StructOpt)]
 #[structopt(
     about = "Various helper utilities",
@@ -38,6 +57,11 @@ enum Utils {
     Encrypt(ConfigEncrypt),
     #[structopt(name = "decrypt", about = "Decrypt the contents of the supplied file.")]
     Decrypt(ConfigDecrypt),
+    #[structopt(
+        name = "explain-block-item",
+        about = "Decode a hex-encoded block item and output a description of it as JSON."
+    )]
+    Explain(ConfigExplain),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -49,6 +73,7 @@ fn main() -> anyhow::Result<()> {
     match utls {
         Utils::Encrypt(cfg) => handle_encrypt(cfg),
         Utils::Decrypt(cfg) => handle_decrypt(cfg),
+        Utils::Explain(cfg) => handle_explain(cfg),
     }
 }
 
@@ -82,3 +107,30 @@ fn handle_decrypt(cfg: ConfigDecrypt) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+fn handle_explain(cfg: ConfigExplain) -> anyhow::Result<()> {
+    let input = match cfg.input {
+        Some(fname) => std::fs::read_to_string(fname).context("Cannot read input file.")?,
+        None => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .context("Cannot read standard input.")?;
+            input
+        }
+    };
+    let bytes = hex::decode(input.trim()).context("Input is not valid hex.")?;
+    let parameter_type = match cfg.parameter_type {
+        Some(fname) => {
+            let data = std::fs::read(&fname).context("Cannot read parameter type file.")?;
+            let ty: schema::Type = contracts_common::from_bytes(&data)
+                .map_err(|_| anyhow::anyhow!("Could not parse the schema type."))?;
+            Some(ty)
+        }
+        None => None,
+    };
+    let explanation = explain_serialized_block_item(&bytes, parameter_type.as_ref())
+        .context("Could not parse the block item.")?;
+    println!("{}", serde_json::to_string_pretty(&explanation)?);
+    Ok(())
+}
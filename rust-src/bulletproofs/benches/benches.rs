@@ -107,6 +107,73 @@ pub fn prove_verify_benchmarks(c: &mut Criterion) {
     });
 }
 
+/// Compare verifying many proofs with [`verify_batch`] to verifying each of
+/// them with [`verify_efficient`].
+pub fn verify_batch_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Verify Batch");
+
+    let rng = &mut thread_rng();
+    let n: u8 = 64;
+    let G_H = (0..n)
+        .map(|_| (SomeCurve::generate(rng), SomeCurve::generate(rng)))
+        .collect();
+    let gens = Generators { G_H };
+    let keys = CommitmentKey {
+        g: SomeCurve::generate(rng),
+        h: SomeCurve::generate(rng),
+    };
+    for &k in [1usize, 4, 16, 64].iter() {
+        let mut commitments = Vec::with_capacity(k);
+        let mut proofs = Vec::with_capacity(k);
+        for _ in 0..k {
+            let v: u64 = rng.gen();
+            let r = Randomness::generate(rng);
+            let com = keys.hide(&Value::<SomeCurve>::new(SomeCurve::scalar_from_u64(v)), &r);
+            let proof = prove(
+                &mut RandomOracle::domain("batch"),
+                rng,
+                n,
+                1,
+                &[v],
+                &gens,
+                &keys,
+                &[r],
+            )
+            .expect("Proving should succeed.");
+            commitments.push([com]);
+            proofs.push(proof);
+        }
+        group.bench_function(format!("Verify Efficient, {} proofs", k), |b| {
+            b.iter(|| {
+                for (commitments, proof) in commitments.iter().zip(proofs.iter()) {
+                    let mut transcript = RandomOracle::domain("batch");
+                    assert!(
+                        verify_efficient(&mut transcript, n, commitments, proof, &gens, &keys)
+                            .is_ok()
+                    );
+                }
+            })
+        });
+        group.bench_function(format!("Verify Batch, {} proofs", k), |b| {
+            b.iter(|| {
+                let items = commitments
+                    .iter()
+                    .zip(proofs.iter())
+                    .map(|(commitments, proof)| RangeProofBatchItem {
+                        transcript: RandomOracle::domain("batch"),
+                        n,
+                        commitments,
+                        proof,
+                        gens: &gens,
+                        v_keys: &keys,
+                    })
+                    .collect::<Vec<_>>();
+                assert!(verify_batch(&mut thread_rng(), &items).is_ok());
+            })
+        });
+    }
+}
+
 #[allow(non_snake_case)]
 fn compare_inner_product_proof(c: &mut Criterion) {
     let mut group = c.benchmark_group("Inner-Product Proof");
@@ -175,5 +242,5 @@ fn compare_inner_product_proof(c: &mut Criterion) {
 criterion_group!(
     name = benchmarks;
     config = Criterion::default().measurement_time(Duration::from_millis(1000)).sample_size(10);
-    targets = prove_verify_benchmarks, compare_inner_product_proof);
+    targets = prove_verify_benchmarks, verify_batch_benchmarks, compare_inner_product_proof);
 criterion_main!(benchmarks);
@@ -1,5 +1,6 @@
 //! Logarithmic sized inner product proof used as base for the other proofs in
 //! this crate
+use crate::utils::MultiExpCheck;
 use crypto_common::*;
 use crypto_common_derive::*;
 use curve_arithmetic::{multiexp, Curve};
@@ -350,6 +351,29 @@ pub(crate) fn verify_inner_product_with_scalars<C: Curve>(
     P_prime_exponents: &[C::Scalar],
     proof: &InnerProductProof<C>,
 ) -> bool {
+    match inner_product_check_with_scalars(
+        transcript,
+        H_exponents,
+        P_prime_bases,
+        P_prime_exponents,
+        proof,
+    ) {
+        Some(check) => check.holds(),
+        None => false,
+    }
+}
+
+/// Like [`verify_inner_product_with_scalars`], but instead of evaluating the
+/// final multi-exponentiation it is returned, so that it can be combined with
+/// other checks. [`None`] is returned if the proof is rejected before that.
+#[allow(non_snake_case)]
+pub(crate) fn inner_product_check_with_scalars<C: Curve>(
+    transcript: &mut RandomOracle,
+    H_exponents: &[C::Scalar],
+    P_prime_bases: &[C],
+    P_prime_exponents: &[C::Scalar],
+    proof: &InnerProductProof<C>,
+) -> Option<MultiExpCheck<C>> {
     let n = H_exponents.len();
     let L_R = &proof.lr_vec;
     let a = proof.a;
@@ -357,10 +381,7 @@ pub(crate) fn verify_inner_product_with_scalars<C: Curve>(
     let mut ab = a;
     ab.mul_assign(&b);
 
-    let verification_scalars = match verify_scalars(transcript, n, proof) {
-        None => return false,
-        Some(scalars) => scalars,
-    };
+    let verification_scalars = verify_scalars(transcript, n, proof)?;
     let (u_sq, u_inv_sq, s) = (
         verification_scalars.u_sq,
         verification_scalars.u_inv_sq,
@@ -384,51 +405,49 @@ pub(crate) fn verify_inner_product_with_scalars<C: Curve>(
     // RHS = prod_i G_i^(s_i*a) H_i^(H_exponents_i * s_i^-1 * b) * Q^{ab} * nsum
     let mut s_inv = s.clone();
     s_inv.reverse();
-    let mut G_exps = s;
-    for ge in &mut G_exps {
-        ge.mul_assign(&a);
-    }
 
     // Prepare bases and exponents for computation of RHS and leave space for
-    // additional P' computation
-    let mut rhs_bases = Vec::with_capacity(nsum_bases.len() + P_prime_bases.len());
-    // Add G, H, and Q to rhs_bases
-    rhs_bases.extend(&P_prime_bases[0..2 * n + 1]);
+    // additional P' computation. The first 2n + 1 elements of P' are G, H, and
+    // Q, which also occur in RHS.
+    let mut rhs_bases = Vec::with_capacity(nsum_bases.len() + P_prime_bases.len() - 2 * n);
+    let mut rhs_exps = Vec::with_capacity(rhs_bases.capacity());
 
-    // add further elements to rhs_bases
-    rhs_bases.append(&mut nsum_bases);
-    rhs_bases.extend(&P_prime_bases[2 * n + 1..]);
-
-    let mut rhs_exps = Vec::with_capacity(rhs_bases.len());
-    rhs_exps.append(&mut G_exps);
-
-    // Compute H_exps_i = H_exponents_i * s_i^-1 * b and add to rhs_exps
-    for i in 0..H_exponents.len() {
+    // check whether P' = RHS <=> 0 = RHS P'^-1
+    // The generators G and H are shared with other checks using the same
+    // generators. Their terms are added as G_0, H_0, G_1, H_1, ..., so that
+    // they line up with the terms of checks that use a prefix of them.
+    let mut shared = Vec::with_capacity(2 * n);
+    for i in 0..n {
+        let mut gi = s[i];
+        gi.mul_assign(&a);
+        gi.sub_assign(&P_prime_exponents[i]);
+        shared.push((P_prime_bases[i], gi));
+        // H_exps_i = H_exponents_i * s_i^-1 * b
         let mut hi = H_exponents[i];
         hi.mul_assign(&s_inv[i]);
         hi.mul_assign(&b);
-        rhs_exps.push(hi);
+        hi.sub_assign(&P_prime_exponents[n + i]);
+        shared.push((P_prime_bases[n + i], hi));
     }
+    // Q
+    rhs_bases.push(P_prime_bases[2 * n]);
+    ab.sub_assign(&P_prime_exponents[2 * n]);
     rhs_exps.push(ab);
-    rhs_exps.append(&mut nsum_exps);
 
-    // check whether P' = RHS <=> 0 = RHS P'^-1
-    // add negation of first 2n + 1 exponents to first elements in rhs_exps since
-    // they belong to G, H, and Q
-    for i in 0..2 * n + 1 {
-        rhs_exps[i].sub_assign(&P_prime_exponents[i]);
-    }
+    // add further elements to rhs_bases
+    rhs_bases.append(&mut nsum_bases);
+    rhs_exps.append(&mut nsum_exps);
 
     // negate remaining elements of P_prime_exponents and append them to rhs_exps
+    rhs_bases.extend(&P_prime_bases[2 * n + 1..]);
     let mut nppexps = P_prime_exponents[2 * n + 1..].to_vec();
     for nppe in &mut nppexps {
         nppe.negate();
     }
     rhs_exps.append(&mut nppexps);
 
-    // Finally compute RHS P'^-1 and check whether it is 0
-    let rhs_p_inv = multiexp(&rhs_bases, &rhs_exps);
-    rhs_p_inv.is_zero_point()
+    // Finally RHS P'^-1 must be 0
+    Some(MultiExpCheck::with_shared(shared, rhs_bases, rhs_exps))
 }
 
 /// This function calculates the inner product between two vectors over any
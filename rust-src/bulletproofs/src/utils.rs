@@ -1,10 +1,9 @@
 //! Shared functions used by the proofs in this crate
 use crypto_common::*;
 use crypto_common_derive::*;
-use curve_arithmetic::Curve;
+use curve_arithmetic::{multiexp, Curve};
 use ff::Field;
-#[cfg(test)]
-use rand::Rng;
+use rand::{CryptoRng, Rng};
 /// Struct containing generators G and H needed for range proofs
 #[allow(non_snake_case)]
 #[derive(Debug, Clone, Serialize, SerdeBase16Serialize)]
@@ -72,11 +71,156 @@ pub(crate) fn pad_vector_to_power_of_two<F: Field>(vec: &mut Vec<F>) {
     }
 }
 
+/// A multi-exponentiation `sum_i exponents_i * bases_i` that must be zero for
+/// a proof to be valid. Verifying a proof amounts to evaluating such checks,
+/// and the checks of independent proofs can be combined with random weights
+/// into a single check. If any of the combined checks do not hold, then the
+/// combination does not hold either, except with negligible probability.
+///
+/// Terms whose bases are shared between proofs, such as the generators of the
+/// range proofs, are kept separately, and when checks are combined the terms
+/// with equal bases are merged into a single term. The cost of evaluating the
+/// combined check is thus linear in the number of bases that are specific to
+/// the proofs, plus the number of distinct shared bases.
+#[derive(Debug, Clone)]
+pub struct MultiExpCheck<C: Curve> {
+    /// Terms whose bases may occur in other checks.
+    shared:    Vec<(C, C::Scalar)>,
+    bases:     Vec<C>,
+    exponents: Vec<C::Scalar>,
+}
+
+impl<C: Curve> Default for MultiExpCheck<C> {
+    fn default() -> Self {
+        Self {
+            shared:    Vec::new(),
+            bases:     Vec::new(),
+            exponents: Vec::new(),
+        }
+    }
+}
+
+impl<C: Curve> MultiExpCheck<C> {
+    /// Construct a check from the bases and the exponents they are raised to.
+    /// The two vectors must have the same length.
+    pub fn new(bases: Vec<C>, exponents: Vec<C::Scalar>) -> Self {
+        debug_assert_eq!(bases.len(), exponents.len());
+        Self {
+            shared: Vec::new(),
+            bases,
+            exponents,
+        }
+    }
+
+    /// Construct a check from terms whose bases may occur in other checks, see
+    /// [`add_shared`](Self::add_shared), and the remaining bases and the
+    /// exponents they are raised to. The latter two vectors must have the same
+    /// length.
+    pub fn with_shared(
+        shared: Vec<(C, C::Scalar)>,
+        bases: Vec<C>,
+        exponents: Vec<C::Scalar>,
+    ) -> Self {
+        debug_assert_eq!(bases.len(), exponents.len());
+        Self {
+            shared,
+            bases,
+            exponents,
+        }
+    }
+
+    /// Add a term whose base may also occur in other checks, e.g., a
+    /// generator. If the check already has a shared term with the same base,
+    /// the exponent is added to the exponent of that term.
+    pub fn add_shared(&mut self, base: C, exponent: C::Scalar) {
+        let existing = self.shared.len();
+        self.add_shared_at(0, existing, base, exponent);
+    }
+
+    /// Add a shared term, merging it with one of the first `existing` shared
+    /// terms if the base is the same. The term at position `hint` is tried
+    /// first. Checks of proofs with the same generators have their shared
+    /// terms in the same order, so that the lookup is usually constant time.
+    /// The position after the term is returned, which is the hint for the
+    /// next term.
+    fn add_shared_at(
+        &mut self,
+        hint: usize,
+        existing: usize,
+        base: C,
+        exponent: C::Scalar,
+    ) -> usize {
+        let candidates = &self.shared[..existing];
+        let position = match candidates.get(hint) {
+            Some((b, _)) if *b == base => Some(hint),
+            _ => candidates.iter().position(|(b, _)| *b == base),
+        };
+        match position {
+            Some(i) => {
+                self.shared[i].1.add_assign(&exponent);
+                i + 1
+            }
+            None => {
+                self.shared.push((base, exponent));
+                self.shared.len()
+            }
+        }
+    }
+
+    /// The number of terms of the multi-exponentiation.
+    pub fn len(&self) -> usize { self.shared.len() + self.bases.len() }
+
+    /// Whether there are no terms, in which case the check trivially holds.
+    pub fn is_empty(&self) -> bool { self.shared.is_empty() && self.bases.is_empty() }
+
+    /// Add the terms of `other` multiplied by `weight` to this check.
+    pub fn add_weighted(&mut self, other: &Self, weight: &C::Scalar) {
+        // The shared bases of `other` are assumed to be distinct, so they are
+        // only compared with the shared terms that were there before.
+        let existing = self.shared.len();
+        let mut hint = 0;
+        for (base, exponent) in &other.shared {
+            let mut exponent = *exponent;
+            exponent.mul_assign(weight);
+            hint = self.add_shared_at(hint, existing, *base, exponent);
+        }
+        self.bases.extend_from_slice(&other.bases);
+        self.exponents.extend(other.exponents.iter().map(|e| {
+            let mut e = *e;
+            e.mul_assign(weight);
+            e
+        }));
+    }
+
+    /// Add the terms of `other` multiplied by a fresh random weight to this
+    /// check. If the result holds, then with overwhelming probability both
+    /// this check and `other` hold.
+    pub fn add_random<R: Rng + CryptoRng>(&mut self, csprng: &mut R, other: &Self) {
+        let weight = C::generate_non_zero_scalar(csprng);
+        self.add_weighted(other, &weight);
+    }
+
+    /// Evaluate the multi-exponentiation and check that it is zero.
+    pub fn holds(&self) -> bool {
+        let mut bases = Vec::with_capacity(self.len());
+        let mut exponents = Vec::with_capacity(self.len());
+        for (base, exponent) in &self.shared {
+            bases.push(*base);
+            exponents.push(*exponent);
+        }
+        bases.extend_from_slice(&self.bases);
+        exponents.extend_from_slice(&self.exponents);
+        multiexp(&bases, &exponents).is_zero_point()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::{pad_vector_to_power_of_two, z_vec};
+    use super::{pad_vector_to_power_of_two, z_vec, MultiExpCheck};
+    use curve_arithmetic::Curve;
     use ff::Field;
+    use pairing::bls12_381::G1;
     use rand::thread_rng;
 
     type SomeField = pairing::bls12_381::Fq;
@@ -138,4 +282,30 @@ mod tests {
         z.square();
         assert_eq!(*vec.get(0).unwrap(), z, "First element should be z^2")
     }
+
+    #[test]
+    fn test_multiexp_check_shared() {
+        let rng = &mut thread_rng();
+        let g = G1::generate(rng);
+        let h = G1::generate(rng);
+        let x = G1::generate_scalar(rng);
+        let y = G1::generate_scalar(rng);
+        // x * g + y * h - p = 0 with shared g and h.
+        let p = g.mul_by_scalar(&x).plus_point(&h.mul_by_scalar(&y));
+        let mut minus_one = <G1 as Curve>::Scalar::one();
+        minus_one.negate();
+        let check = MultiExpCheck::with_shared(vec![(g, x), (h, y)], vec![p], vec![minus_one]);
+        assert!(check.holds());
+        let mut combined = MultiExpCheck::default();
+        combined.add_random(rng, &check);
+        combined.add_random(rng, &check);
+        assert_eq!(combined.len(), 4, "The terms of g and h should be merged.");
+        assert!(combined.holds());
+        // Shared terms in a different order are merged as well.
+        let wrong = MultiExpCheck::with_shared(vec![(h, x), (g, y)], vec![p], vec![minus_one]);
+        assert!(!wrong.holds());
+        combined.add_random(rng, &wrong);
+        assert_eq!(combined.len(), 5, "The terms of g and h should be merged.");
+        assert!(!combined.holds());
+    }
 }
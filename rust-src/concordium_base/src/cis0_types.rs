@@ -0,0 +1,313 @@
+//! This module contains types and their implementations related to the CIS-0
+//! standard, which is used to detect the standards a contract implements.
+//!
+//! Contracts implementing CIS-0 have a `supports` function which is queried
+//! with a list of standard identifiers, and returns for each of them whether
+//! the contract supports the standard, possibly by delegating to other
+//! contracts.
+
+use crate::{
+    cis2_client::{Cis2ClientError, Cis2Contract},
+    smart_contracts::concordium_contracts_common::{
+        deserial_vector_no_length, serial_vector_no_length, ContractAddress, Deserial, ParseError,
+        Read, Serial, Write,
+    },
+    transactions::UpdateContractPayload,
+};
+use derive_more::{AsRef, Display, Into};
+use std::convert::TryFrom;
+use thiserror::*;
+
+/// The identifier of the CIS-0 standard.
+pub const CIS0_STANDARD_IDENTIFIER: &str = "CIS-0";
+
+/// The identifier of the CIS-1 standard.
+pub const CIS1_STANDARD_IDENTIFIER: &str = "CIS-1";
+
+/// The identifier of the CIS-2 standard.
+pub const CIS2_STANDARD_IDENTIFIER: &str = "CIS-2";
+
+/// The identifier of a standard, which is an ASCII string of at most 255
+/// bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Display, AsRef, Into)]
+pub struct StandardIdentifier(String);
+
+/// Error for constructing a new [`StandardIdentifier`](StandardIdentifier).
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum NewStandardIdentifierError {
+    #[error(
+        "Standard identifier too large. Maximum allowed size is 255 bytes. {0} bytes were \
+         provided."
+    )]
+    TooLarge(usize),
+    #[error("Standard identifiers must only contain ASCII characters.")]
+    NotAscii,
+}
+
+impl StandardIdentifier {
+    /// Construct a new StandardIdentifier.
+    /// Ensures the identifier is ASCII and its length is within `u8::MAX`.
+    pub fn new(id: String) -> Result<Self, NewStandardIdentifierError> {
+        if !id.is_ascii() {
+            return Err(NewStandardIdentifierError::NotAscii);
+        }
+        if id.len() > u8::MAX.into() {
+            return Err(NewStandardIdentifierError::TooLarge(id.len()));
+        }
+        Ok(Self(id))
+    }
+
+    /// Construct a new StandardIdentifier.
+    /// Without ensuring the identifier is ASCII and its length is within
+    /// `u8::MAX`.
+    pub fn new_unchecked(id: String) -> Self { Self(id) }
+
+    /// The identifier of [CIS-0](CIS0_STANDARD_IDENTIFIER).
+    pub fn cis0() -> Self { Self::new_unchecked(CIS0_STANDARD_IDENTIFIER.into()) }
+
+    /// The identifier of [CIS-1](CIS1_STANDARD_IDENTIFIER).
+    pub fn cis1() -> Self { Self::new_unchecked(CIS1_STANDARD_IDENTIFIER.into()) }
+
+    /// The identifier of [CIS-2](CIS2_STANDARD_IDENTIFIER).
+    pub fn cis2() -> Self { Self::new_unchecked(CIS2_STANDARD_IDENTIFIER.into()) }
+}
+
+impl std::str::FromStr for StandardIdentifier {
+    type Err = NewStandardIdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::new(s.into()) }
+}
+
+/// Serialize the identifier according to the CIS-0 specification.
+impl Serial for StandardIdentifier {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        let len = u8::try_from(self.0.len()).map_err(|_| W::Err::default())?;
+        len.serial(out)?;
+        out.write_all(self.0.as_bytes())
+    }
+}
+
+/// Deserialize the identifier according to the CIS-0 specification.
+impl Deserial for StandardIdentifier {
+    fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
+        let len = u8::deserial(source)?;
+        let bytes = deserial_vector_no_length(source, len.into())?;
+        let id = String::from_utf8(bytes)?;
+        if !id.is_ascii() {
+            return Err(ParseError::default());
+        }
+        Ok(Self(id))
+    }
+}
+
+/// The parameter type for the contract function `supports`.
+#[derive(Debug, Clone, AsRef, Into)]
+pub struct SupportsQueryParams(Vec<StandardIdentifier>);
+
+/// Error for constructing a new [`SupportsQueryParams`](SupportsQueryParams).
+#[derive(Debug, PartialEq, Eq, Error)]
+#[error("Invalid number of queries, must be withing a length of u16::MAX.")]
+pub struct NewSupportsQueryParamsError;
+
+impl SupportsQueryParams {
+    /// Construct a new SupportsQueryParams.
+    /// Ensures the length of the provided queries are within `u16::MAX`.
+    pub fn new(queries: Vec<StandardIdentifier>) -> Result<Self, NewSupportsQueryParamsError> {
+        if queries.len() > u16::MAX.into() {
+            return Err(NewSupportsQueryParamsError);
+        }
+        Ok(Self(queries))
+    }
+
+    /// Construct a new SupportsQueryParams.
+    /// Without ensuring the length of the provided queries are within
+    /// `u16::MAX`.
+    pub fn new_unchecked(queries: Vec<StandardIdentifier>) -> Self { Self(queries) }
+}
+
+/// Serialization of the supports parameter, according to the CIS-0
+/// specification.
+impl Serial for SupportsQueryParams {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        let len = u16::try_from(self.0.len()).map_err(|_| W::Err::default())?;
+        len.serial(out)?;
+        serial_vector_no_length(&self.0, out)
+    }
+}
+
+/// The support of a contract for a single standard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupportResult {
+    /// The standard is not supported.
+    NoSupport,
+    /// The standard is supported by the contract itself.
+    Support,
+    /// The standard is supported by delegating to one of the given contracts.
+    SupportBy(Vec<ContractAddress>),
+}
+
+impl SupportResult {
+    /// Whether the standard is supported, either directly or by delegation.
+    pub fn is_supported(&self) -> bool { !matches!(self, SupportResult::NoSupport) }
+}
+
+/// Serialization of a support result, according to the CIS-0 specification.
+impl Serial for SupportResult {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        match self {
+            SupportResult::NoSupport => out.write_u8(0),
+            SupportResult::Support => out.write_u8(1),
+            SupportResult::SupportBy(addresses) => {
+                out.write_u8(2)?;
+                let len = u8::try_from(addresses.len()).map_err(|_| W::Err::default())?;
+                len.serial(out)?;
+                serial_vector_no_length(addresses, out)
+            }
+        }
+    }
+}
+
+/// Deserialization of a support result, according to the CIS-0 specification.
+impl Deserial for SupportResult {
+    fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
+        match source.read_u8()? {
+            0 => Ok(SupportResult::NoSupport),
+            1 => Ok(SupportResult::Support),
+            2 => {
+                let len = u8::deserial(source)?;
+                let addresses = deserial_vector_no_length(source, len.into())?;
+                Ok(SupportResult::SupportBy(addresses))
+            }
+            _ => Err(ParseError::default()),
+        }
+    }
+}
+
+/// The response which is sent back when calling the contract function
+/// `supports`.
+/// It consists of the list of results in the same order and length as the
+/// queries in the parameter.
+#[derive(Debug, Clone, PartialEq, Eq, AsRef, Into)]
+pub struct SupportsQueryResponse(Vec<SupportResult>);
+
+/// Error for constructing a new
+/// [`SupportsQueryResponse`](SupportsQueryResponse).
+#[derive(Debug, PartialEq, Eq, Error)]
+#[error("Invalid number of results, must be withing a length of u16::MAX.")]
+pub struct NewSupportsQueryResponseError;
+
+impl SupportsQueryResponse {
+    /// Construct a new SupportsQueryResponse.
+    /// Ensures the length of the provided results is within `u16::MAX`.
+    pub fn new(results: Vec<SupportResult>) -> Result<Self, NewSupportsQueryResponseError> {
+        if results.len() > u16::MAX.into() {
+            return Err(NewSupportsQueryResponseError);
+        }
+        Ok(Self(results))
+    }
+
+    /// Construct a new SupportsQueryResponse.
+    /// Without ensuring the length of the provided results is within
+    /// `u16::MAX`.
+    pub fn new_unchecked(results: Vec<SupportResult>) -> Self { Self(results) }
+}
+
+/// Serialization for SupportsQueryResponse according to the CIS-0
+/// specification.
+impl Serial for SupportsQueryResponse {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        let len = u16::try_from(self.0.len()).map_err(|_| W::Err::default())?;
+        len.serial(out)?;
+        serial_vector_no_length(&self.0, out)
+    }
+}
+
+/// Deserialization for SupportsQueryResponse according to the CIS-0
+/// specification.
+impl Deserial for SupportsQueryResponse {
+    fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
+        let len = source.read_u16()?;
+        let mut results = Vec::with_capacity(len.into());
+        for _ in 0..len {
+            results.push(SupportResult::deserial(source)?)
+        }
+        Ok(SupportsQueryResponse::new_unchecked(results))
+    }
+}
+
+/// Construct the payload for querying which of the given standards the
+/// contract instance at `address` supports. The `contract_name` is the name of
+/// the contract without the `init_` prefix. This is
+/// [`Cis2Contract::supports`], for contracts that are not known to implement
+/// CIS-2.
+pub fn supports_payload(
+    address: ContractAddress,
+    contract_name: &str,
+    queries: &SupportsQueryParams,
+) -> Result<UpdateContractPayload, Cis2ClientError> {
+    Cis2Contract::new(address, contract_name)?.supports(queries)
+}
+
+/// Pair each of the queried standards with the result for it. This fails if
+/// the response does not have exactly one result per query.
+pub fn supported_standards<'a>(
+    queries: &'a SupportsQueryParams,
+    response: &'a SupportsQueryResponse,
+) -> Option<Vec<(&'a StandardIdentifier, &'a SupportResult)>> {
+    if queries.0.len() != response.0.len() {
+        return None;
+    }
+    Some(queries.0.iter().zip(response.0.iter()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::smart_contracts::concordium_contracts_common::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_supports_serialization() {
+        let queries =
+            SupportsQueryParams::new(vec![StandardIdentifier::cis0(), "CIS-2".parse().unwrap()])
+                .unwrap();
+        assert_eq!(to_bytes(&queries), b"\x02\x00\x05CIS-0\x05CIS-2");
+        assert_eq!(
+            StandardIdentifier::new("CIS-∞".into()),
+            Err(NewStandardIdentifierError::NotAscii)
+        );
+        assert_eq!(
+            StandardIdentifier::new("a".repeat(256)),
+            Err(NewStandardIdentifierError::TooLarge(256))
+        );
+
+        let response = SupportsQueryResponse::new(vec![
+            SupportResult::Support,
+            SupportResult::SupportBy(vec![ContractAddress::new(1, 0), ContractAddress::new(2, 0)]),
+            SupportResult::NoSupport,
+        ])
+        .unwrap();
+        let bytes = to_bytes(&response);
+        assert_eq!(bytes[..4], [3, 0, 1, 2]);
+        assert_eq!(bytes[4], 2, "Two contract addresses.");
+        assert_eq!(bytes.len(), 2 + 1 + 2 + 2 * 16 + 1);
+        let deserialized: SupportsQueryResponse = from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized, response);
+        assert!(from_bytes::<SupportResult>(&[3]).is_err());
+
+        assert!(supported_standards(&queries, &response).is_none());
+        let response =
+            SupportsQueryResponse::new(vec![SupportResult::Support, SupportResult::NoSupport])
+                .unwrap();
+        let supported = supported_standards(&queries, &response)
+            .unwrap()
+            .into_iter()
+            .filter(|(_, result)| result.is_supported())
+            .map(|(id, _)| id.as_ref().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(supported, [CIS0_STANDARD_IDENTIFIER]);
+
+        let payload = supports_payload(ContractAddress::new(3, 0), "cis2_nft", &queries).unwrap();
+        let message: &Vec<u8> = payload.message.as_ref();
+        assert_eq!(message, &to_bytes(&queries));
+    }
+}
@@ -0,0 +1,273 @@
+//! A typed interface to contracts implementing the CIS-2 token standard.
+//!
+//! A [`Cis2Contract`] builds the [`UpdateContractPayload`] for invoking each
+//! of the functions of the standard with the parameter types of
+//! [`cis2_types`](crate::cis2_types). The return values of the query functions
+//! are decoded by [`decode_balance_of`], [`decode_operator_of`],
+//! [`decode_token_metadata`] and [`decode_supports`], and the reject reason of
+//! a failed invocation is interpreted by [`decode_reject_reason`].
+use crate::{
+    cis0_types::{SupportResult, SupportsQueryParams, SupportsQueryResponse},
+    cis2_types::{
+        BalanceOfQueryParams, BalanceOfQueryResponse, Cis2ErrorRejectReason, MetadataUrl,
+        OperatorOfQueryParams, OperatorOfQueryResponse, TokenAmount, TokenMetadataQueryParams,
+        TokenMetadataQueryResponse, TransferParams, UpdateOperatorParams,
+    },
+    smart_contracts::{
+        concordium_contracts_common::{
+            from_bytes, Amount, ContractAddress, Deserial, NewReceiveNameError, OwnedReceiveName,
+            Serial,
+        },
+        ExceedsParameterSize, Parameter,
+    },
+    transactions::UpdateContractPayload,
+};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// An error in building a payload for a CIS-2 contract, or in decoding its
+/// return value.
+#[derive(Debug, Error)]
+pub enum Cis2ClientError {
+    #[error("Invalid contract name: {0}")]
+    InvalidContractName(#[from] NewReceiveNameError),
+    #[error("The parameter could not be serialized.")]
+    InvalidParameter,
+    #[error("{0}")]
+    ParameterTooLarge(#[from] ExceedsParameterSize),
+    #[error("The return value is not valid for the function.")]
+    InvalidReturnValue,
+    #[error("The return value has {actual} results, but {expected} queries were made.")]
+    ResultCountMismatch { expected: usize, actual: usize },
+}
+
+/// A contract instance that implements CIS-2.
+#[derive(Debug, Clone)]
+pub struct Cis2Contract {
+    /// The address of the contract instance.
+    pub address:   ContractAddress,
+    contract_name: String,
+}
+
+impl Cis2Contract {
+    /// Construct a client for the instance at the given address. The
+    /// `contract_name` is the name of the contract without the `init_` prefix,
+    /// which is used to construct the names of the functions of the contract.
+    pub fn new(address: ContractAddress, contract_name: &str) -> Result<Self, Cis2ClientError> {
+        // Check that the name can be used for the receive names.
+        OwnedReceiveName::new(format!("{}.transfer", contract_name))?;
+        Ok(Self {
+            address,
+            contract_name: contract_name.into(),
+        })
+    }
+
+    /// The name of the contract, without the `init_` prefix.
+    pub fn contract_name(&self) -> &str { &self.contract_name }
+
+    /// Build the payload for invoking the given function with the parameter.
+    /// No CCD is sent, since CIS-2 functions are not payable.
+    fn make_update(
+        &self,
+        function: &str,
+        parameter: &impl Serial,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        let receive_name = OwnedReceiveName::new(format!("{}.{}", self.contract_name, function))?;
+        let mut bytes = Vec::new();
+        parameter
+            .serial(&mut bytes)
+            .map_err(|_| Cis2ClientError::InvalidParameter)?;
+        Ok(UpdateContractPayload {
+            amount: Amount::from_micro_ccd(0),
+            address: self.address,
+            receive_name,
+            message: Parameter::try_from(bytes)?,
+        })
+    }
+
+    /// Payload for transferring tokens.
+    pub fn transfer(
+        &self,
+        transfers: &TransferParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("transfer", transfers)
+    }
+
+    /// Payload for adding and removing operators of the sender.
+    pub fn update_operator(
+        &self,
+        updates: &UpdateOperatorParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("updateOperator", updates)
+    }
+
+    /// Payload for querying balances. The return value is decoded by
+    /// [`decode_balance_of`].
+    pub fn balance_of(
+        &self,
+        queries: &BalanceOfQueryParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("balanceOf", queries)
+    }
+
+    /// Payload for querying operators. The return value is decoded by
+    /// [`decode_operator_of`].
+    pub fn operator_of(
+        &self,
+        queries: &OperatorOfQueryParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("operatorOf", queries)
+    }
+
+    /// Payload for querying the metadata URLs of tokens. The return value is
+    /// decoded by [`decode_token_metadata`].
+    pub fn token_metadata(
+        &self,
+        queries: &TokenMetadataQueryParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("tokenMetadata", queries)
+    }
+
+    /// Payload for querying which of the given standards the contract
+    /// supports, as specified by CIS-0. The return value is decoded by
+    /// [`decode_supports`].
+    pub fn supports(
+        &self,
+        queries: &SupportsQueryParams,
+    ) -> Result<UpdateContractPayload, Cis2ClientError> {
+        self.make_update("supports", queries)
+    }
+}
+
+/// Decode a return value that must be a list with one result for each of the
+/// `expected` queries.
+fn decode_results<A: Deserial + AsRef<Vec<T>>, T>(
+    return_value: &[u8],
+    expected: usize,
+) -> Result<A, Cis2ClientError> {
+    let response: A = from_bytes(return_value).map_err(|_| Cis2ClientError::InvalidReturnValue)?;
+    let actual = response.as_ref().len();
+    if actual != expected {
+        return Err(Cis2ClientError::ResultCountMismatch { expected, actual });
+    }
+    Ok(response)
+}
+
+/// Decode the return value of `balanceOf` invoked with the given queries.
+pub fn decode_balance_of(
+    queries: &BalanceOfQueryParams,
+    return_value: &[u8],
+) -> Result<BalanceOfQueryResponse, Cis2ClientError> {
+    decode_results::<_, TokenAmount>(return_value, queries.as_ref().len())
+}
+
+/// Decode the return value of `operatorOf` invoked with the given queries.
+pub fn decode_operator_of(
+    queries: &OperatorOfQueryParams,
+    return_value: &[u8],
+) -> Result<OperatorOfQueryResponse, Cis2ClientError> {
+    decode_results::<_, bool>(return_value, queries.as_ref().len())
+}
+
+/// Decode the return value of `tokenMetadata` invoked with the given queries.
+pub fn decode_token_metadata(
+    queries: &TokenMetadataQueryParams,
+    return_value: &[u8],
+) -> Result<TokenMetadataQueryResponse, Cis2ClientError> {
+    decode_results::<_, MetadataUrl>(return_value, queries.as_ref().len())
+}
+
+/// Decode the return value of `supports` invoked with the given queries.
+pub fn decode_supports(
+    queries: &SupportsQueryParams,
+    return_value: &[u8],
+) -> Result<SupportsQueryResponse, Cis2ClientError> {
+    decode_results::<_, SupportResult>(return_value, queries.as_ref().len())
+}
+
+/// Interpret the reject reason of a failed invocation of a CIS-2 function.
+/// Reasons that are not specified by CIS-2 are returned as
+/// [`Other`](Cis2ErrorRejectReason::Other).
+pub fn decode_reject_reason(reject_reason: i32) -> Cis2ErrorRejectReason {
+    Cis2ErrorRejectReason::from(reject_reason)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cis0_types::StandardIdentifier,
+        cis2_types::{AdditionalData, BalanceOfQuery, Receiver, TokenId, Transfer},
+        smart_contracts::concordium_contracts_common::{to_bytes, AccountAddress, Address},
+    };
+
+    #[test]
+    fn test_cis2_contract() {
+        let contract = Cis2Contract::new(ContractAddress::new(3, 0), "cis2_nft").unwrap();
+        assert!(Cis2Contract::new(ContractAddress::new(3, 0), "cis2.nft").is_err());
+
+        let alice = AccountAddress([1u8; 32]);
+        let transfers = TransferParams::new(vec![Transfer {
+            token_id: TokenId::new_unchecked(vec![1]),
+            amount:   TokenAmount::from(1u8),
+            from:     Address::Account(alice),
+            to:       Receiver::Account(AccountAddress([2u8; 32])),
+            data:     AdditionalData::default(),
+        }])
+        .unwrap();
+        let payload = contract.transfer(&transfers).unwrap();
+        assert_eq!(
+            payload.receive_name.as_receive_name().get_chain_name(),
+            "cis2_nft.transfer"
+        );
+        let message: &Vec<u8> = payload.message.as_ref();
+        assert_eq!(message, &to_bytes(&transfers));
+
+        let supports =
+            SupportsQueryParams::new(vec![StandardIdentifier::new("CIS-2".into()).unwrap()])
+                .unwrap();
+        let payload = contract.supports(&supports).unwrap();
+        assert_eq!(
+            payload.receive_name.as_receive_name().get_chain_name(),
+            "cis2_nft.supports"
+        );
+        assert!(matches!(
+            decode_supports(&supports, &[1, 0, 1]),
+            Ok(response) if response.as_ref()[0] == SupportResult::Support
+        ));
+
+        let queries = BalanceOfQueryParams::new(vec![BalanceOfQuery {
+            token_id: TokenId::new_unchecked(vec![1]),
+            address:  Address::Account(alice),
+        }])
+        .unwrap();
+        let response = BalanceOfQueryResponse::new(vec![TokenAmount::from(5u8)]).unwrap();
+        // The response is serialized like the list of amounts, with a two byte
+        // length.
+        let mut return_value = vec![1u8, 0];
+        return_value.extend(to_bytes(&TokenAmount::from(5u8)));
+        assert_eq!(
+            decode_balance_of(&queries, &return_value).unwrap(),
+            response
+        );
+        let mut two_results = vec![2u8, 0];
+        two_results.extend(to_bytes(&TokenAmount::from(5u8)));
+        two_results.extend(to_bytes(&TokenAmount::from(6u8)));
+        assert!(matches!(
+            decode_balance_of(&queries, &two_results),
+            Err(Cis2ClientError::ResultCountMismatch {
+                expected: 1,
+                actual:   2,
+            })
+        ));
+        assert!(matches!(
+            decode_balance_of(&queries, &return_value[..2]),
+            Err(Cis2ClientError::InvalidReturnValue)
+        ));
+
+        assert_eq!(
+            decode_reject_reason(-42000002),
+            Cis2ErrorRejectReason::InsufficientFunds
+        );
+    }
+}
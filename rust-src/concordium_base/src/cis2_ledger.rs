@@ -0,0 +1,472 @@
+//! Reconstruction of the state of CIS-2 token contracts from the events they
+//! log.
+//!
+//! A [`Cis2Ledger`] is fed the logs of contract updates in the order they
+//! occurred on the chain, and maintains the token balances, operators and
+//! metadata URLs of each contract. Since the ledger is built from events only
+//! it reflects the state of a contract as long as the contract adheres to the
+//! CIS-2 standard, which requires that all changes to balances are logged.
+use crate::{
+    cis2_types::{Event, MetadataUrl, OperatorUpdate, TokenAmount, TokenId},
+    smart_contracts::concordium_contracts_common::{
+        from_bytes, to_bytes, Address, ContractAddress, Cursor, Deserial, ParseError, Read, Serial,
+        Write,
+    },
+};
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// The state of a single CIS-2 contract, as reconstructed from its events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractLedger {
+    /// Balances of each token. Only non-zero balances are recorded.
+    pub balances:  BTreeMap<TokenId, BTreeMap<Address, TokenAmount>>,
+    /// The operators of each owner.
+    pub operators: BTreeMap<Address, BTreeSet<Address>>,
+    /// The most recently logged metadata URL of each token.
+    pub metadata:  BTreeMap<TokenId, MetadataUrl>,
+}
+
+impl ContractLedger {
+    /// The balance of the given address in the given token.
+    pub fn balance_of(&self, token_id: &TokenId, address: &Address) -> TokenAmount {
+        self.balances
+            .get(token_id)
+            .and_then(|balances| balances.get(address))
+            .cloned()
+            .unwrap_or_else(|| TokenAmount::from(0u8))
+    }
+
+    /// The total amount of the given token held by all addresses.
+    pub fn total_supply(&self, token_id: &TokenId) -> TokenAmount {
+        self.balances
+            .get(token_id)
+            .into_iter()
+            .flat_map(|balances| balances.values())
+            .fold(TokenAmount::from(0u8), |acc, amount| acc + amount.clone())
+    }
+
+    /// Whether `operator` is an operator of `owner`.
+    pub fn is_operator_of(&self, owner: &Address, operator: &Address) -> bool {
+        self.operators
+            .get(owner)
+            .map_or(false, |operators| operators.contains(operator))
+    }
+
+    fn credit(&mut self, token_id: &TokenId, address: Address, amount: TokenAmount) {
+        if amount.is_zero() {
+            return;
+        }
+        let balances = self.balances.entry(token_id.clone()).or_default();
+        match balances.entry(address) {
+            Entry::Vacant(e) => {
+                e.insert(amount);
+            }
+            Entry::Occupied(mut e) => *e.get_mut() += amount,
+        }
+    }
+
+    /// Remove the amount from the balance of the address, or return the
+    /// balance if it is insufficient.
+    fn debit(
+        &mut self,
+        token_id: &TokenId,
+        address: Address,
+        amount: &TokenAmount,
+    ) -> Result<(), TokenAmount> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let balance = self.balance_of(token_id, &address);
+        if &balance < amount {
+            return Err(balance);
+        }
+        let remaining = balance - amount.clone();
+        // The balance is non-zero, so the maps exist.
+        let balances = self.balances.entry(token_id.clone()).or_default();
+        if remaining.is_zero() {
+            balances.remove(&address);
+            if balances.is_empty() {
+                self.balances.remove(token_id);
+            }
+        } else {
+            balances.insert(address, remaining);
+        }
+        Ok(())
+    }
+
+    /// Apply a single event. Events that are not part of CIS-2 are ignored.
+    fn apply(&mut self, event: Event) -> Result<(), InsufficientBalance> {
+        match event {
+            Event::Transfer {
+                token_id,
+                amount,
+                from,
+                to,
+            } => {
+                self.debit(&token_id, from, &amount)
+                    .map_err(|balance| InsufficientBalance {
+                        token_id: token_id.clone(),
+                        owner: from,
+                        amount: amount.clone(),
+                        balance,
+                    })?;
+                self.credit(&token_id, to, amount);
+            }
+            Event::Mint {
+                token_id,
+                amount,
+                owner,
+            } => self.credit(&token_id, owner, amount),
+            Event::Burn {
+                token_id,
+                amount,
+                owner,
+            } => self
+                .debit(&token_id, owner, &amount)
+                .map_err(|balance| InsufficientBalance {
+                    token_id,
+                    owner,
+                    amount,
+                    balance,
+                })?,
+            Event::UpdateOperator {
+                update,
+                owner,
+                operator,
+            } => match update {
+                OperatorUpdate::Add => {
+                    self.operators.entry(owner).or_default().insert(operator);
+                }
+                OperatorUpdate::Remove => {
+                    if let Entry::Occupied(mut e) = self.operators.entry(owner) {
+                        e.get_mut().remove(&operator);
+                        if e.get().is_empty() {
+                            e.remove();
+                        }
+                    }
+                }
+            },
+            Event::TokenMetadata {
+                token_id,
+                metadata_url,
+            } => {
+                self.metadata.insert(token_id, metadata_url);
+            }
+            Event::Unknown => (),
+        }
+        Ok(())
+    }
+}
+
+/// An event that removes more tokens from an address than it owns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsufficientBalance {
+    /// The token that is transferred or burned.
+    pub token_id: TokenId,
+    /// The address the tokens are removed from.
+    pub owner:    Address,
+    /// The amount that is removed.
+    pub amount:   TokenAmount,
+    /// The balance of the owner before the event.
+    pub balance:  TokenAmount,
+}
+
+/// An inconsistency in the logs of a contract. The index is the position of
+/// the offending log in the logs of the update.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Cis2LedgerError {
+    #[error("Log {index} of contract {contract} is not a well-formed CIS-2 event.")]
+    MalformedEvent {
+        contract: ContractAddress,
+        index:    usize,
+    },
+    #[error(
+        "Log {index} of contract {contract} removes {} of token {} from {:?}, which only owns {}.",
+        .error.amount, .error.token_id, .error.owner, .error.balance
+    )]
+    InsufficientBalance {
+        contract: ContractAddress,
+        index:    usize,
+        error:    InsufficientBalance,
+    },
+}
+
+/// Decode a single log of a contract as a CIS-2 event. Logs that are not
+/// CIS-2 events decode as [`Event::Unknown`]. This fails if the log is a
+/// CIS-2 event that is malformed or has trailing bytes.
+pub fn decode_event(log: &[u8]) -> Result<Event, ParseError> {
+    let mut cursor = Cursor::new(log);
+    let event = Event::deserial(&mut cursor)?;
+    match event {
+        Event::Unknown => Ok(event),
+        _ if cursor.offset == log.len() => Ok(event),
+        _ => Err(ParseError::default()),
+    }
+}
+
+/// Token holdings of any number of CIS-2 contracts, reconstructed from the
+/// logs of the contracts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cis2Ledger {
+    contracts: BTreeMap<ContractAddress, ContractLedger>,
+}
+
+impl Cis2Ledger {
+    /// Construct a ledger without any contracts.
+    pub fn new() -> Self { Self::default() }
+
+    /// The state of the given contract, if it has logged any events.
+    pub fn contract(&self, contract: &ContractAddress) -> Option<&ContractLedger> {
+        self.contracts.get(contract)
+    }
+
+    /// All the contracts that have logged events, and their states.
+    pub fn contracts(&self) -> &BTreeMap<ContractAddress, ContractLedger> { &self.contracts }
+
+    /// Process the logs produced by a contract in a single update, in the order
+    /// they were logged. If any of the logs is inconsistent with the state of
+    /// the contract the state is left unchanged.
+    pub fn process<L: AsRef<[u8]>>(
+        &mut self,
+        contract: ContractAddress,
+        logs: &[L],
+    ) -> Result<(), Cis2LedgerError> {
+        let mut ledger = self.contracts.get(&contract).cloned().unwrap_or_default();
+        for (index, log) in logs.iter().enumerate() {
+            let event = decode_event(log.as_ref())
+                .map_err(|_| Cis2LedgerError::MalformedEvent { contract, index })?;
+            ledger
+                .apply(event)
+                .map_err(|error| Cis2LedgerError::InsufficientBalance {
+                    contract,
+                    index,
+                    error,
+                })?;
+        }
+        if ledger != ContractLedger::default() || self.contracts.contains_key(&contract) {
+            self.contracts.insert(contract, ledger);
+        }
+        Ok(())
+    }
+
+    /// Process the logs of a sequence of contract updates in order. This stops
+    /// at the first inconsistency, leaving the effects of the preceding
+    /// updates in place.
+    pub fn process_all<L: AsRef<[u8]>>(
+        &mut self,
+        updates: impl IntoIterator<Item = (ContractAddress, Vec<L>)>,
+    ) -> Result<(), Cis2LedgerError> {
+        for (contract, logs) in updates {
+            self.process(contract, &logs)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the state of the ledger, so that it can later be restored
+    /// with [`restore`](Cis2Ledger::restore) and further logs processed.
+    pub fn snapshot(&self) -> Vec<u8> { to_bytes(self) }
+
+    /// Restore a ledger from a snapshot produced by
+    /// [`snapshot`](Cis2Ledger::snapshot).
+    pub fn restore(bytes: &[u8]) -> Result<Self, ParseError> { from_bytes(bytes) }
+}
+
+/// Serialize the entries of a map in increasing order of keys, preceded by
+/// the number of entries.
+fn serial_entries<K: Serial, V, W: Write>(
+    map: &BTreeMap<K, V>,
+    out: &mut W,
+    mut serial_value: impl FnMut(&V, &mut W) -> Result<(), W::Err>,
+) -> Result<(), W::Err> {
+    let len = u32::try_from(map.len()).map_err(|_| W::Err::default())?;
+    len.serial(out)?;
+    for (k, v) in map {
+        k.serial(out)?;
+        serial_value(v, out)?;
+    }
+    Ok(())
+}
+
+/// Inverse of [`serial_entries`]. This ensures that keys are in strictly
+/// increasing order so that each state has a unique serialization.
+fn deserial_entries<K: Deserial + Ord, V, R: Read>(
+    source: &mut R,
+    mut deserial_value: impl FnMut(&mut R) -> Result<V, ParseError>,
+) -> Result<BTreeMap<K, V>, ParseError> {
+    let len = u32::deserial(source)?;
+    let mut map = BTreeMap::new();
+    for _ in 0..len {
+        let k = K::deserial(source)?;
+        if map.keys().next_back().map_or(false, |last| last >= &k) {
+            return Err(ParseError::default());
+        }
+        let v = deserial_value(source)?;
+        map.insert(k, v);
+    }
+    Ok(map)
+}
+
+impl Serial for ContractLedger {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        serial_entries(&self.balances, out, |balances, out| {
+            serial_entries(balances, out, |amount, out| amount.serial(out))
+        })?;
+        serial_entries(&self.operators, out, |operators, out| {
+            let len = u32::try_from(operators.len()).map_err(|_| W::Err::default())?;
+            len.serial(out)?;
+            operators
+                .iter()
+                .try_for_each(|operator| operator.serial(out))
+        })?;
+        serial_entries(&self.metadata, out, |url, out| url.serial(out))
+    }
+}
+
+impl Deserial for ContractLedger {
+    fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
+        let balances = deserial_entries(source, |source| {
+            deserial_entries(source, |source| {
+                let amount = TokenAmount::deserial(source)?;
+                // Only non-zero balances are recorded.
+                if amount.is_zero() {
+                    return Err(ParseError::default());
+                }
+                Ok(amount)
+            })
+        })?;
+        let operators = deserial_entries(source, |source| {
+            let len = u32::deserial(source)?;
+            let mut operators = BTreeSet::new();
+            for _ in 0..len {
+                if !operators.insert(Address::deserial(source)?) {
+                    return Err(ParseError::default());
+                }
+            }
+            Ok(operators)
+        })?;
+        let metadata = deserial_entries(source, MetadataUrl::deserial)?;
+        Ok(Self {
+            balances,
+            operators,
+            metadata,
+        })
+    }
+}
+
+impl Serial for Cis2Ledger {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        serial_entries(&self.contracts, out, |ledger, out| ledger.serial(out))
+    }
+}
+
+impl Deserial for Cis2Ledger {
+    fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
+        let contracts = deserial_entries(source, ContractLedger::deserial)?;
+        Ok(Self { contracts })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::smart_contracts::concordium_contracts_common::AccountAddress;
+
+    fn transfer(token: u8, amount: u64, from: Address, to: Address) -> Vec<u8> {
+        let mut out = vec![255u8];
+        TokenId::new_unchecked(vec![token])
+            .serial(&mut out)
+            .unwrap();
+        TokenAmount::from(amount).serial(&mut out).unwrap();
+        from.serial(&mut out).unwrap();
+        to.serial(&mut out).unwrap();
+        out
+    }
+
+    fn mint_or_burn(tag: u8, token: u8, amount: u64, owner: Address) -> Vec<u8> {
+        let mut out = vec![tag];
+        TokenId::new_unchecked(vec![token])
+            .serial(&mut out)
+            .unwrap();
+        TokenAmount::from(amount).serial(&mut out).unwrap();
+        owner.serial(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_cis2_ledger() {
+        let contract = ContractAddress::new(7, 0);
+        let alice = Address::Account(AccountAddress([1u8; 32]));
+        let bob = Address::Account(AccountAddress([2u8; 32]));
+        let other = Address::Contract(ContractAddress::new(8, 0));
+        let token = TokenId::new_unchecked(vec![0]);
+
+        let mut operator_update = vec![252u8, 1];
+        alice.serial(&mut operator_update).unwrap();
+        other.serial(&mut operator_update).unwrap();
+        let mut metadata = vec![251u8];
+        token.serial(&mut metadata).unwrap();
+        MetadataUrl::new_unchecked("https://example.com/0".into(), None)
+            .serial(&mut metadata)
+            .unwrap();
+
+        let mut ledger = Cis2Ledger::new();
+        ledger
+            .process_all(vec![
+                (contract, vec![
+                    mint_or_burn(254, 0, 100, alice),
+                    metadata,
+                    // Logs that are not CIS-2 events are ignored.
+                    vec![0u8, 1, 2],
+                ]),
+                (contract, vec![transfer(0, 30, alice, bob), operator_update]),
+                (contract, vec![mint_or_burn(253, 0, 30, bob)]),
+            ])
+            .unwrap();
+        let state = ledger.contract(&contract).unwrap();
+        assert_eq!(state.balance_of(&token, &alice), TokenAmount::from(70u64));
+        assert_eq!(state.balance_of(&token, &bob), TokenAmount::from(0u64));
+        assert_eq!(
+            state.balances[&token].len(),
+            1,
+            "Zero balances are not recorded."
+        );
+        assert_eq!(state.total_supply(&token), TokenAmount::from(70u64));
+        assert!(state.is_operator_of(&alice, &other));
+        assert_eq!(state.metadata[&token].url(), "https://example.com/0");
+
+        let snapshot = ledger.snapshot();
+        let mut restored = Cis2Ledger::restore(&snapshot).unwrap();
+        assert_eq!(restored, ledger);
+
+        // Burning more than is owned is an inconsistency, and leaves the state
+        // unchanged.
+        let result = restored.process(contract, &[
+            mint_or_burn(254, 0, 10, bob),
+            mint_or_burn(253, 0, 71, alice),
+        ]);
+        assert_eq!(
+            result,
+            Err(Cis2LedgerError::InsufficientBalance {
+                contract,
+                index: 1,
+                error: InsufficientBalance {
+                    token_id: token.clone(),
+                    owner:    alice,
+                    amount:   TokenAmount::from(71u64),
+                    balance:  TokenAmount::from(70u64),
+                },
+            })
+        );
+        assert_eq!(restored, ledger);
+
+        // A CIS-2 event with trailing bytes is malformed.
+        let mut malformed = transfer(0, 1, alice, bob);
+        malformed.push(0);
+        assert_eq!(
+            restored.process(contract, &[malformed]),
+            Err(Cis2LedgerError::MalformedEvent { contract, index: 0 })
+        );
+        assert!(Cis2Ledger::restore(&snapshot[..snapshot.len() - 1]).is_err());
+    }
+}
@@ -817,6 +817,22 @@ impl MetadataUrl {
     pub fn hash(&self) -> Option<Hash> { self.hash }
 }
 
+/// Serialization for MetadataUrl according to the CIS2 specification.
+impl Serial for MetadataUrl {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        let len = u16::try_from(self.url.len()).map_err(|_| W::Err::default())?;
+        len.serial(out)?;
+        out.write_all(self.url.as_bytes())?;
+        match self.hash {
+            Some(hash) => {
+                out.write_u8(1)?;
+                out.write_all(hash.as_ref())
+            }
+            None => out.write_u8(0),
+        }
+    }
+}
+
 /// Deserialization for MetadataUrl according to the CIS2 specification.
 impl Deserial for MetadataUrl {
     fn deserial<R: Read>(source: &mut R) -> Result<Self, ParseError> {
@@ -12,6 +12,9 @@
 //! may simplify their dependencies. Users are intended to get the re-exported
 //! dependencies through the library, instead of separately.
 pub mod base;
+pub mod cis0_types;
+pub mod cis2_client;
+pub mod cis2_ledger;
 pub mod cis2_types;
 pub mod constants;
 pub mod hashes;
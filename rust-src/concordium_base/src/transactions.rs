@@ -20,12 +20,15 @@ use derive_more::*;
 use encrypted_transfers::types::{EncryptedAmountTransferData, SecToPubAmountTransferData};
 use id::types::{
     AccountAddress, AccountCredentialMessage, AccountKeys, CredentialDeploymentInfo,
-    CredentialPublicKeys,
+    CredentialPublicKeys, VerifyKey,
 };
 use rand::{CryptoRng, Rng};
 use random_oracle::RandomOracle;
 use sha2::Digest;
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+};
 use thiserror::Error;
 
 #[derive(SerdeSerialize, SerdeDeserialize, Serial, Debug, Clone, AsRef, Into)]
@@ -372,7 +375,7 @@ pub enum ConfigureBakerKeysMarker {}
 /// markers: `AddBakerKeysMarker` and `UpdateBakerKeysMarker`.
 pub struct BakerKeysPayload<V> {
     #[serde(skip)] // use default when deserializing
-    phantom:                    PhantomData<V>,
+    phantom: PhantomData<V>,
     /// New public key for participating in the election lottery.
     pub election_verify_key:    BakerElectionVerifyKey,
     /// New public key for verifying this baker's signatures.
@@ -1420,29 +1423,359 @@ pub fn verify_signature_transaction_sign_hash(
     hash: &hashes::TransactionSignHash,
     signature: &TransactionSignature,
 ) -> bool {
+    match signature_keys(keys, signature) {
+        Some(signatures) => signatures.iter().all(|(pk, sig)| pk.verify(hash, sig)),
+        None => false,
+    }
+}
+
+/// Pair each signature with the key it must be verified with, checking that
+/// the signatures are for known keys and meet the thresholds of the account.
+/// Returns [`None`] if that is not the case.
+fn signature_keys<'a>(
+    keys: &'a impl HasAccountAccessStructure,
+    signature: &'a TransactionSignature,
+) -> Option<Vec<(&'a VerifyKey, &'a Signature)>> {
     if usize::from(u8::from(keys.threshold())) > signature.signatures.len() {
-        return false;
+        return None;
     }
     // There are enough signatures.
+    let mut signatures = Vec::new();
     for (&ci, cred_sigs) in signature.signatures.iter() {
-        if let Some(cred_keys) = keys.credential_keys(ci) {
-            if usize::from(u8::from(cred_keys.threshold)) > cred_sigs.len() {
-                return false;
+        let cred_keys = keys.credential_keys(ci)?;
+        if usize::from(u8::from(cred_keys.threshold)) > cred_sigs.len() {
+            return None;
+        }
+        for (&ki, sig) in cred_sigs {
+            signatures.push((cred_keys.get(ki)?, sig));
+        }
+    }
+    Some(signatures)
+}
+
+/// Verify many signatures on transaction sign hashes at once. This is
+/// considerably faster than verifying the signatures one by one. If not all
+/// signatures are valid they are verified individually, and the indices of the
+/// invalid ones are returned in increasing order.
+pub fn verify_signatures_batch(
+    items: &[(&VerifyKey, &hashes::TransactionSignHash, &Signature)],
+) -> Result<(), Vec<usize>> {
+    let mut invalid = Vec::new();
+    // Indices in `items` of the signatures in the batch.
+    let mut indices = Vec::with_capacity(items.len());
+    let mut batch = Vec::with_capacity(items.len());
+    for (i, (key, hash, sig)) in items.iter().enumerate() {
+        let VerifyKey::Ed25519VerifyKey(pk) = key;
+        match ed25519_dalek::Signature::try_from(sig.as_ref()) {
+            Ok(sig) => {
+                let message: &[u8] = hash.as_ref();
+                indices.push(i);
+                batch.push((*pk, message, sig));
             }
-            for (&ki, sig) in cred_sigs {
-                if let Some(pk) = cred_keys.get(ki) {
-                    if !pk.verify(hash, sig) {
-                        return false;
+            Err(_) => invalid.push(i),
+        }
+    }
+    if let Err(batch_invalid) =
+        eddsa_ed25519::verify_batch_with_fallback(&mut rand::thread_rng(), &batch)
+    {
+        invalid.extend(batch_invalid.into_iter().map(|i| indices[i]));
+        invalid.sort_unstable();
+    }
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+/// Verify the signatures of many transactions at once, given the keys of the
+/// sender and the transaction sign hash of each transaction. The indices of the
+/// transactions whose signatures are not valid are returned in increasing
+/// order. These are (with overwhelming probability) exactly the transactions
+/// for which [`verify_signature_transaction_sign_hash`] returns `false`, since
+/// the batch check accepts no signature that individual verification rejects,
+/// and the signatures are verified individually if the batch check fails.
+pub fn verify_transaction_signatures_batch<K: HasAccountAccessStructure>(
+    transactions: &[(&K, &hashes::TransactionSignHash, &TransactionSignature)],
+) -> Result<(), Vec<usize>> {
+    let mut invalid = BTreeSet::new();
+    // The transaction each signature in `items` belongs to.
+    let mut owners = Vec::new();
+    let mut items = Vec::new();
+    for (i, (keys, hash, signature)) in transactions.iter().enumerate() {
+        match signature_keys(*keys, signature) {
+            Some(signatures) => {
+                for (pk, sig) in signatures {
+                    owners.push(i);
+                    items.push((pk, *hash, sig));
+                }
+            }
+            None => {
+                invalid.insert(i);
+            }
+        }
+    }
+    if let Err(invalid_signatures) = verify_signatures_batch(&items) {
+        invalid.extend(invalid_signatures.into_iter().map(|j| owners[j]));
+    }
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Clone, SerdeSerialize, SerdeDeserialize)]
+#[serde(rename_all = "camelCase")]
+/// An account transaction that is signed by several parties, each holding some
+/// of the keys of the sender account. Signatures are accumulated with
+/// [`add_signatures`](Self::add_signatures) and [`merge`](Self::merge), and
+/// once the thresholds of the account are met the transaction is turned into
+/// an [`AccountTransaction`] with [`finalize`](Self::finalize).
+///
+/// Note that the energy amount in the header depends on the number of
+/// signatures, so the header must be constructed with the number of signatures
+/// the final transaction is expected to have.
+pub struct PartiallySignedTransaction {
+    pub header:    TransactionHeader,
+    pub payload:   EncodedPayload,
+    /// Signatures collected so far. In contrast to the signature of an
+    /// [`AccountTransaction`] this may be empty.
+    pub signature: TransactionSignature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Signatures of a credential that are still missing.
+pub struct MissingKeys {
+    /// The number of additional valid signatures needed to reach the threshold
+    /// of the credential.
+    pub needed:   u8,
+    /// Keys of the credential that have not produced a valid signature.
+    pub unsigned: BTreeSet<KeyIndex>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The result of checking a [`PartiallySignedTransaction`] against an
+/// [`AccountAccessStructure`].
+pub struct MissingSignatures {
+    /// The number of credentials, in addition to the ones that already reached
+    /// their threshold, that must reach their threshold.
+    pub credentials: u8,
+    /// Credentials that have not reached their threshold.
+    pub incomplete:  BTreeMap<CredentialIndex, MissingKeys>,
+    /// Signatures that do not verify, or that are by keys that are not part of
+    /// the access structure. These are dropped when finalizing.
+    pub invalid:     BTreeSet<(CredentialIndex, KeyIndex)>,
+}
+
+impl MissingSignatures {
+    /// Whether enough credentials have reached their threshold for the
+    /// transaction to be finalized.
+    pub fn is_complete(&self) -> bool { self.credentials == 0 }
+}
+
+#[derive(Debug, Error)]
+/// Errors when collecting signatures on a [`PartiallySignedTransaction`].
+pub enum PartialSignatureError {
+    #[error("The partially signed transactions have different headers or payloads.")]
+    DifferentTransactions,
+    #[error("Conflicting signatures by key {key} of credential {credential}.")]
+    ConflictingSignatures {
+        credential: CredentialIndex,
+        key:        KeyIndex,
+    },
+    #[error("The transaction does not have enough valid signatures.")]
+    Incomplete(MissingSignatures),
+}
+
+impl PartiallySignedTransaction {
+    /// Construct a transaction with no signatures. The payload size in the
+    /// header must match the size of the payload.
+    pub fn new(header: TransactionHeader, payload: EncodedPayload) -> Self {
+        Self {
+            header,
+            payload,
+            signature: TransactionSignature {
+                signatures: BTreeMap::new(),
+            },
+        }
+    }
+
+    /// The hash that the signatures are on.
+    pub fn hash_to_sign(&self) -> hashes::TransactionSignHash {
+        compute_transaction_sign_hash(&self.header, &self.payload)
+    }
+
+    /// Sign the transaction with the given keys, and add the signatures to the
+    /// ones collected so far.
+    pub fn add_signatures(
+        &mut self,
+        signer: &impl TransactionSigner,
+    ) -> Result<(), PartialSignatureError> {
+        let signature = signer.sign_transaction_hash(&self.hash_to_sign());
+        self.insert_signatures(signature)
+    }
+
+    /// Add the signatures collected by another party on the same transaction.
+    pub fn merge(
+        &mut self,
+        other: &PartiallySignedTransaction,
+    ) -> Result<(), PartialSignatureError> {
+        if self.hash_to_sign().as_ref() != other.hash_to_sign().as_ref() {
+            return Err(PartialSignatureError::DifferentTransactions);
+        }
+        self.insert_signatures(other.signature.clone())
+    }
+
+    /// Insert the signatures. If a key already has a different signature
+    /// nothing is inserted.
+    fn insert_signatures(
+        &mut self,
+        signature: TransactionSignature,
+    ) -> Result<(), PartialSignatureError> {
+        for (ci, sigs) in signature.signatures.iter() {
+            if let Some(existing) = self.signature.signatures.get(ci) {
+                for (ki, sig) in sigs.iter() {
+                    if existing.get(ki).map_or(false, |e| e != sig) {
+                        return Err(PartialSignatureError::ConflictingSignatures {
+                            credential: *ci,
+                            key:        *ki,
+                        });
                     }
-                } else {
-                    return false;
                 }
             }
+        }
+        for (ci, sigs) in signature.signatures {
+            self.signature
+                .signatures
+                .entry(ci)
+                .or_default()
+                .extend(sigs);
+        }
+        Ok(())
+    }
+
+    /// Check which signatures are still needed for the transaction to be valid
+    /// for an account with the given access structure.
+    pub fn missing_signatures(&self, keys: &AccountAccessStructure) -> MissingSignatures {
+        let hash = self.hash_to_sign();
+        let mut complete = 0usize;
+        let mut incomplete = BTreeMap::new();
+        let mut invalid = BTreeSet::new();
+        for (&ci, cred_keys) in keys.keys.iter() {
+            let sigs = self.signature.signatures.get(&ci);
+            let mut signed = 0u8;
+            let mut unsigned = BTreeSet::new();
+            for (&ki, pk) in cred_keys.keys.iter() {
+                match sigs.and_then(|sigs| sigs.get(&ki)) {
+                    Some(sig) if pk.verify(&hash, sig) => signed += 1,
+                    Some(_) => {
+                        invalid.insert((ci, ki));
+                        unsigned.insert(ki);
+                    }
+                    None => {
+                        unsigned.insert(ki);
+                    }
+                }
+            }
+            let threshold = u8::from(cred_keys.threshold);
+            if signed >= threshold {
+                complete += 1;
+            } else {
+                incomplete.insert(ci, MissingKeys {
+                    needed: threshold - signed,
+                    unsigned,
+                });
+            }
+        }
+        for (&ci, sigs) in self.signature.signatures.iter() {
+            for &ki in sigs.keys() {
+                if keys.credential_keys(ci).and_then(|c| c.get(ki)).is_none() {
+                    invalid.insert((ci, ki));
+                }
+            }
+        }
+        // The result is at most the threshold, so it fits into a u8.
+        let credentials = usize::from(u8::from(keys.threshold)).saturating_sub(complete) as u8;
+        MissingSignatures {
+            credentials,
+            incomplete,
+            invalid,
+        }
+    }
+
+    /// Construct the signed transaction if the signatures meet the thresholds
+    /// of the given access structure. Invalid signatures, and signatures of
+    /// credentials that have not reached their threshold, are left out since
+    /// they would make the transaction invalid.
+    pub fn finalize(
+        self,
+        keys: &AccountAccessStructure,
+    ) -> Result<AccountTransaction<EncodedPayload>, PartialSignatureError> {
+        let missing = self.missing_signatures(keys);
+        if !missing.is_complete() {
+            return Err(PartialSignatureError::Incomplete(missing));
+        }
+        let hash = self.hash_to_sign();
+        let mut signatures = self.signature.signatures;
+        signatures.retain(|ci, sigs| {
+            if missing.incomplete.contains_key(ci) {
+                return false;
+            }
+            sigs.retain(|ki, _| !missing.invalid.contains(&(*ci, *ki)));
+            !sigs.is_empty()
+        });
+        let signature = TransactionSignature { signatures };
+        if verify_signature_transaction_sign_hash(keys, &hash, &signature) {
+            Ok(AccountTransaction {
+                signature,
+                header: self.header,
+                payload: self.payload,
+            })
         } else {
-            return false;
+            Err(PartialSignatureError::Incomplete(missing))
+        }
+    }
+}
+
+/// The signatures are serialized in the same way as [`TransactionSignature`],
+/// except that there may be none.
+impl Serial for PartiallySignedTransaction {
+    fn serial<B: Buffer>(&self, out: &mut B) {
+        out.put(&self.header);
+        out.put(&self.payload);
+        out.put(&self.signature);
+    }
+}
+
+impl Deserial for PartiallySignedTransaction {
+    fn deserial<R: ReadBytesExt>(source: &mut R) -> ParseResult<Self> {
+        let header: TransactionHeader = source.get()?;
+        let payload = get_encoded_payload(source, header.payload_size)?;
+        let num_creds: u8 = source.get()?;
+        let mut signatures = BTreeMap::new();
+        let mut last = None;
+        for _ in 0..num_creds {
+            let idx = source.get()?;
+            anyhow::ensure!(
+                last < Some(idx),
+                "Credential indices must be strictly increasing."
+            );
+            last = Some(idx);
+            let inner_len: u8 = source.get()?;
+            anyhow::ensure!(
+                inner_len > 0,
+                "Each credential must have at least one signature."
+            );
+            let inner_map = crypto_common::deserial_map_no_length(source, inner_len.into())?;
+            signatures.insert(idx, inner_map);
         }
+        Ok(Self {
+            header,
+            payload,
+            signature: TransactionSignature { signatures },
+        })
     }
-    true
 }
 
 #[derive(Debug, Clone)]
@@ -1653,6 +1986,7 @@ pub mod cost {
     use id::types::CredentialType;
 
     use super::*;
+    use crate::base::ProtocolVersion;
 
     /// The B constant for NRG assignment. This scales the effect of the number
     /// of signatures on the energy.
@@ -1709,6 +2043,13 @@ pub mod cost {
         UPDATE_CREDENTIALS_BASE + update_credentials_variable(num_credentials_before, num_keys)
     }
 
+    /// Additional cost of updating the keys of a credential, parametrized by
+    /// - the number of credentials on the account
+    /// - the number of keys of the credential after the update.
+    pub fn update_credential_keys(num_credentials_before: u16, num_keys: u16) -> Energy {
+        Energy::from(500 * u64::from(num_credentials_before) + 100 * u64::from(num_keys))
+    }
+
     /// Additional cost of registering a piece of data.
     pub const REGISTER_DATA: Energy = Energy { energy: 300 };
 
@@ -1758,6 +2099,147 @@ pub mod cost {
                 .sum::<u64>();
         Energy::from(energy)
     }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, SerdeSerialize)]
+    #[serde(tag = "kind", content = "energy", rename_all = "camelCase")]
+    /// An estimate of the energy cost of an account transaction.
+    pub enum CostEstimate {
+        /// The transaction costs exactly this amount of energy.
+        Exact(Energy),
+        /// The transaction costs at least this amount of energy. The actual
+        /// cost depends either on the state of the sender account, or on the
+        /// execution of a smart contract.
+        AtLeast(Energy),
+    }
+
+    impl CostEstimate {
+        /// The estimated energy, which is a lower bound if the cost is not
+        /// exact.
+        pub fn energy(self) -> Energy {
+            match self {
+                CostEstimate::Exact(energy) => energy,
+                CostEstimate::AtLeast(energy) => energy,
+            }
+        }
+
+        /// Whether the estimate is the exact cost of the transaction.
+        pub fn is_exact(self) -> bool { matches!(self, CostEstimate::Exact(_)) }
+    }
+
+    #[derive(Debug, Error)]
+    #[error(
+        "Transactions of type {transaction_type} are not supported in protocol version \
+         {protocol_version}."
+    )]
+    /// The payload is not valid in the protocol version, so a transaction with
+    /// it cannot be included in a block.
+    pub struct UnsupportedTransactionType {
+        pub transaction_type: TransactionType,
+        pub protocol_version: ProtocolVersion,
+    }
+
+    /// Whether transactions of the given type are supported in the protocol
+    /// version. Transfers with memos are supported from protocol version 2,
+    /// and in protocol version 4 the baker transactions of earlier versions are
+    /// replaced by [`ConfigureBaker`](TransactionType::ConfigureBaker) and
+    /// [`ConfigureDelegation`](TransactionType::ConfigureDelegation).
+    pub fn is_supported_in(
+        transaction_type: TransactionType,
+        protocol_version: ProtocolVersion,
+    ) -> bool {
+        use TransactionType::*;
+        match transaction_type {
+            TransferWithMemo | EncryptedAmountTransferWithMemo | TransferWithScheduleAndMemo => {
+                protocol_version >= ProtocolVersion::P2
+            }
+            AddBaker
+            | RemoveBaker
+            | UpdateBakerStake
+            | UpdateBakerRestakeEarnings
+            | UpdateBakerKeys => protocol_version <= ProtocolVersion::P3,
+            ConfigureBaker | ConfigureDelegation => protocol_version >= ProtocolVersion::P4,
+            _ => true,
+        }
+    }
+
+    /// Estimate the total energy cost of an account transaction with the given
+    /// payload and number of signatures in the given protocol version. This
+    /// includes the [base cost](base_cost) of the transaction.
+    ///
+    /// The estimate is a lower bound for
+    /// - contract initializations and updates, where the cost of executing the
+    ///   contract is not included, and
+    /// - [`UpdateCredentialKeys`](Payload::UpdateCredentialKeys) and
+    ///   [`UpdateCredentials`](Payload::UpdateCredentials), where the sender
+    ///   account is assumed to have a single credential before the update. Use
+    ///   [`update_credential_keys`] and [`update_credentials`] to compute the
+    ///   exact cost for an account with more credentials.
+    ///
+    /// For all other payloads the estimate is exact.
+    pub fn estimate_cost(
+        payload: &Payload,
+        num_signatures: u32,
+        protocol_version: ProtocolVersion,
+    ) -> Result<CostEstimate, UnsupportedTransactionType> {
+        let transaction_type = payload.transaction_type();
+        if !is_supported_in(transaction_type, protocol_version) {
+            return Err(UnsupportedTransactionType {
+                transaction_type,
+                protocol_version,
+            });
+        }
+        let size =
+            construct::TRANSACTION_HEADER_SIZE + u64::from(u32::from(payload.encode().size()));
+        let base = base_cost(size, num_signatures);
+        let estimate = match payload {
+            Payload::DeployModule { module } => {
+                CostEstimate::Exact(base + deploy_module(module.source.size()))
+            }
+            Payload::InitContract { .. } | Payload::Update { .. } => CostEstimate::AtLeast(base),
+            Payload::Transfer { .. } | Payload::TransferWithMemo { .. } => {
+                CostEstimate::Exact(base + SIMPLE_TRANSFER)
+            }
+            Payload::AddBaker { .. } => CostEstimate::Exact(base + ADD_BAKER),
+            Payload::RemoveBaker => CostEstimate::Exact(base + REMOVE_BAKER),
+            Payload::UpdateBakerStake { .. } => CostEstimate::Exact(base + UPDATE_BAKER_STAKE),
+            Payload::UpdateBakerRestakeEarnings { .. } => {
+                CostEstimate::Exact(base + UPDATE_BAKER_RESTAKE)
+            }
+            Payload::UpdateBakerKeys { .. } => CostEstimate::Exact(base + UPDATE_BAKER_KEYS),
+            Payload::UpdateCredentialKeys { keys, .. } => {
+                CostEstimate::AtLeast(base + update_credential_keys(1, keys.keys.len() as u16))
+            }
+            Payload::EncryptedAmountTransfer { .. }
+            | Payload::EncryptedAmountTransferWithMemo { .. } => {
+                CostEstimate::Exact(base + ENCRYPTED_TRANSFER)
+            }
+            Payload::TransferToEncrypted { .. } => {
+                CostEstimate::Exact(base + TRANSFER_TO_ENCRYPTED)
+            }
+            Payload::TransferToPublic { .. } => CostEstimate::Exact(base + TRANSFER_TO_PUBLIC),
+            Payload::TransferWithSchedule { schedule, .. }
+            | Payload::TransferWithScheduleAndMemo { schedule, .. } => {
+                CostEstimate::Exact(base + scheduled_transfer(schedule.len() as u16))
+            }
+            Payload::UpdateCredentials { new_cred_infos, .. } => {
+                let num_keys = new_cred_infos
+                    .values()
+                    .map(|cdi| cdi.values.cred_key_info.keys.len() as u16)
+                    .collect::<Vec<_>>();
+                CostEstimate::AtLeast(base + update_credentials(1, &num_keys))
+            }
+            Payload::RegisterData { .. } => CostEstimate::Exact(base + REGISTER_DATA),
+            Payload::ConfigureBaker { data } => {
+                if data.keys_with_proofs.is_some() {
+                    CostEstimate::Exact(base + CONFIGURE_BAKER_WITH_KEYS)
+                } else {
+                    CostEstimate::Exact(base + CONFIGURE_BAKER_WITHOUT_KEYS)
+                }
+            }
+            Payload::ConfigureDelegation { .. } => CostEstimate::Exact(base + CONFIGURE_DELEGATION),
+        };
+        Ok(estimate)
+    }
 }
 
 /// High level wrappers for making transactions with minimal user input.
@@ -1766,6 +2248,7 @@ pub mod cost {
 /// See also the [send] module above which combines construction with signing.
 pub mod construct {
     use super::*;
+    use crate::base::ProtocolVersion;
 
     /// A transaction that is prepared to be signed.
     /// The serde instance serializes the structured payload and skips
@@ -1795,6 +2278,13 @@ pub mod construct {
         }
     }
 
+    /// Start collecting signatures from several parties.
+    impl From<PreAccountTransaction> for PartiallySignedTransaction {
+        fn from(pre: PreAccountTransaction) -> Self {
+            PartiallySignedTransaction::new(pre.header, pre.encoded)
+        }
+    }
+
     /// Serialize only the header and payload, so that this can be deserialized
     /// as a transaction body.
     impl Serial for PreAccountTransaction {
@@ -2432,6 +2922,219 @@ pub mod construct {
         };
         builder.construct(cost)
     }
+
+    /// A builder for a batch of account transactions from a single sender. The
+    /// transactions are assigned consecutive nonces in the order their
+    /// payloads are added, starting at the given nonce.
+    ///
+    /// The energy of a transaction is its exact cost, computed by
+    /// [`cost::estimate_cost`], unless energy is given explicitly when adding
+    /// the payload. Explicit energy is required for payloads where only a lower
+    /// bound on the cost is known, such as contract initializations and
+    /// updates.
+    pub struct BatchBuilder {
+        sender:           AccountAddress,
+        start_nonce:      Nonce,
+        expiry:           TransactionTime,
+        protocol_version: ProtocolVersion,
+        parallel:         bool,
+        payloads:         Vec<(Payload, Option<Energy>)>,
+    }
+
+    #[derive(Debug, Error)]
+    /// An error that prevents a batch from being constructed. The index is the
+    /// position of the offending payload in the batch.
+    pub enum BatchError {
+        #[error("Payload {index} cannot be sent: {error}")]
+        Unsupported {
+            index: usize,
+            error: cost::UnsupportedTransactionType,
+        },
+        #[error(
+            "The cost of payload {index} of type {transaction_type} is not known exactly, so \
+             energy must be given explicitly."
+        )]
+        EnergyRequired {
+            index:            usize,
+            transaction_type: TransactionType,
+        },
+    }
+
+    impl BatchBuilder {
+        /// Start a batch of transactions that will all have the given expiry.
+        /// The first transaction gets nonce `start_nonce`.
+        pub fn new(
+            sender: AccountAddress,
+            start_nonce: Nonce,
+            expiry: TransactionTime,
+            protocol_version: ProtocolVersion,
+        ) -> Self {
+            Self {
+                sender,
+                start_nonce,
+                expiry,
+                protocol_version,
+                parallel: false,
+                payloads: Vec::new(),
+            }
+        }
+
+        /// Set whether transactions are signed in parallel. The default is to
+        /// sign them sequentially.
+        pub fn parallel(mut self, parallel: bool) -> Self {
+            self.parallel = parallel;
+            self
+        }
+
+        /// Add a payload whose cost is computed from the payload.
+        pub fn add(mut self, payload: Payload) -> Self {
+            self.payloads.push((payload, None));
+            self
+        }
+
+        /// Add a payload with the given amount of energy for its execution.
+        /// The base cost that covers transaction size and signature checking
+        /// is added to this energy.
+        pub fn add_with_energy(mut self, payload: Payload, energy: Energy) -> Self {
+            self.payloads.push((payload, Some(energy)));
+            self
+        }
+
+        /// The number of payloads added so far.
+        pub fn len(&self) -> usize { self.payloads.len() }
+
+        /// Whether no payloads have been added.
+        pub fn is_empty(&self) -> bool { self.payloads.is_empty() }
+
+        /// Construct and sign all the transactions of the batch.
+        pub fn sign(
+            self,
+            signer: &(impl ExactSizeTransactionSigner + Sync),
+        ) -> Result<TransactionBatch, BatchError> {
+            let num_sigs = signer.num_keys();
+            let mut nonce = self.start_nonce;
+            let mut prepared = Vec::with_capacity(self.payloads.len());
+            for (index, (payload, energy)) in self.payloads.into_iter().enumerate() {
+                let energy = match energy {
+                    Some(energy) => GivenEnergy::Add { energy, num_sigs },
+                    None => {
+                        let estimate =
+                            cost::estimate_cost(&payload, num_sigs, self.protocol_version)
+                                .map_err(|error| BatchError::Unsupported { index, error })?;
+                        if !estimate.is_exact() {
+                            return Err(BatchError::EnergyRequired {
+                                index,
+                                transaction_type: payload.transaction_type(),
+                            });
+                        }
+                        GivenEnergy::Absolute(estimate.energy())
+                    }
+                };
+                prepared.push(make_transaction(
+                    self.sender,
+                    nonce,
+                    self.expiry,
+                    energy,
+                    payload,
+                ));
+                nonce.next_mut();
+            }
+            let sign = |pre: PreAccountTransaction| BatchItem::new(pre.sign(signer));
+            let items: Vec<BatchItem> = if self.parallel {
+                use rayon::prelude::*;
+                prepared.into_par_iter().map(sign).collect()
+            } else {
+                prepared.into_iter().map(sign).collect()
+            };
+            Ok(TransactionBatch { items })
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    /// A signed transaction of a [`TransactionBatch`].
+    pub struct BatchItem {
+        /// The nonce of the transaction.
+        pub nonce: Nonce,
+        /// The hash of the block item, which identifies it on the chain.
+        pub hash:  hashes::TransactionHash,
+        /// The transaction, ready to be sent.
+        pub item:  BlockItem<EncodedPayload>,
+    }
+
+    impl BatchItem {
+        fn new(transaction: AccountTransaction<EncodedPayload>) -> Self {
+            let nonce = transaction.header.nonce;
+            let item = BlockItem::from(transaction);
+            Self {
+                nonce,
+                hash: item.hash(),
+                item,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    /// A batch of signed transactions from a single sender with consecutive
+    /// nonces.
+    ///
+    /// A batch can be serialized so that sending it can be resumed after an
+    /// interruption. Transactions whose nonce is below the next nonce of the
+    /// sender account have already been accepted by the chain, and
+    /// [`pending`](TransactionBatch::pending) returns the ones that have not.
+    pub struct TransactionBatch {
+        items: Vec<BatchItem>,
+    }
+
+    impl TransactionBatch {
+        /// All the transactions of the batch in order of increasing nonces.
+        pub fn items(&self) -> &[BatchItem] { &self.items }
+
+        /// The transactions that remain to be sent if the next nonce of the
+        /// sender account is `next_nonce`.
+        pub fn pending(&self, next_nonce: Nonce) -> &[BatchItem] {
+            let start = self.items.partition_point(|item| item.nonce < next_nonce);
+            &self.items[start..]
+        }
+    }
+
+    impl Serial for TransactionBatch {
+        fn serial<B: Buffer>(&self, out: &mut B) {
+            out.put(&(self.items.len() as u32));
+            for item in self.items.iter() {
+                out.put(&item.item);
+            }
+        }
+    }
+
+    /// Deserialization checks that the batch consists of account transactions
+    /// from a single sender with consecutive nonces.
+    impl Deserial for TransactionBatch {
+        fn deserial<R: ReadBytesExt>(source: &mut R) -> ParseResult<Self> {
+            let len: u32 = source.get()?;
+            let mut items: Vec<BatchItem> = Vec::new();
+            for _ in 0..len {
+                let item: BlockItem<EncodedPayload> = source.get()?;
+                let transaction = match item {
+                    BlockItem::AccountTransaction(at) => at,
+                    _ => anyhow::bail!("A batch may only contain account transactions."),
+                };
+                if let Some(last) = items.last() {
+                    anyhow::ensure!(
+                        transaction.header.nonce == last.nonce.next(),
+                        "Nonces in a batch must be consecutive."
+                    );
+                    if let BlockItem::AccountTransaction(at) = &last.item {
+                        anyhow::ensure!(
+                            transaction.header.sender == at.header.sender,
+                            "All transactions in a batch must have the same sender."
+                        );
+                    }
+                }
+                items.push(BatchItem::new(transaction));
+            }
+            Ok(TransactionBatch { items })
+        }
+    }
 }
 
 /// High level wrappers for making transactions with minimal user input.
@@ -2835,6 +3538,269 @@ pub mod send {
     }
 }
 
+/// Decoding of serialized block items into a structured, human-readable form.
+/// The main entrypoint is [`explain_block_item`](explain::explain_block_item),
+/// whose result is intended to be rendered as JSON.
+pub mod explain {
+    use super::*;
+    use crate::base::UpdateKeysIndex;
+    use concordium_contracts_common::schema;
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(rename_all = "camelCase")]
+    /// Explanation of a block item.
+    pub struct BlockItemExplanation {
+        /// Hash of the block item that identifies it on the chain.
+        pub transaction_hash: hashes::TransactionHash,
+        /// Size of the serialized block item in bytes, including the tag.
+        pub size:             u64,
+        #[serde(flatten)]
+        pub details:          BlockItemDetails,
+    }
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    /// Details that are specific to the kind of block item.
+    pub enum BlockItemDetails {
+        AccountTransaction(Box<AccountTransactionExplanation>),
+        CredentialDeployment {
+            #[serde(flatten)]
+            message: Box<
+                AccountCredentialMessage<
+                    id::constants::IpPairing,
+                    id::constants::ArCurve,
+                    id::constants::AttributeKind,
+                >,
+            >,
+        },
+        UpdateInstruction(Box<UpdateInstructionExplanation>),
+    }
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(rename_all = "camelCase")]
+    /// Explanation of an account transaction.
+    pub struct AccountTransactionExplanation {
+        /// Sender account of the transaction.
+        pub sender:        AccountAddress,
+        /// Sequence number of the transaction.
+        pub nonce:         Nonce,
+        /// Maximum amount of energy the transaction can take to execute.
+        pub energy_amount: Energy,
+        /// Latest time the transaction can be included in a block.
+        pub expiry:        TransactionTime,
+        /// The hash that the signatures are on.
+        pub sign_hash:     hashes::TransactionSignHash,
+        /// Indices of the keys that signed, for each credential that signed.
+        pub signatures:    BTreeMap<CredentialIndex, BTreeSet<KeyIndex>>,
+        /// The payload of the transaction.
+        pub payload:       PayloadExplanation,
+        /// Breakdown of the size of the transaction and the cost that follows
+        /// from it.
+        pub cost:          CostBreakdown,
+    }
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(rename_all = "camelCase")]
+    /// Explanation of the payload of an account transaction. The payload is
+    /// always present in its encoded form. If it could be decoded then exactly
+    /// one of `decoded` and `decode_error` is present.
+    pub struct PayloadExplanation {
+        /// The payload as it appears in the transaction.
+        pub encoded:          EncodedPayload,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        /// The type of the transaction, if the payload could be decoded.
+        pub transaction_type: Option<TransactionType>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        /// The decoded payload.
+        pub decoded:          Option<Payload>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        /// The reason the payload could not be decoded.
+        pub decode_error:     Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        /// The parameter of a contract initialization or update, decoded
+        /// according to the supplied schema type.
+        pub parameter:        Option<serde_json::Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        /// The reason the parameter could not be decoded according to the
+        /// supplied schema type.
+        pub parameter_error:  Option<String>,
+    }
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(rename_all = "camelCase")]
+    /// The sizes of the parts of an account transaction and the base cost
+    /// they induce. The base cost is charged in addition to the cost of
+    /// executing the payload.
+    pub struct CostBreakdown {
+        /// Size of the serialized signatures in bytes. This is not charged for.
+        pub signatures_size: u64,
+        /// Size of the transaction header in bytes.
+        pub header_size:     u64,
+        /// Size of the payload in bytes.
+        pub payload_size:    u64,
+        /// The number of signatures on the transaction.
+        pub num_signatures:  u32,
+        /// Cost for the size of the header and payload.
+        pub size_cost:       Energy,
+        /// Cost for checking the signatures.
+        pub signature_cost:  Energy,
+        /// The total base cost, the sum of the size and signature cost.
+        pub base_cost:       Energy,
+    }
+
+    #[derive(Debug, SerdeSerialize)]
+    #[serde(rename_all = "camelCase")]
+    /// Explanation of an update instruction.
+    pub struct UpdateInstructionExplanation {
+        /// The header of the update.
+        pub header:      updates::UpdateHeader,
+        /// The type of the update. This determines which keys may sign it.
+        pub update_type: updates::UpdateType,
+        /// The hash that the signatures are on.
+        pub sign_hash:   hashes::UpdateSignHash,
+        /// Indices of the update keys that signed.
+        pub signatures:  BTreeSet<UpdateKeysIndex>,
+        /// The payload of the update.
+        pub payload:     updates::UpdatePayload,
+    }
+
+    /// Parse a serialized block item and explain it. The entire input must be
+    /// used. See [`explain_block_item`] for the meaning of `parameter_type`.
+    pub fn explain_serialized_block_item(
+        bytes: &[u8],
+        parameter_type: Option<&schema::Type>,
+    ) -> ParseResult<BlockItemExplanation> {
+        let mut source = std::io::Cursor::new(bytes);
+        let item: BlockItem<EncodedPayload> = source.get()?;
+        let remaining = bytes.len() as u64 - source.position();
+        anyhow::ensure!(
+            remaining == 0,
+            "Block item has {} bytes of trailing input.",
+            remaining
+        );
+        Ok(explain_block_item(item, parameter_type))
+    }
+
+    /// Explain the block item. If a `parameter_type` is supplied, the parameter
+    /// of a contract initialization or update is decoded according to it.
+    ///
+    /// Failure to decode the payload of an account transaction, or its
+    /// parameter, is recorded in the explanation, since such transactions can
+    /// still appear on the chain.
+    pub fn explain_block_item(
+        item: BlockItem<EncodedPayload>,
+        parameter_type: Option<&schema::Type>,
+    ) -> BlockItemExplanation {
+        let transaction_hash = item.hash();
+        let size = crypto_common::to_bytes(&item).len() as u64;
+        let details = match item {
+            BlockItem::AccountTransaction(at) => BlockItemDetails::AccountTransaction(Box::new(
+                explain_account_transaction(at, parameter_type),
+            )),
+            BlockItem::CredentialDeployment(message) => {
+                BlockItemDetails::CredentialDeployment { message }
+            }
+            BlockItem::UpdateInstruction(ui) => {
+                let update = updates::update::PartiallySignedUpdate::from(ui);
+                BlockItemDetails::UpdateInstruction(Box::new(UpdateInstructionExplanation {
+                    sign_hash:   update.hash_to_sign(),
+                    update_type: update.payload.update_type(),
+                    signatures:  update.signatures.into_keys().collect(),
+                    header:      update.header,
+                    payload:     update.payload,
+                }))
+            }
+        };
+        BlockItemExplanation {
+            transaction_hash,
+            size,
+            details,
+        }
+    }
+
+    fn explain_account_transaction(
+        at: AccountTransaction<EncodedPayload>,
+        parameter_type: Option<&schema::Type>,
+    ) -> AccountTransactionExplanation {
+        let num_signatures = at.signature.num_signatures();
+        let payload_size = u64::from(u32::from(at.header.payload_size));
+        let size_cost = cost::base_cost(construct::TRANSACTION_HEADER_SIZE + payload_size, 0);
+        let signature_cost = cost::base_cost(0, num_signatures);
+        let cost = CostBreakdown {
+            signatures_size: crypto_common::to_bytes(&at.signature).len() as u64,
+            header_size: construct::TRANSACTION_HEADER_SIZE,
+            payload_size,
+            num_signatures,
+            size_cost,
+            signature_cost,
+            base_cost: size_cost + signature_cost,
+        };
+        AccountTransactionExplanation {
+            sender: at.header.sender,
+            nonce: at.header.nonce,
+            energy_amount: at.header.energy_amount,
+            expiry: at.header.expiry,
+            sign_hash: compute_transaction_sign_hash(&at.header, &at.payload),
+            signatures: at
+                .signature
+                .signatures
+                .into_iter()
+                .map(|(ci, sigs)| (ci, sigs.into_keys().collect()))
+                .collect(),
+            payload: explain_payload(at.payload, parameter_type),
+            cost,
+        }
+    }
+
+    fn explain_payload(
+        encoded: EncodedPayload,
+        parameter_type: Option<&schema::Type>,
+    ) -> PayloadExplanation {
+        let mut explanation = PayloadExplanation {
+            encoded,
+            transaction_type: None,
+            decoded: None,
+            decode_error: None,
+            parameter: None,
+            parameter_error: None,
+        };
+        match explanation.encoded.decode() {
+            Ok(payload) => {
+                let parameter = match &payload {
+                    Payload::InitContract { payload } => Some(&payload.param),
+                    Payload::Update { payload } => Some(&payload.message),
+                    _ => None,
+                };
+                if let (Some(parameter), Some(ty)) = (parameter, parameter_type) {
+                    let bytes: &Vec<u8> = parameter.as_ref();
+                    match decode_parameter(bytes, ty) {
+                        Ok(value) => explanation.parameter = Some(value),
+                        Err(e) => explanation.parameter_error = Some(e.into()),
+                    }
+                }
+                explanation.transaction_type = Some(payload.transaction_type());
+                explanation.decoded = Some(payload);
+            }
+            Err(e) => explanation.decode_error = Some(e.to_string()),
+        }
+        explanation
+    }
+
+    /// Decode the parameter according to the schema type, requiring that all
+    /// of the parameter is used.
+    fn decode_parameter(
+        parameter: &[u8],
+        ty: &schema::Type,
+    ) -> Result<serde_json::Value, &'static str> {
+        let mut cursor = concordium_contracts_common::Cursor::new(parameter);
+        match ty.to_json(&mut cursor) {
+            Ok(value) if cursor.offset == parameter.len() => Ok(value),
+            Ok(_) => Err("Parameter has trailing bytes."),
+            Err(_) => Err("Parameter does not match the schema type."),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::hashes::TransactionSignHash;
@@ -2891,4 +3857,501 @@ mod tests {
             "Transaction signature must not validate with invalid threshold."
         );
     }
+
+    #[test]
+    fn test_transaction_signatures_batch() {
+        let mut rng = rand::thread_rng();
+        let accounts = (0..10)
+            .map(|_| {
+                let cred_keys = (0..2u8)
+                    .map(|ki| (KeyIndex(ki), KeyPair::generate(&mut rng)))
+                    .collect::<BTreeMap<_, _>>();
+                let keys = [(CredentialIndex::from(0), cred_keys)]
+                    .into_iter()
+                    .collect::<BTreeMap<_, _>>();
+                let access_structure = AccountAccessStructure {
+                    threshold: AccountThreshold::try_from(1).unwrap(),
+                    keys:      keys
+                        .iter()
+                        .map(|(&ci, keys)| {
+                            let keys = keys
+                                .iter()
+                                .map(|(&ki, kp)| (ki, VerifyKey::from(kp)))
+                                .collect();
+                            (ci, CredentialPublicKeys {
+                                keys,
+                                threshold: SignatureThreshold(2),
+                            })
+                        })
+                        .collect(),
+                };
+                (keys, access_structure)
+            })
+            .collect::<Vec<_>>();
+        let hashes = (0..10)
+            .map(|_| TransactionSignHash::new(rng.gen()))
+            .collect::<Vec<_>>();
+        let mut signatures = accounts
+            .iter()
+            .zip(&hashes)
+            .map(|((keys, _), hash)| keys.sign_transaction_hash(hash))
+            .collect::<Vec<_>>();
+        let transactions = accounts
+            .iter()
+            .zip(&hashes)
+            .zip(&signatures)
+            .map(|(((_, access_structure), hash), sig)| (access_structure, hash, sig))
+            .collect::<Vec<_>>();
+        assert_eq!(verify_transaction_signatures_batch(&transactions), Ok(()));
+
+        // Sign the wrong hash.
+        signatures[2] = accounts[2].0.sign_transaction_hash(&hashes[3]);
+        // Leave out a signature, so the threshold of the credential is not met.
+        signatures[7]
+            .signatures
+            .get_mut(&CredentialIndex::from(0))
+            .unwrap()
+            .remove(&KeyIndex(1));
+        let transactions = accounts
+            .iter()
+            .zip(&hashes)
+            .zip(&signatures)
+            .map(|(((_, access_structure), hash), sig)| (access_structure, hash, sig))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_transaction_signatures_batch(&transactions),
+            Err(vec![2, 7])
+        );
+        for (i, (keys, hash, sig)) in transactions.iter().enumerate() {
+            assert_eq!(
+                verify_signature_transaction_sign_hash(*keys, hash, sig),
+                i != 2 && i != 7,
+                "Batch verification agrees with individual verification."
+            );
+        }
+    }
+
+    #[test]
+    fn test_partially_signed_transaction() {
+        let mut rng = rand::thread_rng();
+        // Three credentials with three keys each. Each credential needs two
+        // signatures, and two credentials need to sign.
+        let keys = (0..3u8)
+            .map(|ci| {
+                let cred_keys = (0..3u8)
+                    .map(|ki| (KeyIndex(ki), KeyPair::generate(&mut rng)))
+                    .collect::<BTreeMap<_, _>>();
+                (CredentialIndex::from(ci), cred_keys)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let access_structure = AccountAccessStructure {
+            threshold: AccountThreshold::try_from(2u8).unwrap(),
+            keys:      keys
+                .iter()
+                .map(|(&ci, keys)| {
+                    let keys = keys
+                        .iter()
+                        .map(|(&ki, kp)| (ki, VerifyKey::from(kp)))
+                        .collect();
+                    (ci, CredentialPublicKeys {
+                        keys,
+                        threshold: SignatureThreshold(2),
+                    })
+                })
+                .collect(),
+        };
+        // Hand the given keys of the given credential to a signing party.
+        let mut keys = keys;
+        let mut subset = |ci: u8, kis: &[u8]| {
+            let ci = CredentialIndex::from(ci);
+            let cred = keys.get_mut(&ci).unwrap();
+            let cred_keys = kis
+                .iter()
+                .map(|&ki| (KeyIndex(ki), cred.remove(&KeyIndex(ki)).unwrap()))
+                .collect::<BTreeMap<_, _>>();
+            let mut signer = BTreeMap::new();
+            signer.insert(ci, cred_keys);
+            signer
+        };
+
+        let payload = Payload::Transfer {
+            to_address: AccountAddress([1u8; 32]),
+            amount:     Amount::from_micro_ccd(17),
+        }
+        .encode();
+        let header = TransactionHeader {
+            sender:        AccountAddress([0u8; 32]),
+            nonce:         Nonce::from(1),
+            energy_amount: Energy::from(1000),
+            payload_size:  payload.size(),
+            expiry:        TransactionTime::from_seconds(123),
+        };
+        let mut first = PartiallySignedTransaction::new(header.clone(), payload.clone());
+        first.add_signatures(&subset(0, &[0, 1])).unwrap();
+        let mut second = PartiallySignedTransaction::new(header.clone(), payload.clone());
+        second.add_signatures(&subset(1, &[0])).unwrap();
+        first.merge(&second).unwrap();
+
+        let missing = first.missing_signatures(&access_structure);
+        assert_eq!(missing.credentials, 1, "One more credential must sign.");
+        assert_eq!(missing.incomplete.len(), 2);
+        assert_eq!(missing.incomplete[&CredentialIndex::from(1)].needed, 1);
+        assert_eq!(
+            missing.incomplete[&CredentialIndex::from(2)].unsigned.len(),
+            3,
+            "No key of the third credential has signed."
+        );
+        assert!(missing.invalid.is_empty());
+        assert!(matches!(
+            first.clone().finalize(&access_structure),
+            Err(PartialSignatureError::Incomplete(_))
+        ));
+
+        // Signatures of the third credential that are not enough for it are left
+        // out of the final transaction.
+        second.add_signatures(&subset(1, &[2])).unwrap();
+        second.add_signatures(&subset(2, &[1])).unwrap();
+        first.merge(&second).unwrap();
+        let bytes = crypto_common::to_bytes(&first);
+        let deserialized: PartiallySignedTransaction =
+            crypto_common::from_bytes(&mut std::io::Cursor::new(&bytes)).unwrap();
+        assert_eq!(deserialized.signature, first.signature);
+        assert!(deserialized
+            .missing_signatures(&access_structure)
+            .is_complete());
+        let transaction = deserialized.finalize(&access_structure).unwrap();
+        assert_eq!(transaction.signature.num_signatures(), 4);
+        assert!(transaction.verify_transaction_signature(&access_structure));
+
+        let mut other_header = header;
+        other_header.nonce = Nonce::from(2);
+        let other = PartiallySignedTransaction::new(other_header, payload);
+        assert!(matches!(
+            first.merge(&other),
+            Err(PartialSignatureError::DifferentTransactions)
+        ));
+    }
+
+    #[test]
+    fn test_explain_block_item() {
+        use concordium_contracts_common::schema;
+        let mut rng = rand::thread_rng();
+        let mut cred_keys = BTreeMap::new();
+        cred_keys.insert(KeyIndex(0), KeyPair::generate(&mut rng));
+        cred_keys.insert(KeyIndex(2), KeyPair::generate(&mut rng));
+        let mut keys = BTreeMap::new();
+        keys.insert(CredentialIndex::from(1), cred_keys);
+
+        let payload = Payload::Update {
+            payload: UpdateContractPayload {
+                amount:       Amount::from_micro_ccd(0),
+                address:      ContractAddress::new(3, 0),
+                receive_name: smart_contracts::OwnedReceiveName::new("c.f".into()).unwrap(),
+                message:      smart_contracts::Parameter::new_unchecked(
+                    42u32.to_le_bytes().to_vec(),
+                ),
+            },
+        };
+        let transaction = construct::make_transaction(
+            AccountAddress([0u8; 32]),
+            Nonce::from(7),
+            TransactionTime::from_seconds(123),
+            construct::GivenEnergy::Absolute(Energy::from(5000)),
+            payload,
+        )
+        .sign(&keys);
+        let sign_hash = compute_transaction_sign_hash(&transaction.header, &transaction.payload);
+        let payload_size = u64::from(u32::from(transaction.header.payload_size));
+        let item = BlockItem::from(transaction);
+        let mut bytes = crypto_common::to_bytes(&item);
+
+        let explanation =
+            explain::explain_serialized_block_item(&bytes, Some(&schema::Type::U32)).unwrap();
+        assert_eq!(explanation.transaction_hash.as_ref(), item.hash().as_ref());
+        assert_eq!(explanation.size, bytes.len() as u64);
+        let details = match explanation.details {
+            explain::BlockItemDetails::AccountTransaction(details) => details,
+            _ => panic!("The block item is an account transaction."),
+        };
+        assert_eq!(details.nonce, Nonce::from(7));
+        assert_eq!(details.energy_amount, Energy::from(5000));
+        assert_eq!(details.sign_hash.as_ref(), sign_hash.as_ref());
+        assert_eq!(
+            details.signatures[&CredentialIndex::from(1)]
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            [KeyIndex(0), KeyIndex(2)]
+        );
+        assert_eq!(
+            details.payload.transaction_type,
+            Some(TransactionType::Update)
+        );
+        assert_eq!(details.payload.parameter, Some(serde_json::json!(42)));
+        assert_eq!(details.cost.num_signatures, 2);
+        assert_eq!(
+            details.cost.base_cost,
+            cost::base_cost(construct::TRANSACTION_HEADER_SIZE + payload_size, 2)
+        );
+
+        // A schema type that does not use the entire parameter is reported, but
+        // the rest of the transaction is still explained.
+        let explanation =
+            explain::explain_serialized_block_item(&bytes, Some(&schema::Type::U8)).unwrap();
+        match explanation.details {
+            explain::BlockItemDetails::AccountTransaction(details) => {
+                assert!(details.payload.decoded.is_some());
+                assert!(details.payload.parameter.is_none());
+                assert!(details.payload.parameter_error.is_some());
+            }
+            _ => panic!("The block item is an account transaction."),
+        }
+
+        bytes.push(0);
+        assert!(
+            explain::explain_serialized_block_item(&bytes, None).is_err(),
+            "Trailing bytes must be rejected."
+        );
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        use crate::base::ProtocolVersion;
+        use cost::{estimate_cost, CostEstimate};
+        let to_address = AccountAddress([1u8; 32]);
+        let amount = Amount::from_micro_ccd(17);
+        // The expected costs are the base cost, 60 bytes of header plus the size of
+        // the payload plus 100 for each signature, and the cost specific to the
+        // transaction type.
+        let vectors = [
+            // 41 bytes of payload.
+            (
+                Payload::Transfer { to_address, amount },
+                1,
+                CostEstimate::Exact(Energy::from(501)),
+            ),
+            (
+                Payload::Transfer { to_address, amount },
+                3,
+                CostEstimate::Exact(Energy::from(701)),
+            ),
+            // 53 bytes of payload.
+            (
+                Payload::TransferWithMemo {
+                    to_address,
+                    memo: Memo::try_from(vec![0u8; 10]).unwrap(),
+                    amount,
+                },
+                1,
+                CostEstimate::Exact(Energy::from(513)),
+            ),
+            // 9 bytes of payload.
+            (
+                Payload::TransferToEncrypted { amount },
+                1,
+                CostEstimate::Exact(Energy::from(769)),
+            ),
+            // 66 bytes of payload, and 364 for each release.
+            (
+                Payload::TransferWithSchedule {
+                    to:       to_address,
+                    schedule: vec![
+                        (Timestamp::from(1000), amount),
+                        (Timestamp::from(2000), amount),
+                    ],
+                },
+                1,
+                CostEstimate::Exact(Energy::from(954)),
+            ),
+            // 8 bytes of payload.
+            (
+                Payload::RegisterData {
+                    data: RegisteredData::try_from(vec![0u8; 5]).unwrap(),
+                },
+                1,
+                CostEstimate::Exact(Energy::from(468)),
+            ),
+            // 4 bytes of payload, and at least 500 for the existing credential.
+            (
+                Payload::UpdateCredentials {
+                    new_cred_infos:  BTreeMap::new(),
+                    remove_cred_ids: Vec::new(),
+                    new_threshold:   AccountThreshold::try_from(1u8).unwrap(),
+                },
+                1,
+                CostEstimate::AtLeast(Energy::from(1164)),
+            ),
+            // 3 bytes of payload.
+            (
+                Payload::ConfigureBaker {
+                    data: Box::new(ConfigureBakerPayload::default()),
+                },
+                2,
+                CostEstimate::Exact(Energy::from(563)),
+            ),
+            // 4 bytes of payload.
+            (
+                Payload::ConfigureDelegation {
+                    data: ConfigureDelegationPayload {
+                        restake_earnings: Some(true),
+                        ..ConfigureDelegationPayload::default()
+                    },
+                },
+                1,
+                CostEstimate::Exact(Energy::from(464)),
+            ),
+            // 32 bytes of payload, and the contract execution is not included.
+            (
+                Payload::Update {
+                    payload: UpdateContractPayload {
+                        amount,
+                        address: ContractAddress::new(3, 0),
+                        receive_name: smart_contracts::OwnedReceiveName::new("c.f".into()).unwrap(),
+                        message: smart_contracts::Parameter::default(),
+                    },
+                },
+                1,
+                CostEstimate::AtLeast(Energy::from(192)),
+            ),
+        ];
+        for (payload, num_signatures, expected) in vectors {
+            assert_eq!(
+                estimate_cost(&payload, num_signatures, ProtocolVersion::P5).unwrap(),
+                expected,
+                "Unexpected cost of {}.",
+                payload.transaction_type()
+            );
+        }
+
+        assert_eq!(
+            estimate_cost(&Payload::RemoveBaker, 1, ProtocolVersion::P3).unwrap(),
+            CostEstimate::Exact(Energy::from(461))
+        );
+        assert!(estimate_cost(&Payload::RemoveBaker, 1, ProtocolVersion::P4).is_err());
+        assert!(estimate_cost(
+            &Payload::ConfigureDelegation {
+                data: ConfigureDelegationPayload::default(),
+            },
+            1,
+            ProtocolVersion::P3
+        )
+        .is_err());
+        assert!(estimate_cost(
+            &Payload::TransferWithMemo {
+                to_address,
+                memo: Memo::try_from(Vec::new()).unwrap(),
+                amount,
+            },
+            1,
+            ProtocolVersion::P1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_transaction_batch() {
+        use crate::base::ProtocolVersion;
+        use construct::{BatchBuilder, BatchError, TransactionBatch};
+        let mut rng = rand::thread_rng();
+        let mut cred_keys = BTreeMap::new();
+        cred_keys.insert(KeyIndex(0), KeyPair::generate(&mut rng));
+        let mut keys = BTreeMap::new();
+        keys.insert(CredentialIndex::from(0), cred_keys);
+
+        let access_structure = AccountAccessStructure {
+            threshold: AccountThreshold::try_from(1u8).unwrap(),
+            keys:      keys
+                .iter()
+                .map(|(&ci, keys)| {
+                    let keys = keys
+                        .iter()
+                        .map(|(&ki, kp)| (ki, VerifyKey::from(kp)))
+                        .collect();
+                    (ci, CredentialPublicKeys {
+                        keys,
+                        threshold: SignatureThreshold(1),
+                    })
+                })
+                .collect(),
+        };
+
+        let sender = AccountAddress([0u8; 32]);
+        let expiry = TransactionTime::from_seconds(123);
+        let transfer = |micro_ccd| Payload::Transfer {
+            to_address: AccountAddress([1u8; 32]),
+            amount:     Amount::from_micro_ccd(micro_ccd),
+        };
+        let update = Payload::Update {
+            payload: UpdateContractPayload {
+                amount:       Amount::from_micro_ccd(0),
+                address:      ContractAddress::new(3, 0),
+                receive_name: smart_contracts::OwnedReceiveName::new("c.f".into()).unwrap(),
+                message:      smart_contracts::Parameter::default(),
+            },
+        };
+        let builder = || {
+            BatchBuilder::new(sender, Nonce::from(5), expiry, ProtocolVersion::P5)
+                .add(transfer(1))
+                .add(transfer(2))
+                .add_with_energy(update.clone(), Energy::from(1000))
+                .add(transfer(3))
+        };
+        let batch = builder().sign(&keys).unwrap();
+        let nonces = batch
+            .items()
+            .iter()
+            .map(|item| item.nonce.nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, [5, 6, 7, 8]);
+        for item in batch.items() {
+            assert_eq!(item.hash.as_ref(), item.item.hash().as_ref());
+            match &item.item {
+                BlockItem::AccountTransaction(at) => {
+                    assert!(at.verify_transaction_signature(&access_structure))
+                }
+                _ => panic!("Batches consist of account transactions."),
+            }
+        }
+        match &batch.items()[2].item {
+            BlockItem::AccountTransaction(at) => assert_eq!(
+                at.header.energy_amount,
+                Energy::from(1192),
+                "Explicit energy is added to the base cost."
+            ),
+            _ => panic!("Batches consist of account transactions."),
+        }
+
+        // Signatures are deterministic, so signing in parallel gives the same
+        // transactions.
+        let parallel = builder().parallel(true).sign(&keys).unwrap();
+        for (a, b) in batch.items().iter().zip(parallel.items()) {
+            assert_eq!(a.hash.as_ref(), b.hash.as_ref());
+        }
+
+        // Resuming after the first two transactions were accepted.
+        let bytes = crypto_common::to_bytes(&batch);
+        let resumed: TransactionBatch =
+            crypto_common::from_bytes(&mut std::io::Cursor::new(&bytes)).unwrap();
+        let pending = resumed.pending(Nonce::from(7));
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].hash.as_ref(), batch.items()[2].hash.as_ref());
+        assert!(resumed.pending(Nonce::from(9)).is_empty());
+        assert_eq!(resumed.pending(Nonce::from(1)).len(), 4);
+
+        let without_energy = BatchBuilder::new(sender, Nonce::from(1), expiry, ProtocolVersion::P5)
+            .add(transfer(1))
+            .add(update)
+            .sign(&keys);
+        assert!(matches!(
+            without_energy,
+            Err(BatchError::EnergyRequired { index: 1, .. })
+        ));
+        let unsupported = BatchBuilder::new(sender, Nonce::from(1), expiry, ProtocolVersion::P4)
+            .add(Payload::RemoveBaker)
+            .sign(&keys);
+        assert!(matches!(
+            unsupported,
+            Err(BatchError::Unsupported { index: 0, .. })
+        ));
+    }
 }
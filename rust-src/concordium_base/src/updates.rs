@@ -442,6 +442,54 @@ impl AuthorizationsFamily for ChainParameterVersion1 {
 /// A mapping of chain parameter versions to authorization versions.
 pub type Authorizations<CPV> = <CPV as AuthorizationsFamily>::Output;
 
+/// Implementations of this trait give the level 2 keys and the access
+/// structures that authorize chain updates of each type.
+pub trait HasUpdateAuthorizations {
+    /// All the level 2 keys. Indices in the access structures refer to this
+    /// list.
+    fn update_keys(&self) -> &[UpdatePublicKey];
+    /// The access structure for updates of the given type, or [`None`] if
+    /// these are not authorized by level 2 keys. Updates of the root and level
+    /// 1 keys are authorized by the higher level keys.
+    fn access_structure(&self, update_type: UpdateType) -> Option<&AccessStructure>;
+}
+
+impl HasUpdateAuthorizations for AuthorizationsV0 {
+    fn update_keys(&self) -> &[UpdatePublicKey] { &self.keys }
+
+    fn access_structure(&self, update_type: UpdateType) -> Option<&AccessStructure> {
+        use UpdateType::*;
+        match update_type {
+            UpdateProtocol => Some(&self.protocol),
+            UpdateElectionDifficulty => Some(&self.election_difficulty),
+            UpdateEuroPerEnergy => Some(&self.euro_per_energy),
+            UpdateMicroGTUPerEuro => Some(&self.micro_gtu_per_euro),
+            UpdateFoundationAccount => Some(&self.foundation_account),
+            UpdateMintDistribution => Some(&self.mint_distribution),
+            UpdateTransactionFeeDistribution => Some(&self.transaction_fee_distribution),
+            UpdateGASRewards => Some(&self.param_gas_rewards),
+            UpdatePoolParameters => Some(&self.pool_parameters),
+            UpdateAddAnonymityRevoker => Some(&self.add_anonymity_revoker),
+            UpdateAddIdentityProvider => Some(&self.add_identity_provider),
+            UpdateRootKeys | UpdateLevel1Keys | UpdateLevel2Keys => None,
+            // These updates only exist from chain parameters version 1.
+            UpdateCooldownParameters | UpdateTimeParameters => None,
+        }
+    }
+}
+
+impl HasUpdateAuthorizations for AuthorizationsV1 {
+    fn update_keys(&self) -> &[UpdatePublicKey] { &self.v0.keys }
+
+    fn access_structure(&self, update_type: UpdateType) -> Option<&AccessStructure> {
+        match update_type {
+            UpdateType::UpdateCooldownParameters => Some(&self.cooldown_parameters),
+            UpdateType::UpdateTimeParameters => Some(&self.time_parameters),
+            other => self.v0.access_structure(other),
+        }
+    }
+}
+
 #[derive(SerdeSerialize, SerdeDeserialize, derive::Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 /// Parameters related to becoming a baker that apply to protocol versions 1-3.
@@ -627,6 +675,313 @@ impl UpdatePayload {
     }
 }
 
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Parameters that determine the distribution of rewards in protocol versions
+/// 1-3.
+pub struct RewardParametersV0 {
+    /// Distribution of newly minted CCD.
+    pub mint_distribution:            MintDistributionV0,
+    /// Distribution of transaction fees.
+    pub transaction_fee_distribution: TransactionFeeDistribution,
+    #[serde(rename = "gASRewards")]
+    /// Rewards from the GAS account.
+    pub gas_rewards:                  GASRewards,
+}
+
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Parameters that determine the distribution of rewards in protocol version 4
+/// and up.
+pub struct RewardParametersV1 {
+    /// Distribution of newly minted CCD.
+    pub mint_distribution:            MintDistributionV1,
+    /// Distribution of transaction fees.
+    pub transaction_fee_distribution: TransactionFeeDistribution,
+    #[serde(rename = "gASRewards")]
+    /// Rewards from the GAS account.
+    pub gas_rewards:                  GASRewards,
+}
+
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The chain parameters that are in effect in protocol versions 1-3. The JSON
+/// and binary representations are the ones used by the node.
+pub struct ChainParametersV0 {
+    /// Election difficulty for consensus lottery.
+    pub election_difficulty:          ElectionDifficulty,
+    /// Euro per energy exchange rate.
+    pub euro_per_energy:              ExchangeRate,
+    #[serde(rename = "microGTUPerEuro")]
+    /// Micro ccd per euro exchange rate.
+    pub micro_ccd_per_euro:           ExchangeRate,
+    /// Extra number of epochs before reduction in stake, or baker
+    /// deregistration is completed.
+    pub baker_cooldown_epochs:        Epoch,
+    /// The limit for the number of account creations in a block.
+    pub account_creation_limit:       CredentialsPerBlockLimit,
+    /// Parameters related to the distribution of rewards.
+    pub reward_parameters:            RewardParametersV0,
+    /// Index of the foundation account.
+    pub foundation_account_index:     AccountIndex,
+    /// Minimum threshold for becoming a baker.
+    pub minimum_threshold_for_baking: Amount,
+}
+
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The chain parameters that are in effect in protocol version 4 and up. The
+/// JSON and binary representations are the ones used by the node.
+pub struct ChainParametersV1 {
+    /// Election difficulty for consensus lottery.
+    pub election_difficulty:      ElectionDifficulty,
+    /// Euro per energy exchange rate.
+    pub euro_per_energy:          ExchangeRate,
+    #[serde(rename = "microGTUPerEuro")]
+    /// Micro ccd per euro exchange rate.
+    pub micro_ccd_per_euro:       ExchangeRate,
+    #[serde(flatten)]
+    /// Cooldown periods of pool owners and delegators.
+    pub cooldown_parameters:      CooldownParameters,
+    #[serde(flatten)]
+    /// Length of the reward period and the mint rate per payday.
+    pub time_parameters:          TimeParameters,
+    /// The limit for the number of account creations in a block.
+    pub account_creation_limit:   CredentialsPerBlockLimit,
+    /// Parameters related to the distribution of rewards.
+    pub reward_parameters:        RewardParametersV1,
+    /// Index of the foundation account.
+    pub foundation_account_index: AccountIndex,
+    #[serde(flatten)]
+    /// Parameters governing baking pools and their commissions.
+    pub pool_parameters:          PoolParameters,
+}
+
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The keys that are allowed to make chain updates in protocol versions 1-3.
+pub struct UpdateKeysCollectionV0 {
+    /// Keys allowed to do root updates.
+    pub root_keys:    HigherLevelAccessStructure<RootKeysKind>,
+    #[serde(rename = "level1Keys")]
+    /// Keys allowed to do level1 updates.
+    pub level_1_keys: HigherLevelAccessStructure<Level1KeysKind>,
+    /// Keys allowed to do parameter updates.
+    pub level_2_keys: AuthorizationsV0,
+}
+
+#[derive(Debug, SerdeSerialize, SerdeDeserialize, derive::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The keys that are allowed to make chain updates in protocol version 4 and
+/// up.
+pub struct UpdateKeysCollectionV1 {
+    /// Keys allowed to do root updates.
+    pub root_keys:    HigherLevelAccessStructure<RootKeysKind>,
+    #[serde(rename = "level1Keys")]
+    /// Keys allowed to do level1 updates.
+    pub level_1_keys: HigherLevelAccessStructure<Level1KeysKind>,
+    /// Keys allowed to do parameter updates.
+    pub level_2_keys: AuthorizationsV1,
+}
+
+#[derive(Debug, thiserror::Error)]
+/// Reasons why an update payload cannot be applied.
+pub enum InapplicableUpdate {
+    #[error("Updates of type {update_type:?} do not apply to parameters of this version.")]
+    WrongVersion { update_type: UpdateType },
+    #[error("The new foundation account {0} does not exist.")]
+    UnknownFoundationAccount(AccountAddress),
+}
+
+impl ChainParametersV0 {
+    /// Apply the update to the chain parameters, returning the parameters that
+    /// are in effect after it. The node records the foundation account by its
+    /// index, so `account_index` is used to look up the index of the account
+    /// set by a foundation account update.
+    ///
+    /// Protocol updates, updates of keys, and additions of anonymity revokers
+    /// and identity providers leave the chain parameters unchanged. Note that
+    /// in particular the migration of chain parameters that a protocol update
+    /// might entail is not performed.
+    pub fn apply(
+        &self,
+        update: &UpdatePayload,
+        account_index: impl FnOnce(&AccountAddress) -> Option<AccountIndex>,
+    ) -> Result<Self, InapplicableUpdate> {
+        let mut new = self.clone();
+        match update {
+            UpdatePayload::Protocol(_)
+            | UpdatePayload::Root(_)
+            | UpdatePayload::Level1(_)
+            | UpdatePayload::AddAnonymityRevoker(_)
+            | UpdatePayload::AddIdentityProvider(_) => {}
+            UpdatePayload::ElectionDifficulty(ed) => new.election_difficulty = *ed,
+            UpdatePayload::EuroPerEnergy(rate) => new.euro_per_energy = *rate,
+            UpdatePayload::MicroGTUPerEuro(rate) => new.micro_ccd_per_euro = *rate,
+            UpdatePayload::FoundationAccount(addr) => {
+                new.foundation_account_index = account_index(addr)
+                    .ok_or(InapplicableUpdate::UnknownFoundationAccount(*addr))?
+            }
+            UpdatePayload::MintDistribution(md) => {
+                new.reward_parameters.mint_distribution = md.clone()
+            }
+            UpdatePayload::TransactionFeeDistribution(tf) => {
+                new.reward_parameters.transaction_fee_distribution = tf.clone()
+            }
+            UpdatePayload::GASRewards(gr) => new.reward_parameters.gas_rewards = gr.clone(),
+            UpdatePayload::BakerStakeThreshold(bp) => {
+                new.minimum_threshold_for_baking = bp.minimum_threshold_for_baking
+            }
+            UpdatePayload::CooldownParametersCPV1(_)
+            | UpdatePayload::PoolParametersCPV1(_)
+            | UpdatePayload::TimeParametersCPV1(_)
+            | UpdatePayload::MintDistributionCPV1(_) => {
+                return Err(InapplicableUpdate::WrongVersion {
+                    update_type: update.update_type(),
+                })
+            }
+        }
+        Ok(new)
+    }
+}
+
+impl ChainParametersV1 {
+    /// Apply the update to the chain parameters, returning the parameters that
+    /// are in effect after it. The node records the foundation account by its
+    /// index, so `account_index` is used to look up the index of the account
+    /// set by a foundation account update.
+    ///
+    /// Protocol updates, updates of keys, and additions of anonymity revokers
+    /// and identity providers leave the chain parameters unchanged.
+    pub fn apply(
+        &self,
+        update: &UpdatePayload,
+        account_index: impl FnOnce(&AccountAddress) -> Option<AccountIndex>,
+    ) -> Result<Self, InapplicableUpdate> {
+        let mut new = self.clone();
+        match update {
+            UpdatePayload::Protocol(_)
+            | UpdatePayload::Root(_)
+            | UpdatePayload::Level1(_)
+            | UpdatePayload::AddAnonymityRevoker(_)
+            | UpdatePayload::AddIdentityProvider(_) => {}
+            UpdatePayload::ElectionDifficulty(ed) => new.election_difficulty = *ed,
+            UpdatePayload::EuroPerEnergy(rate) => new.euro_per_energy = *rate,
+            UpdatePayload::MicroGTUPerEuro(rate) => new.micro_ccd_per_euro = *rate,
+            UpdatePayload::FoundationAccount(addr) => {
+                new.foundation_account_index = account_index(addr)
+                    .ok_or(InapplicableUpdate::UnknownFoundationAccount(*addr))?
+            }
+            UpdatePayload::MintDistributionCPV1(md) => {
+                new.reward_parameters.mint_distribution = md.clone()
+            }
+            UpdatePayload::TransactionFeeDistribution(tf) => {
+                new.reward_parameters.transaction_fee_distribution = tf.clone()
+            }
+            UpdatePayload::GASRewards(gr) => new.reward_parameters.gas_rewards = gr.clone(),
+            UpdatePayload::CooldownParametersCPV1(cp) => new.cooldown_parameters = *cp,
+            UpdatePayload::PoolParametersCPV1(pp) => new.pool_parameters = pp.clone(),
+            UpdatePayload::TimeParametersCPV1(tp) => new.time_parameters = *tp,
+            UpdatePayload::BakerStakeThreshold(_) | UpdatePayload::MintDistribution(_) => {
+                return Err(InapplicableUpdate::WrongVersion {
+                    update_type: update.update_type(),
+                })
+            }
+        }
+        Ok(new)
+    }
+}
+
+impl UpdateKeysCollectionV0 {
+    /// Apply the update to the keys, returning the keys that are in effect
+    /// after it. Updates other than key updates leave the keys unchanged.
+    pub fn apply(&self, update: &UpdatePayload) -> Result<Self, InapplicableUpdate> {
+        let mut new = self.clone();
+        match update {
+            UpdatePayload::Root(RootUpdate::RootKeysUpdate(keys)) => new.root_keys = keys.clone(),
+            UpdatePayload::Root(RootUpdate::Level1KeysUpdate(keys))
+            | UpdatePayload::Level1(Level1Update::Level1KeysUpdate(keys)) => {
+                new.level_1_keys = keys.clone()
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdate(keys))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdate(keys)) => {
+                new.level_2_keys = keys.as_ref().clone()
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdateV1(_))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdateV1(_)) => {
+                return Err(InapplicableUpdate::WrongVersion {
+                    update_type: update.update_type(),
+                })
+            }
+            _ => {}
+        }
+        Ok(new)
+    }
+}
+
+impl UpdateKeysCollectionV1 {
+    /// Apply the update to the keys, returning the keys that are in effect
+    /// after it. Updates other than key updates leave the keys unchanged.
+    pub fn apply(&self, update: &UpdatePayload) -> Result<Self, InapplicableUpdate> {
+        let mut new = self.clone();
+        match update {
+            UpdatePayload::Root(RootUpdate::RootKeysUpdate(keys)) => new.root_keys = keys.clone(),
+            UpdatePayload::Root(RootUpdate::Level1KeysUpdate(keys))
+            | UpdatePayload::Level1(Level1Update::Level1KeysUpdate(keys)) => {
+                new.level_1_keys = keys.clone()
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdateV1(keys))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdateV1(keys)) => {
+                new.level_2_keys = keys.as_ref().clone()
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdate(_))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdate(_)) => {
+                return Err(InapplicableUpdate::WrongVersion {
+                    update_type: update.update_type(),
+                })
+            }
+            _ => {}
+        }
+        Ok(new)
+    }
+}
+
+/// Together with [`ChainParameters`] this defines a type family allowing us to
+/// map [`ChainParameterVersion0`] and [`ChainParameterVersion1`] to the
+/// corresponding `ChainParameters` version.
+pub trait ChainParametersFamily {
+    type Output: std::fmt::Debug;
+}
+
+impl ChainParametersFamily for ChainParameterVersion0 {
+    type Output = ChainParametersV0;
+}
+
+impl ChainParametersFamily for ChainParameterVersion1 {
+    type Output = ChainParametersV1;
+}
+
+/// A mapping of chain parameter versions to the types of chain parameters.
+pub type ChainParameters<CPV> = <CPV as ChainParametersFamily>::Output;
+
+/// Together with [`UpdateKeysCollection`] this defines a type family allowing
+/// us to map [`ChainParameterVersion0`] and [`ChainParameterVersion1`] to the
+/// corresponding `UpdateKeysCollection` version.
+pub trait UpdateKeysCollectionFamily {
+    type Output: std::fmt::Debug;
+}
+
+impl UpdateKeysCollectionFamily for ChainParameterVersion0 {
+    type Output = UpdateKeysCollectionV0;
+}
+
+impl UpdateKeysCollectionFamily for ChainParameterVersion1 {
+    type Output = UpdateKeysCollectionV1;
+}
+
+/// A mapping of chain parameter versions to the types of update keys.
+pub type UpdateKeysCollection<CPV> = <CPV as UpdateKeysCollectionFamily>::Output;
+
 #[derive(Debug, Clone, derive::Serialize)]
 pub struct UpdateInstruction {
     pub header:     UpdateHeader,
@@ -668,7 +1023,8 @@ impl UpdateSigner for &[(UpdateKeysIndex, UpdateKeyPair)] {
     }
 }
 
-#[derive(Debug, Clone, Copy, derive::Serialize)]
+#[derive(Debug, Clone, Copy, derive::Serialize, SerdeSerialize, SerdeDeserialize)]
+#[serde(rename_all = "camelCase")]
 /// A header common to all update instructions.
 pub struct UpdateHeader {
     /// Sequence number of the update. Each update queue maintains its own
@@ -717,15 +1073,15 @@ pub mod update {
         <[u8; 32]>::from(hasher.finalize()).into()
     }
 
-    /// Construct an update instruction and sign it.
-    pub fn update(
-        signer: impl UpdateSigner,
+    /// Construct the header of an update with the given payload. This returns
+    /// the header together with the serialized payload.
+    fn make_header(
         seq_number: UpdateSequenceNumber,
         effective_time: TransactionTime,
         timeout: TransactionTime,
-        payload: UpdatePayload,
-    ) -> UpdateInstruction {
-        let serialized_payload = crypto_common::to_bytes(&payload);
+        payload: &UpdatePayload,
+    ) -> (UpdateHeader, Vec<u8>) {
+        let serialized_payload = crypto_common::to_bytes(payload);
         let header = UpdateHeader {
             seq_number,
             effective_time,
@@ -734,6 +1090,19 @@ pub mod update {
                 size: serialized_payload.len() as u32,
             },
         };
+        (header, serialized_payload)
+    }
+
+    /// Construct an update instruction and sign it.
+    pub fn update(
+        signer: impl UpdateSigner,
+        seq_number: UpdateSequenceNumber,
+        effective_time: TransactionTime,
+        timeout: TransactionTime,
+        payload: UpdatePayload,
+    ) -> UpdateInstruction {
+        let (header, serialized_payload) =
+            make_header(seq_number, effective_time, timeout, &payload);
         let signatures = signer.sign_update_hash(&compute_sign_hash(&header, &serialized_payload));
         UpdateInstruction {
             header,
@@ -741,6 +1110,290 @@ pub mod update {
             signatures,
         }
     }
+
+    #[derive(Debug, Clone, SerdeSerialize, SerdeDeserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[serde(try_from = "partially_signed_update_json::PartiallySignedUpdateRaw")]
+    /// An update instruction that is signed by several holders of update keys.
+    /// The bundle is passed around the holders, each adding their signatures
+    /// with [`add_signatures`](Self::add_signatures), or bundles signed in
+    /// parallel are combined with [`merge`](Self::merge). Once enough keys
+    /// have signed it is turned into an [`UpdateInstruction`].
+    pub struct PartiallySignedUpdate {
+        pub header:     UpdateHeader,
+        pub payload:    UpdatePayload,
+        /// Signatures collected so far. This may be empty.
+        pub signatures: BTreeMap<UpdateKeysIndex, Signature>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    /// Signatures that are still needed for an update to be authorized.
+    pub struct MissingUpdateSignatures {
+        /// The number of additional valid signatures needed to reach the
+        /// threshold.
+        pub needed:   u16,
+        /// Authorized keys that have not produced a valid signature.
+        pub unsigned: BTreeSet<UpdateKeysIndex>,
+        /// Signatures that do not verify, or that are by keys that are not
+        /// authorized for the update. These are dropped when finalizing.
+        pub invalid:  BTreeSet<UpdateKeysIndex>,
+    }
+
+    impl MissingUpdateSignatures {
+        /// Whether the threshold is reached.
+        pub fn is_complete(&self) -> bool { self.needed == 0 }
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    /// Errors when collecting signatures on a [`PartiallySignedUpdate`].
+    pub enum UpdateSignatureError {
+        #[error("The partially signed updates have different headers or payloads.")]
+        DifferentUpdates,
+        #[error("Conflicting signatures by update key {0}.")]
+        ConflictingSignatures(UpdateKeysIndex),
+        #[error("Updates of type {0:?} are not authorized by level 2 keys.")]
+        NotLevel2Update(UpdateType),
+        #[error("The update does not have enough valid signatures.")]
+        Incomplete(MissingUpdateSignatures),
+    }
+
+    impl PartiallySignedUpdate {
+        /// Construct an update with no signatures.
+        pub fn new(
+            seq_number: UpdateSequenceNumber,
+            effective_time: TransactionTime,
+            timeout: TransactionTime,
+            payload: UpdatePayload,
+        ) -> Self {
+            let (header, _) = make_header(seq_number, effective_time, timeout, &payload);
+            Self {
+                header,
+                payload,
+                signatures: BTreeMap::new(),
+            }
+        }
+
+        /// The hash that the signatures are on.
+        pub fn hash_to_sign(&self) -> hashes::UpdateSignHash {
+            compute_sign_hash(&self.header, &crypto_common::to_bytes(&self.payload))
+        }
+
+        /// Sign the update with the given keys, and add the signatures to the
+        /// ones collected so far.
+        pub fn add_signatures(
+            &mut self,
+            signer: impl UpdateSigner,
+        ) -> Result<(), UpdateSignatureError> {
+            let signature = signer.sign_update_hash(&self.hash_to_sign());
+            self.insert_signatures(signature.signatures)
+        }
+
+        /// Add the signatures collected on another bundle of the same update.
+        pub fn merge(&mut self, other: &PartiallySignedUpdate) -> Result<(), UpdateSignatureError> {
+            if self.hash_to_sign().as_ref() != other.hash_to_sign().as_ref() {
+                return Err(UpdateSignatureError::DifferentUpdates);
+            }
+            self.insert_signatures(other.signatures.clone())
+        }
+
+        /// Insert the signatures. If a key already has a different signature
+        /// nothing is inserted.
+        fn insert_signatures(
+            &mut self,
+            signatures: BTreeMap<UpdateKeysIndex, Signature>,
+        ) -> Result<(), UpdateSignatureError> {
+            for (idx, sig) in signatures.iter() {
+                if self
+                    .signatures
+                    .get(idx)
+                    .map_or(false, |existing| existing != sig)
+                {
+                    return Err(UpdateSignatureError::ConflictingSignatures(*idx));
+                }
+            }
+            self.signatures.extend(signatures);
+            Ok(())
+        }
+
+        /// Check the signatures against the given authorized keys and
+        /// threshold.
+        fn check_signatures(
+            &self,
+            keys: &[UpdatePublicKey],
+            authorized: &BTreeSet<UpdateKeysIndex>,
+            threshold: UpdateKeysThreshold,
+        ) -> MissingUpdateSignatures {
+            let hash = self.hash_to_sign();
+            let mut signed = 0u16;
+            let mut invalid = BTreeSet::new();
+            for (idx, sig) in self.signatures.iter() {
+                let valid = authorized.contains(idx)
+                    && keys
+                        .get(usize::from(idx.index))
+                        .map_or(false, |key| key.public.verify(&hash, sig));
+                if valid {
+                    signed += 1;
+                } else {
+                    invalid.insert(*idx);
+                }
+            }
+            let unsigned = authorized
+                .iter()
+                .filter(|idx| !self.signatures.contains_key(idx) || invalid.contains(idx))
+                .copied()
+                .collect();
+            MissingUpdateSignatures {
+                needed: u16::from(threshold).saturating_sub(signed),
+                unsigned,
+                invalid,
+            }
+        }
+
+        /// Check which signatures are still needed for the update to be
+        /// authorized by the level 2 keys. This fails for updates of the root
+        /// and level 1 keys, see
+        /// [`missing_signatures_higher_level`](Self::missing_signatures_higher_level)
+        /// for those.
+        pub fn missing_signatures(
+            &self,
+            authorizations: &impl HasUpdateAuthorizations,
+        ) -> Result<MissingUpdateSignatures, UpdateSignatureError> {
+            let update_type = self.payload.update_type();
+            let access = authorizations
+                .access_structure(update_type)
+                .ok_or(UpdateSignatureError::NotLevel2Update(update_type))?;
+            Ok(self.check_signatures(
+                authorizations.update_keys(),
+                &access.authorized_keys,
+                access.threshold,
+            ))
+        }
+
+        /// Check which signatures are still needed for the update to be
+        /// authorized by the given root or level 1 keys.
+        pub fn missing_signatures_higher_level<Kind>(
+            &self,
+            access: &HigherLevelAccessStructure<Kind>,
+        ) -> MissingUpdateSignatures {
+            let authorized = (0..access.keys.len())
+                .map(|index| UpdateKeysIndex {
+                    index: index as u16,
+                })
+                .collect();
+            self.check_signatures(&access.keys, &authorized, access.threshold)
+        }
+
+        /// Construct the update instruction if enough keys have signed.
+        /// Signatures that are invalid or by keys that are not authorized are
+        /// left out.
+        fn finalize_checked(
+            self,
+            missing: MissingUpdateSignatures,
+        ) -> Result<UpdateInstruction, UpdateSignatureError> {
+            if !missing.is_complete() {
+                return Err(UpdateSignatureError::Incomplete(missing));
+            }
+            let mut signatures = self.signatures;
+            signatures.retain(|idx, _| !missing.invalid.contains(idx));
+            Ok(UpdateInstruction {
+                header:     self.header,
+                payload:    self.payload,
+                signatures: UpdateInstructionSignature { signatures },
+            })
+        }
+
+        /// Construct the update instruction if the level 2 keys that have
+        /// signed reach the threshold for the update type.
+        pub fn finalize(
+            self,
+            authorizations: &impl HasUpdateAuthorizations,
+        ) -> Result<UpdateInstruction, UpdateSignatureError> {
+            let missing = self.missing_signatures(authorizations)?;
+            self.finalize_checked(missing)
+        }
+
+        /// Construct the update instruction if the root or level 1 keys that
+        /// have signed reach the threshold.
+        pub fn finalize_higher_level<Kind>(
+            self,
+            access: &HigherLevelAccessStructure<Kind>,
+        ) -> Result<UpdateInstruction, UpdateSignatureError> {
+            let missing = self.missing_signatures_higher_level(access);
+            self.finalize_checked(missing)
+        }
+    }
+
+    impl From<UpdateInstruction> for PartiallySignedUpdate {
+        fn from(ui: UpdateInstruction) -> Self {
+            Self {
+                header:     ui.header,
+                payload:    ui.payload,
+                signatures: ui.signatures.signatures,
+            }
+        }
+    }
+
+    /// The serialization is the same as that of an [`UpdateInstruction`],
+    /// except that there may be no signatures.
+    impl Serial for PartiallySignedUpdate {
+        fn serial<B: Buffer>(&self, out: &mut B) {
+            self.header.serial(out);
+            self.payload.serial(out);
+            (self.signatures.len() as u16).serial(out);
+            crypto_common::serial_map_no_length(&self.signatures, out);
+        }
+    }
+
+    /// Check that the payload size in the header is the size of the payload.
+    fn check_payload_size(header: &UpdateHeader, payload: &UpdatePayload) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            crypto_common::to_bytes(payload).len() as u32 == u32::from(header.payload_size),
+            "Payload size does not match the header."
+        );
+        Ok(())
+    }
+
+    /// An internal helper to deserialize a partially signed update and ensure
+    /// that the payload size in the header is correct.
+    mod partially_signed_update_json {
+        use super::*;
+
+        #[derive(SerdeDeserialize)]
+        #[serde(rename_all = "camelCase")]
+        pub struct PartiallySignedUpdateRaw {
+            pub header:     UpdateHeader,
+            pub payload:    UpdatePayload,
+            pub signatures: BTreeMap<UpdateKeysIndex, Signature>,
+        }
+
+        impl std::convert::TryFrom<PartiallySignedUpdateRaw> for PartiallySignedUpdate {
+            type Error = anyhow::Error;
+
+            fn try_from(value: PartiallySignedUpdateRaw) -> Result<Self, Self::Error> {
+                check_payload_size(&value.header, &value.payload)?;
+                Ok(Self {
+                    header:     value.header,
+                    payload:    value.payload,
+                    signatures: value.signatures,
+                })
+            }
+        }
+    }
+
+    impl Deserial for PartiallySignedUpdate {
+        fn deserial<R: ReadBytesExt>(source: &mut R) -> ParseResult<Self> {
+            let header: UpdateHeader = source.get()?;
+            let payload: UpdatePayload = source.get()?;
+            check_payload_size(&header, &payload)?;
+            let len: u16 = source.get()?;
+            let signatures = deserial_map_no_length(source, len.into())?;
+            Ok(Self {
+                header,
+                payload,
+                signatures,
+            })
+        }
+    }
 }
 
 impl Serial for UpdatePayload {
@@ -842,3 +1495,879 @@ impl Deserial for UpdatePayload {
         }
     }
 }
+
+/// Validation of update payloads beyond what is ensured by deserialization.
+/// This checks that the payload is allowed in a given protocol version, and
+/// that its parameters are consistent. Updates that fail validation would be
+/// rejected by the chain, so governance tooling should check them before they
+/// are signed.
+pub mod validation {
+    use super::*;
+    use thiserror::Error;
+
+    #[derive(Debug, Error, Clone, PartialEq, Eq)]
+    /// A reason why an update payload is not valid.
+    pub enum UpdatePayloadError {
+        #[error("Update {payload} is not supported in protocol version {protocol_version}.")]
+        UnsupportedInProtocolVersion {
+            payload:          &'static str,
+            protocol_version: ProtocolVersion,
+        },
+        #[error("The fractions of {parameter} sum to more than 1.")]
+        FractionsExceedOne { parameter: &'static str },
+        #[error("The fraction {parameter} exceeds 1.")]
+        FractionTooLarge { parameter: &'static str },
+        #[error("The cooldown {parameter} does not fit into a timestamp.")]
+        CooldownTooLong { parameter: &'static str },
+        #[error("The election difficulty must be less than 1.")]
+        ElectionDifficultyTooLarge,
+        #[error("The mint rate {parameter} exceeds 1.")]
+        MintRateTooLarge { parameter: &'static str },
+        #[error("The range {parameter} is empty.")]
+        EmptyRange { parameter: &'static str },
+        #[error(
+            "The {parameter} commission {rate} that is currently in use is outside the range."
+        )]
+        RateOutsideRange {
+            parameter: &'static str,
+            rate:      AmountFraction,
+        },
+        #[error("The reward period length must be at least one epoch.")]
+        ZeroRewardPeriodLength,
+        #[error("The capital bound must be greater than 0.")]
+        ZeroCapitalBound,
+        #[error("The leverage bound must be at least 1.")]
+        InvalidLeverageBound,
+        #[error("The access structure {structure} has no keys.")]
+        NoKeys { structure: &'static str },
+        #[error("The access structure {structure} refers to key {index} that does not exist.")]
+        UnknownKeyIndex {
+            structure: &'static str,
+            index:     UpdateKeysIndex,
+        },
+        #[error("The threshold of the access structure {structure} exceeds the number of keys.")]
+        ThresholdTooLarge { structure: &'static str },
+    }
+
+    /// The name of the payload. This is the tag used in its JSON
+    /// serialization.
+    fn payload_name(payload: &UpdatePayload) -> &'static str {
+        match payload {
+            UpdatePayload::Protocol(_) => "protocol",
+            UpdatePayload::ElectionDifficulty(_) => "electionDifficulty",
+            UpdatePayload::EuroPerEnergy(_) => "euroPerEnergy",
+            UpdatePayload::MicroGTUPerEuro(_) => "microGTUPerEuro",
+            UpdatePayload::FoundationAccount(_) => "foundationAccount",
+            UpdatePayload::MintDistribution(_) => "mintDistribution",
+            UpdatePayload::TransactionFeeDistribution(_) => "transactionFeeDistribution",
+            UpdatePayload::GASRewards(_) => "gASRewards",
+            UpdatePayload::BakerStakeThreshold(_) => "bakerStakeThreshold",
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdate(_)) => "root.level2KeysUpdate",
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdateV1(_)) => "root.level2KeysUpdateV1",
+            UpdatePayload::Root(_) => "root",
+            UpdatePayload::Level1(Level1Update::Level2KeysUpdate(_)) => "level1.level2KeysUpdate",
+            UpdatePayload::Level1(Level1Update::Level2KeysUpdateV1(_)) => {
+                "level1.level2KeysUpdateV1"
+            }
+            UpdatePayload::Level1(_) => "level1",
+            UpdatePayload::AddAnonymityRevoker(_) => "addAnonymityRevoker",
+            UpdatePayload::AddIdentityProvider(_) => "addIdentityProvider",
+            UpdatePayload::CooldownParametersCPV1(_) => "cooldownParametersCPV1",
+            UpdatePayload::PoolParametersCPV1(_) => "poolParametersCPV1",
+            UpdatePayload::TimeParametersCPV1(_) => "timeParametersCPV1",
+            UpdatePayload::MintDistributionCPV1(_) => "mintDistributionCPV1",
+        }
+    }
+
+    /// Whether the payload can be used in the given protocol version. Protocol
+    /// version 4 changes the chain parameters to version 1, which replaces
+    /// some updates and adds others.
+    pub fn is_supported_in(payload: &UpdatePayload, protocol_version: ProtocolVersion) -> bool {
+        let cpv1 = protocol_version >= ProtocolVersion::P4;
+        match payload {
+            UpdatePayload::MintDistribution(_)
+            | UpdatePayload::BakerStakeThreshold(_)
+            | UpdatePayload::Root(RootUpdate::Level2KeysUpdate(_))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdate(_)) => !cpv1,
+            UpdatePayload::MintDistributionCPV1(_)
+            | UpdatePayload::PoolParametersCPV1(_)
+            | UpdatePayload::CooldownParametersCPV1(_)
+            | UpdatePayload::TimeParametersCPV1(_)
+            | UpdatePayload::Root(RootUpdate::Level2KeysUpdateV1(_))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdateV1(_)) => cpv1,
+            _ => true,
+        }
+    }
+
+    /// Check that the payload is allowed in the given protocol version and
+    /// that its parameters are consistent. All problems that are found are
+    /// returned.
+    pub fn validate_update_payload(
+        payload: &UpdatePayload,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), Vec<UpdatePayloadError>> {
+        let mut errors = Vec::new();
+        if !is_supported_in(payload, protocol_version) {
+            errors.push(UpdatePayloadError::UnsupportedInProtocolVersion {
+                payload: payload_name(payload),
+                protocol_version,
+            });
+        }
+        match payload {
+            UpdatePayload::ElectionDifficulty(ed) => {
+                if ed.parts_per_hundred_thousands.parts >= 100_000 {
+                    errors.push(UpdatePayloadError::ElectionDifficultyTooLarge);
+                }
+            }
+            UpdatePayload::MintDistribution(md) => {
+                check_mint_rate("mintPerSlot", &md.mint_per_slot, &mut errors);
+                check_fractions(
+                    "mintDistribution",
+                    md.baking_reward,
+                    md.finalization_reward,
+                    &mut errors,
+                );
+            }
+            UpdatePayload::MintDistributionCPV1(md) => check_fractions(
+                "mintDistribution",
+                md.baking_reward,
+                md.finalization_reward,
+                &mut errors,
+            ),
+            UpdatePayload::TransactionFeeDistribution(tf) => check_fractions(
+                "transactionFeeDistribution",
+                tf.baker,
+                tf.gas_account,
+                &mut errors,
+            ),
+            UpdatePayload::GASRewards(gr) => {
+                let fractions = [
+                    ("baker", gr.baker),
+                    ("finalizationProof", gr.finalization_proof),
+                    ("accountCreation", gr.account_creation),
+                    ("chainUpdate", gr.chain_update),
+                ];
+                for (parameter, fraction) in fractions {
+                    if fraction.parts_per_hundred_thousands.parts > 100_000 {
+                        errors.push(UpdatePayloadError::FractionTooLarge { parameter });
+                    }
+                }
+            }
+            UpdatePayload::CooldownParametersCPV1(cp) => {
+                let cooldowns = [
+                    ("poolOwnerCooldown", cp.pool_owner_cooldown),
+                    ("delegatorCooldown", cp.delegator_cooldown),
+                ];
+                // The end of a cooldown is a timestamp in milliseconds.
+                for (parameter, cooldown) in cooldowns {
+                    if cooldown.seconds.checked_mul(1000).is_none() {
+                        errors.push(UpdatePayloadError::CooldownTooLong { parameter });
+                    }
+                }
+            }
+            UpdatePayload::PoolParametersCPV1(pp) => check_pool_parameters(pp, &mut errors),
+            UpdatePayload::TimeParametersCPV1(tp) => {
+                if tp.reward_period_length.reward_period_epochs.epoch == 0 {
+                    errors.push(UpdatePayloadError::ZeroRewardPeriodLength);
+                }
+                check_mint_rate("mintPerPayday", &tp.mint_per_payday, &mut errors);
+            }
+            UpdatePayload::Root(RootUpdate::RootKeysUpdate(keys)) => {
+                check_higher_level("rootKeys", keys, &mut errors)
+            }
+            UpdatePayload::Root(RootUpdate::Level1KeysUpdate(keys))
+            | UpdatePayload::Level1(Level1Update::Level1KeysUpdate(keys)) => {
+                check_higher_level("level1Keys", keys, &mut errors)
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdate(auth))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdate(auth)) => {
+                check_authorizations_v0(auth, &mut errors)
+            }
+            UpdatePayload::Root(RootUpdate::Level2KeysUpdateV1(auth))
+            | UpdatePayload::Level1(Level1Update::Level2KeysUpdateV1(auth)) => {
+                check_authorizations_v0(&auth.v0, &mut errors);
+                let num_keys = auth.v0.keys.len();
+                check_access_structure(
+                    "cooldownParameters",
+                    num_keys,
+                    &auth.cooldown_parameters,
+                    &mut errors,
+                );
+                check_access_structure(
+                    "timeParameters",
+                    num_keys,
+                    &auth.time_parameters,
+                    &mut errors,
+                );
+            }
+            UpdatePayload::Protocol(_)
+            | UpdatePayload::EuroPerEnergy(_)
+            | UpdatePayload::MicroGTUPerEuro(_)
+            | UpdatePayload::FoundationAccount(_)
+            | UpdatePayload::BakerStakeThreshold(_)
+            | UpdatePayload::AddAnonymityRevoker(_)
+            | UpdatePayload::AddIdentityProvider(_) => (),
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check that the commission ranges contain the given commission rates,
+    /// for example the rates that pools currently charge.
+    pub fn validate_commission_ranges(
+        ranges: &CommissionRanges,
+        current_rates: &[CommissionRates],
+    ) -> Result<(), Vec<UpdatePayloadError>> {
+        let mut errors = Vec::new();
+        for rates in current_rates {
+            let checks = [
+                ("finalization", &ranges.finalization, rates.finalization),
+                ("baking", &ranges.baking, rates.baking),
+                ("transaction", &ranges.transaction, rates.transaction),
+            ];
+            for (parameter, range, rate) in checks {
+                if !range.contains(&rate) {
+                    errors.push(UpdatePayloadError::RateOutsideRange { parameter, rate });
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn check_fractions(
+        parameter: &'static str,
+        left: AmountFraction,
+        right: AmountFraction,
+        errors: &mut Vec<UpdatePayloadError>,
+    ) {
+        if (left + right).is_none() {
+            errors.push(UpdatePayloadError::FractionsExceedOne { parameter });
+        }
+    }
+
+    fn check_mint_rate(
+        parameter: &'static str,
+        rate: &MintRate,
+        errors: &mut Vec<UpdatePayloadError>,
+    ) {
+        // The rate is `mantissa * 10^(-exponent)`. If the power does not fit
+        // into a u64 then it exceeds any mantissa.
+        let at_most_one = 10u64
+            .checked_pow(rate.exponent.into())
+            .map_or(true, |scale| u64::from(rate.mantissa) <= scale);
+        if !at_most_one {
+            errors.push(UpdatePayloadError::MintRateTooLarge { parameter });
+        }
+    }
+
+    fn check_pool_parameters(pp: &PoolParameters, errors: &mut Vec<UpdatePayloadError>) {
+        let ranges = [
+            (
+                "finalizationCommissionRange",
+                &pp.commission_bounds.finalization,
+            ),
+            ("bakingCommissionRange", &pp.commission_bounds.baking),
+            (
+                "transactionCommissionRange",
+                &pp.commission_bounds.transaction,
+            ),
+        ];
+        for (parameter, range) in ranges {
+            if range.min > range.max {
+                errors.push(UpdatePayloadError::EmptyRange { parameter });
+            }
+        }
+        // The commissions of passive delegation must be within the ranges as well.
+        let passive_commissions = CommissionRates {
+            finalization: pp.passive_finalization_commission,
+            baking:       pp.passive_baking_commission,
+            transaction:  pp.passive_transaction_commission,
+        };
+        if let Err(es) = validate_commission_ranges(&pp.commission_bounds, &[passive_commissions]) {
+            errors.extend(es);
+        }
+        if pp.capital_bound.bound == AmountFraction::default() {
+            errors.push(UpdatePayloadError::ZeroCapitalBound);
+        }
+        let leverage = &pp.leverage_bound;
+        if leverage.denominator == 0 || leverage.numerator < leverage.denominator {
+            errors.push(UpdatePayloadError::InvalidLeverageBound);
+        }
+    }
+
+    fn check_higher_level<Kind>(
+        structure: &'static str,
+        keys: &HigherLevelAccessStructure<Kind>,
+        errors: &mut Vec<UpdatePayloadError>,
+    ) {
+        if keys.keys.is_empty() {
+            errors.push(UpdatePayloadError::NoKeys { structure });
+        }
+        if usize::from(u16::from(keys.threshold)) > keys.keys.len() {
+            errors.push(UpdatePayloadError::ThresholdTooLarge { structure });
+        }
+    }
+
+    fn check_access_structure(
+        structure: &'static str,
+        num_keys: usize,
+        access: &AccessStructure,
+        errors: &mut Vec<UpdatePayloadError>,
+    ) {
+        if let Some(index) = access
+            .authorized_keys
+            .iter()
+            .find(|index| usize::from(index.index) >= num_keys)
+        {
+            errors.push(UpdatePayloadError::UnknownKeyIndex {
+                structure,
+                index: *index,
+            });
+        }
+        if usize::from(u16::from(access.threshold)) > access.authorized_keys.len() {
+            errors.push(UpdatePayloadError::ThresholdTooLarge { structure });
+        }
+    }
+
+    fn check_authorizations_v0(auth: &AuthorizationsV0, errors: &mut Vec<UpdatePayloadError>) {
+        if auth.keys.is_empty() {
+            errors.push(UpdatePayloadError::NoKeys {
+                structure: "level2Keys",
+            });
+        }
+        let structures = [
+            ("emergency", &auth.emergency),
+            ("protocol", &auth.protocol),
+            ("electionDifficulty", &auth.election_difficulty),
+            ("euroPerEnergy", &auth.euro_per_energy),
+            ("microGTUPerEuro", &auth.micro_gtu_per_euro),
+            ("foundationAccount", &auth.foundation_account),
+            ("mintDistribution", &auth.mint_distribution),
+            (
+                "transactionFeeDistribution",
+                &auth.transaction_fee_distribution,
+            ),
+            ("paramGASRewards", &auth.param_gas_rewards),
+            ("poolParameters", &auth.pool_parameters),
+            ("addAnonymityRevoker", &auth.add_anonymity_revoker),
+            ("addIdentityProvider", &auth.add_identity_provider),
+        ];
+        for (structure, access) in structures {
+            check_access_structure(structure, auth.keys.len(), access, errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update::*, *};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_partially_signed_update() {
+        let mut rng = rand::thread_rng();
+        let key_pairs = (0..4)
+            .map(|_| UpdateKeyPair::generate(&mut rng))
+            .collect::<Vec<_>>();
+        // Keys 0, 1 and 2 may update the election difficulty, and two of them
+        // must sign.
+        let access = AccessStructure {
+            authorized_keys: (0..3).map(|index| UpdateKeysIndex { index }).collect(),
+            threshold:       UpdateKeysThreshold::try_from(2).unwrap(),
+        };
+        let authorizations = AuthorizationsV0 {
+            keys: key_pairs.iter().map(UpdatePublicKey::from).collect(),
+            emergency: access.clone(),
+            protocol: access.clone(),
+            election_difficulty: access.clone(),
+            euro_per_energy: access.clone(),
+            micro_gtu_per_euro: access.clone(),
+            foundation_account: access.clone(),
+            mint_distribution: access.clone(),
+            transaction_fee_distribution: access.clone(),
+            param_gas_rewards: access.clone(),
+            pool_parameters: access.clone(),
+            add_anonymity_revoker: access.clone(),
+            add_identity_provider: access,
+        };
+        let mut key_pairs = key_pairs.into_iter().enumerate().collect::<Vec<_>>();
+        // Hand the key with the given index to a signing party.
+        let mut take_key = |index: u16| {
+            let pos = key_pairs
+                .iter()
+                .position(|(i, _)| *i == usize::from(index))
+                .unwrap();
+            let (_, kp) = key_pairs.remove(pos);
+            let mut signer = BTreeMap::new();
+            signer.insert(UpdateKeysIndex { index }, kp);
+            signer
+        };
+
+        let payload = UpdatePayload::ElectionDifficulty(ElectionDifficulty::new(2500).unwrap());
+        let mut first = PartiallySignedUpdate::new(
+            UpdateSequenceNumber::from(1),
+            TransactionTime::from_seconds(0),
+            TransactionTime::from_seconds(100),
+            payload,
+        );
+        let mut second = first.clone();
+        first.add_signatures(&take_key(0)).unwrap();
+        // Key 3 is not authorized for this update.
+        second.add_signatures(&take_key(3)).unwrap();
+        first.merge(&second).unwrap();
+        let missing = first.missing_signatures(&authorizations).unwrap();
+        assert_eq!(missing.needed, 1);
+        assert_eq!(
+            missing.unsigned,
+            [1, 2]
+                .iter()
+                .map(|&index| UpdateKeysIndex { index })
+                .collect::<BTreeSet<_>>()
+        );
+        assert_eq!(
+            missing.invalid,
+            std::iter::once(UpdateKeysIndex { index: 3 }).collect::<BTreeSet<_>>()
+        );
+        assert!(matches!(
+            first.clone().finalize(&authorizations),
+            Err(UpdateSignatureError::Incomplete(_))
+        ));
+
+        // The bundle can be passed on both as JSON and in binary.
+        let json = serde_json::to_string(&first).unwrap();
+        let mut from_json: PartiallySignedUpdate = serde_json::from_str(&json).unwrap();
+        from_json.add_signatures(&take_key(2)).unwrap();
+        let bytes = crypto_common::to_bytes(&from_json);
+        let from_binary: PartiallySignedUpdate =
+            crypto_common::from_bytes(&mut std::io::Cursor::new(&bytes)).unwrap();
+        assert!(from_binary
+            .missing_signatures(&authorizations)
+            .unwrap()
+            .is_complete());
+        let instruction = from_binary.clone().finalize(&authorizations).unwrap();
+        assert_eq!(
+            instruction
+                .signatures
+                .signatures
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![UpdateKeysIndex { index: 0 }, UpdateKeysIndex { index: 2 }],
+            "The signature of the unauthorized key is left out."
+        );
+
+        // A payload size that does not match the payload is rejected both in
+        // JSON and in binary.
+        let mut wrong_size = from_binary;
+        wrong_size.header.payload_size =
+            PayloadSize::from(u32::from(wrong_size.header.payload_size) + 1);
+        let json = serde_json::to_string(&wrong_size).unwrap();
+        assert!(serde_json::from_str::<PartiallySignedUpdate>(&json).is_err());
+        let bytes = crypto_common::to_bytes(&wrong_size);
+        let from_binary: ParseResult<PartiallySignedUpdate> =
+            crypto_common::from_bytes(&mut std::io::Cursor::new(&bytes));
+        assert!(from_binary.is_err());
+    }
+
+    #[test]
+    fn test_validate_update_payload() {
+        use validation::*;
+        let fraction = |parts| AmountFraction::new(parts).unwrap();
+        let range = |min, max| InclusiveRange {
+            min: fraction(min),
+            max: fraction(max),
+        };
+        let pool_parameters = PoolParameters {
+            passive_finalization_commission: fraction(100_000),
+            passive_baking_commission:       fraction(10_000),
+            passive_transaction_commission:  fraction(10_000),
+            commission_bounds:               CommissionRanges {
+                finalization: range(100_000, 100_000),
+                baking:       range(5_000, 10_000),
+                transaction:  range(5_000, 10_000),
+            },
+            minimum_equity_capital:          Amount::from_ccd(14_000),
+            capital_bound:                   CapitalBound {
+                bound: fraction(10_000),
+            },
+            leverage_bound:                  LeverageFactor::new_integral(3),
+        };
+        let payload = UpdatePayload::PoolParametersCPV1(pool_parameters.clone());
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P4),
+            Ok(())
+        );
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P3),
+            Err(vec![UpdatePayloadError::UnsupportedInProtocolVersion {
+                payload:          "poolParametersCPV1",
+                protocol_version: ProtocolVersion::P3,
+            }])
+        );
+        let payload = UpdatePayload::PoolParametersCPV1(PoolParameters {
+            passive_transaction_commission: fraction(12_000),
+            ..pool_parameters.clone()
+        });
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P4),
+            Err(vec![UpdatePayloadError::RateOutsideRange {
+                parameter: "transaction",
+                rate:      fraction(12_000),
+            }])
+        );
+        let payload = UpdatePayload::BakerStakeThreshold(BakerParameters {
+            minimum_threshold_for_baking: Amount::from_ccd(14_000),
+        });
+        assert!(validate_update_payload(&payload, ProtocolVersion::P3).is_ok());
+        assert!(validate_update_payload(&payload, ProtocolVersion::P5).is_err());
+
+        let current_rates = CommissionRates {
+            finalization: fraction(100_000),
+            baking:       fraction(10_000),
+            transaction:  fraction(4_000),
+        };
+        assert_eq!(
+            validate_commission_ranges(&pool_parameters.commission_bounds, &[current_rates]),
+            Err(vec![UpdatePayloadError::RateOutsideRange {
+                parameter: "transaction",
+                rate:      fraction(4_000),
+            }])
+        );
+
+        let payload = UpdatePayload::TimeParametersCPV1(TimeParameters {
+            reward_period_length: RewardPeriodLength {
+                reward_period_epochs: Epoch { epoch: 0 },
+            },
+            mint_per_payday:      MintRate {
+                mantissa: 2,
+                exponent: 0,
+            },
+        });
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P4),
+            Err(vec![
+                UpdatePayloadError::ZeroRewardPeriodLength,
+                UpdatePayloadError::MintRateTooLarge {
+                    parameter: "mintPerPayday",
+                }
+            ])
+        );
+
+        let payload = UpdatePayload::GASRewards(GASRewards {
+            baker:              fraction(25_000),
+            finalization_proof: AmountFraction::new_unchecked(100_001),
+            account_creation:   fraction(2_000),
+            chain_update:       fraction(100_000),
+        });
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P4),
+            Err(vec![UpdatePayloadError::FractionTooLarge {
+                parameter: "finalizationProof",
+            }])
+        );
+
+        let payload = UpdatePayload::CooldownParametersCPV1(CooldownParameters {
+            pool_owner_cooldown: DurationSeconds { seconds: 1_814_400 },
+            delegator_cooldown:  DurationSeconds { seconds: u64::MAX },
+        });
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P4),
+            Err(vec![UpdatePayloadError::CooldownTooLong {
+                parameter: "delegatorCooldown",
+            }])
+        );
+
+        let payload = UpdatePayload::MintDistributionCPV1(MintDistributionV1 {
+            baking_reward:       fraction(60_000),
+            finalization_reward: fraction(50_000),
+        });
+        assert_eq!(
+            validate_update_payload(&payload, ProtocolVersion::P5),
+            Err(vec![UpdatePayloadError::FractionsExceedOne {
+                parameter: "mintDistribution",
+            }])
+        );
+    }
+
+    /// Chain parameters of protocol version 3 as reported by the node.
+    const CHAIN_PARAMETERS_V0_JSON: &str = r#"{
+        "electionDifficulty": 2.5e-2,
+        "euroPerEnergy": { "denominator": 1000000, "numerator": 1 },
+        "microGTUPerEuro": { "denominator": 1, "numerator": 500000 },
+        "bakerCooldownEpochs": 166,
+        "accountCreationLimit": 10,
+        "rewardParameters": {
+            "mintDistribution": {
+                "mintPerSlot": 7.555665e-10,
+                "bakingReward": 0.6,
+                "finalizationReward": 0.3
+            },
+            "transactionFeeDistribution": { "baker": 0.45, "gasAccount": 0.45 },
+            "gASRewards": {
+                "baker": 0.25,
+                "finalizationProof": 5.0e-3,
+                "accountCreation": 2.0e-2,
+                "chainUpdate": 5.0e-3
+            }
+        },
+        "foundationAccountIndex": 5,
+        "minimumThresholdForBaking": "15000000000"
+    }"#;
+
+    /// Chain parameters of protocol version 4 as reported by the node.
+    const CHAIN_PARAMETERS_V1_JSON: &str = r#"{
+        "electionDifficulty": 2.5e-2,
+        "euroPerEnergy": { "denominator": 1000000, "numerator": 1 },
+        "microGTUPerEuro": { "denominator": 1, "numerator": 500000 },
+        "poolOwnerCooldown": 10800,
+        "delegatorCooldown": 7200,
+        "rewardPeriodLength": 4,
+        "mintPerPayday": 2.61157877e-4,
+        "accountCreationLimit": 10,
+        "rewardParameters": {
+            "mintDistribution": { "bakingReward": 0.6, "finalizationReward": 0.3 },
+            "transactionFeeDistribution": { "baker": 0.45, "gasAccount": 0.45 },
+            "gASRewards": {
+                "baker": 0.25,
+                "finalizationProof": 5.0e-3,
+                "accountCreation": 2.0e-2,
+                "chainUpdate": 5.0e-3
+            }
+        },
+        "foundationAccountIndex": 5,
+        "passiveFinalizationCommission": 1.0,
+        "passiveBakingCommission": 0.12,
+        "passiveTransactionCommission": 0.12,
+        "finalizationCommissionRange": { "max": 1.0, "min": 1.0 },
+        "bakingCommissionRange": { "max": 0.1, "min": 0.1 },
+        "transactionCommissionRange": { "max": 0.1, "min": 0.1 },
+        "minimumEquityCapital": "14000000000",
+        "capitalBound": 0.25,
+        "leverageBound": { "denominator": 1, "numerator": 3 }
+    }"#;
+
+    #[test]
+    fn test_chain_parameters_node_json() {
+        let params: ChainParametersV0 = serde_json::from_str(CHAIN_PARAMETERS_V0_JSON)
+            .expect("Chain parameters of the node deserialize.");
+        assert_eq!(
+            params.election_difficulty,
+            ElectionDifficulty::new_unchecked(2_500)
+        );
+        assert_eq!(params.baker_cooldown_epochs, Epoch { epoch: 166 });
+        assert_eq!(params.foundation_account_index, AccountIndex { index: 5 });
+        assert_eq!(
+            params.minimum_threshold_for_baking,
+            Amount::from_ccd(15_000)
+        );
+        let mint_per_slot = params.reward_parameters.mint_distribution.mint_per_slot;
+        assert_eq!(
+            (mint_per_slot.mantissa, mint_per_slot.exponent),
+            (7555665, 16)
+        );
+        assert_eq!(
+            params.reward_parameters.gas_rewards.finalization_proof,
+            AmountFraction::new_unchecked(500)
+        );
+        let deserialized =
+            crypto_common::serialize_deserialize(&params).expect("Chain parameters deserialize.");
+        assert_eq!(
+            crypto_common::to_bytes(&deserialized),
+            crypto_common::to_bytes(&params)
+        );
+        let json = serde_json::to_string(&params).unwrap();
+        let from_json: ChainParametersV0 = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            crypto_common::to_bytes(&from_json),
+            crypto_common::to_bytes(&params)
+        );
+
+        let params: ChainParametersV1 = serde_json::from_str(CHAIN_PARAMETERS_V1_JSON)
+            .expect("Chain parameters of the node deserialize.");
+        assert_eq!(
+            params.cooldown_parameters.pool_owner_cooldown,
+            DurationSeconds { seconds: 10800 }
+        );
+        assert_eq!(
+            params.cooldown_parameters.delegator_cooldown,
+            DurationSeconds { seconds: 7200 }
+        );
+        let mint_per_payday = params.time_parameters.mint_per_payday;
+        assert_eq!(
+            (mint_per_payday.mantissa, mint_per_payday.exponent),
+            (261157877, 12)
+        );
+        assert_eq!(params.foundation_account_index, AccountIndex { index: 5 });
+        assert_eq!(
+            params.pool_parameters.passive_baking_commission,
+            AmountFraction::new_unchecked(12_000)
+        );
+        assert_eq!(
+            params.pool_parameters.commission_bounds.finalization.max,
+            AmountFraction::new_unchecked(100_000)
+        );
+        assert_eq!(
+            params.pool_parameters.minimum_equity_capital,
+            Amount::from_ccd(14_000)
+        );
+        let deserialized =
+            crypto_common::serialize_deserialize(&params).expect("Chain parameters deserialize.");
+        assert_eq!(
+            crypto_common::to_bytes(&deserialized),
+            crypto_common::to_bytes(&params)
+        );
+        let json = serde_json::to_string(&params).unwrap();
+        let from_json: ChainParametersV1 = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            crypto_common::to_bytes(&from_json),
+            crypto_common::to_bytes(&params)
+        );
+    }
+
+    #[test]
+    fn test_apply_chain_parameter_updates() {
+        let params: ChainParametersV0 = serde_json::from_str(CHAIN_PARAMETERS_V0_JSON).unwrap();
+        let foundation_account = AccountAddress([1u8; 32]);
+        let account_index = |addr: &AccountAddress| {
+            if *addr == foundation_account {
+                Some(AccountIndex { index: 7 })
+            } else {
+                None
+            }
+        };
+
+        let updated = params
+            .apply(
+                &UpdatePayload::ElectionDifficulty(ElectionDifficulty::new_unchecked(30_000)),
+                account_index,
+            )
+            .expect("Election difficulty updates apply to all chain parameters.");
+        assert_eq!(
+            updated.election_difficulty,
+            ElectionDifficulty::new_unchecked(30_000)
+        );
+        let updated = updated
+            .apply(
+                &UpdatePayload::BakerStakeThreshold(BakerParameters {
+                    minimum_threshold_for_baking: Amount::from_ccd(20_000),
+                }),
+                account_index,
+            )
+            .expect("The baker stake threshold is a version 0 parameter.");
+        assert_eq!(
+            updated.minimum_threshold_for_baking,
+            Amount::from_ccd(20_000)
+        );
+        assert_eq!(
+            crypto_common::to_bytes(&updated.reward_parameters),
+            crypto_common::to_bytes(&params.reward_parameters),
+            "Other parameters are unchanged."
+        );
+        let updated = updated
+            .apply(
+                &UpdatePayload::FoundationAccount(foundation_account),
+                account_index,
+            )
+            .expect("The foundation account exists.");
+        assert_eq!(updated.foundation_account_index, AccountIndex { index: 7 });
+        assert!(matches!(
+            updated.apply(
+                &UpdatePayload::FoundationAccount(AccountAddress([2u8; 32])),
+                account_index
+            ),
+            Err(InapplicableUpdate::UnknownFoundationAccount(_))
+        ));
+        assert!(matches!(
+            updated.apply(
+                &UpdatePayload::MintDistributionCPV1(MintDistributionV1 {
+                    baking_reward:       AmountFraction::new_unchecked(60_000),
+                    finalization_reward: AmountFraction::new_unchecked(30_000),
+                }),
+                account_index
+            ),
+            Err(InapplicableUpdate::WrongVersion {
+                update_type: UpdateType::UpdateMintDistribution,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_apply_update_keys_updates() {
+        let mut rng = rand::thread_rng();
+        let key_pair = UpdateKeyPair::generate(&mut rng);
+        let new_key_pair = UpdateKeyPair::generate(&mut rng);
+        let threshold = UpdateKeysThreshold::try_from(1).unwrap();
+        let access = AccessStructure {
+            authorized_keys: [UpdateKeysIndex { index: 0 }].into_iter().collect(),
+            threshold,
+        };
+        let higher_level = |key_pair: &UpdateKeyPair| HigherLevelAccessStructure {
+            keys: vec![UpdatePublicKey::from(key_pair)],
+            threshold,
+            _phantom: Default::default(),
+        };
+        let keys = UpdateKeysCollectionV0 {
+            root_keys:    higher_level(&key_pair),
+            level_1_keys: higher_level(&key_pair),
+            level_2_keys: AuthorizationsV0 {
+                keys: vec![UpdatePublicKey::from(&key_pair)],
+                emergency: access.clone(),
+                protocol: access.clone(),
+                election_difficulty: access.clone(),
+                euro_per_energy: access.clone(),
+                micro_gtu_per_euro: access.clone(),
+                foundation_account: access.clone(),
+                mint_distribution: access.clone(),
+                transaction_fee_distribution: access.clone(),
+                param_gas_rewards: access.clone(),
+                pool_parameters: access.clone(),
+                add_anonymity_revoker: access.clone(),
+                add_identity_provider: access,
+            },
+        };
+
+        let updated = keys
+            .apply(&UpdatePayload::Level1(Level1Update::Level1KeysUpdate(
+                higher_level(&new_key_pair),
+            )))
+            .expect("Level 1 keys are keys of all versions.");
+        assert_eq!(
+            crypto_common::to_bytes(&updated.level_1_keys),
+            crypto_common::to_bytes(&higher_level(&new_key_pair))
+        );
+        assert_eq!(
+            crypto_common::to_bytes(&updated.root_keys),
+            crypto_common::to_bytes(&keys.root_keys),
+            "Other keys are unchanged."
+        );
+        let unchanged = updated
+            .apply(&UpdatePayload::ElectionDifficulty(
+                ElectionDifficulty::new_unchecked(30_000),
+            ))
+            .expect("Chain parameter updates leave the keys unchanged.");
+        assert_eq!(
+            crypto_common::to_bytes(&unchanged),
+            crypto_common::to_bytes(&updated)
+        );
+
+        let deserialized =
+            crypto_common::serialize_deserialize(&updated).expect("Update keys deserialize.");
+        assert_eq!(
+            crypto_common::to_bytes(&deserialized),
+            crypto_common::to_bytes(&updated)
+        );
+        let json = serde_json::to_string(&updated).unwrap();
+        let from_json: UpdateKeysCollectionV0 = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            crypto_common::to_bytes(&from_json),
+            crypto_common::to_bytes(&updated)
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Batch verification of ed25519 signatures.
+//!
+//! Instead of checking the verification equation `s B = R + h A` of each
+//! signature separately, the equations of all signatures are combined with
+//! random 128-bit weights `z_i` into the single check
+//! `(-sum z_i s_i) B + sum z_i R_i + sum (z_i h_i) A_i = 0` which is evaluated
+//! with one multi-exponentiation.
+//!
+//! Individual verification in `ed25519-dalek` is cofactorless and compares the
+//! encoding of `s B - h A` with the bytes of `R`. To make sure that the batch
+//! check accepts no signature that individual verification rejects, signatures
+//! whose `R` or key `A` is not canonically encoded, or has a small order
+//! component, are rejected before batching. All the points in the combined
+//! check are then in the prime order subgroup, so that it holds if and only if
+//! (with overwhelming probability) each of the individual equations holds.
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use rand::{CryptoRng, Rng};
+use sha2::{Digest, Sha512};
+
+/// Verify all the given signatures at once. If this returns `true` then (with
+/// overwhelming probability) all the signatures are valid signatures on the
+/// respective message by the respective key. The converse does not quite hold:
+/// a signature whose key or `R` has a small order (torsion) component is
+/// rejected even if it verifies individually, see the [module
+/// documentation](self). If the result is `false` it is not known which of the
+/// signatures are invalid, see [`verify_batch_with_fallback`].
+pub fn verify_batch<R: Rng + CryptoRng, M: AsRef<[u8]>>(
+    csprng: &mut R,
+    items: &[(PublicKey, M, Signature)],
+) -> bool {
+    let mut scalars = Vec::with_capacity(2 * items.len() + 1);
+    let mut points = Vec::with_capacity(2 * items.len() + 1);
+    let mut basepoint_scalar = Scalar::zero();
+    for (public_key, message, signature) in items {
+        let bytes = signature.to_bytes();
+        let mut r_bytes = [0u8; 32];
+        r_bytes.copy_from_slice(&bytes[..32]);
+        let mut s_bytes = [0u8; 32];
+        s_bytes.copy_from_slice(&bytes[32..]);
+        let r = match decompress_strict(r_bytes) {
+            Some(r) => r,
+            None => return false,
+        };
+        let s = match Scalar::from_canonical_bytes(s_bytes) {
+            Some(s) => s,
+            None => return false,
+        };
+        let a = match decompress_strict(public_key.to_bytes()) {
+            Some(a) => a,
+            None => return false,
+        };
+        let h = challenge(&r_bytes, public_key, message.as_ref());
+        // A 128-bit weight suffices for a soundness error of 2^-128.
+        let mut z_bytes = [0u8; 32];
+        csprng.fill_bytes(&mut z_bytes[..16]);
+        let z = Scalar::from_bits(z_bytes);
+        basepoint_scalar -= z * s;
+        scalars.push(z);
+        points.push(r);
+        scalars.push(z * h);
+        points.push(a);
+    }
+    scalars.push(basepoint_scalar);
+    points.push(ED25519_BASEPOINT_POINT);
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity()
+}
+
+/// Decompress the point, returning [`None`] if the encoding is not canonical
+/// or the point is not in the prime order subgroup.
+fn decompress_strict(bytes: [u8; 32]) -> Option<EdwardsPoint> {
+    let point = CompressedEdwardsY(bytes).decompress()?;
+    if point.compress().to_bytes() == bytes && point.is_torsion_free() {
+        Some(point)
+    } else {
+        None
+    }
+}
+
+/// The hash `h` of the verification equation `s B = R + h A`.
+fn challenge(r_bytes: &[u8; 32], public_key: &PublicKey, message: &[u8]) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(r_bytes);
+    hasher.update(public_key.as_bytes());
+    hasher.update(message);
+    let mut h_bytes = [0u8; 64];
+    h_bytes.copy_from_slice(&hasher.finalize());
+    Scalar::from_bytes_mod_order_wide(&h_bytes)
+}
+
+/// Verify all the given signatures at once, and if that fails verify each of
+/// them individually to find the invalid ones. The indices of the invalid
+/// signatures are returned in increasing order in the error case.
+pub fn verify_batch_with_fallback<R: Rng + CryptoRng, M: AsRef<[u8]>>(
+    csprng: &mut R,
+    items: &[(PublicKey, M, Signature)],
+) -> Result<(), Vec<usize>> {
+    if verify_batch(csprng, items) {
+        return Ok(());
+    }
+    let invalid = items
+        .iter()
+        .enumerate()
+        .filter(|(_, (public_key, message, signature))| {
+            public_key.verify(message.as_ref(), signature).is_err()
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if invalid.is_empty() {
+        // This only happens for signatures with keys that have a small order
+        // component, which are rejected by the batch check but may be
+        // accepted by the individual verification.
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::EIGHT_TORSION;
+    use ed25519_dalek::{ExpandedSecretKey, Keypair, Signer};
+    use rand::{thread_rng, RngCore};
+    use std::convert::TryFrom;
+
+    #[test]
+    pub fn test_verify_batch() {
+        let mut csprng = thread_rng();
+        let mut items = Vec::new();
+        for i in 0..20u8 {
+            let keypair = Keypair::generate(&mut csprng);
+            let message = vec![i; usize::from(i)];
+            let signature = keypair.sign(&message);
+            items.push((keypair.public, message, signature));
+        }
+        assert!(verify_batch(&mut csprng, &items));
+        assert_eq!(verify_batch_with_fallback(&mut csprng, &items), Ok(()));
+        assert!(verify_batch::<_, Vec<u8>>(&mut csprng, &[]));
+
+        items[3].1.push(0);
+        let other_key = items[5].0;
+        items[11].0 = other_key;
+        assert!(!verify_batch(&mut csprng, &items));
+        assert_eq!(
+            verify_batch_with_fallback(&mut csprng, &items),
+            Err(vec![3, 11])
+        );
+    }
+
+    #[test]
+    pub fn test_verify_batch_small_order_r() {
+        let mut csprng = thread_rng();
+        let keypair = Keypair::generate(&mut csprng);
+        let mut secret_bytes = [0u8; 32];
+        secret_bytes.copy_from_slice(&ExpandedSecretKey::from(&keypair.secret).to_bytes()[..32]);
+        let secret = Scalar::from_bits(secret_bytes);
+        let message = b"message";
+        // Sign with a nonce commitment that has a small order component. The
+        // cofactored verification equation holds, the cofactorless one does
+        // not.
+        let mut nonce_bytes = [0u8; 64];
+        csprng.fill_bytes(&mut nonce_bytes);
+        let nonce = Scalar::from_bytes_mod_order_wide(&nonce_bytes);
+        let r = ED25519_BASEPOINT_POINT * nonce + EIGHT_TORSION[1];
+        let r_bytes = r.compress().to_bytes();
+        let s = nonce + challenge(&r_bytes, &keypair.public, message) * secret;
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes[..32].copy_from_slice(&r_bytes);
+        signature_bytes[32..].copy_from_slice(s.as_bytes());
+        let signature =
+            Signature::try_from(&signature_bytes[..]).expect("Signature is well formed.");
+        assert!(keypair.public.verify(message, &signature).is_err());
+
+        let mut items = vec![(keypair.public, message.to_vec(), keypair.sign(message))];
+        assert!(verify_batch(&mut csprng, &items));
+        items.push((keypair.public, message.to_vec(), signature));
+        assert!(!verify_batch(&mut csprng, &items));
+        assert_eq!(
+            verify_batch_with_fallback(&mut csprng, &items),
+            Err(vec![1])
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Baby step giant step tables stored in files so that they can be computed
+//! once and shared between processes and between decryptions.
+//!
+//! The file consists of a header followed by the table entries. The header is
+//!
+//! - the 7 byte magic string `CCDBSGS` followed by a one byte format version,
+//! - the serialization of [`Curve::one_point`], which identifies the curve,
+//! - the generator the table is for,
+//! - the chunk size in bits of the values the table is intended to decrypt,
+//! - the number of entries `m` as a big endian `u64`.
+//!
+//! Each of the `m` entries consists of an 8 byte key, which is the last 8
+//! bytes of the serialization of `j * generator`, followed by `j` as a big
+//! endian `u32`. The entries are sorted by key, so that lookups can be done by
+//! binary search directly in the file contents. Since keys are truncated a
+//! match is confirmed by recomputing the point, which is only needed on
+//! candidate matches.
+use crate::{elgamal::ChunkSize, secret::DiscreteLog};
+use crypto_common::*;
+use curve_arithmetic::Curve;
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+const MAGIC: [u8; 7] = *b"CCDBSGS";
+const VERSION: u8 = 1;
+/// Size of a single entry, an 8 byte key and a 4 byte value.
+const ENTRY_LENGTH: usize = 12;
+
+/// Errors that can occur when loading a baby step giant step table.
+#[derive(Debug, Error)]
+pub enum BsgsTableError {
+    #[error("Could not read the table: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The data is not a baby step giant step table of a supported version.")]
+    InvalidHeader,
+    #[error("The table is for a different curve.")]
+    WrongCurve,
+    #[error("The generator of the table is not a valid group element.")]
+    InvalidGenerator,
+    #[error("Unsupported chunk size {0}.")]
+    InvalidChunkSize(u8),
+    #[error("Table size {0} is not supported, it must be between 1 and 2^32.")]
+    InvalidSize(u64),
+    #[error("The table has {actual} bytes, but {expected} bytes were expected.")]
+    WrongLength { expected: u64, actual: u64 },
+}
+
+/// The bytes of a table, either owned or mapped from a file.
+enum TableBytes {
+    Owned(Vec<u8>),
+    Static(&'static [u8]),
+    Mapped(memmap2::Mmap),
+}
+
+impl AsRef<[u8]> for TableBytes {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            TableBytes::Owned(bytes) => bytes,
+            TableBytes::Static(bytes) => bytes,
+            TableBytes::Mapped(mapping) => mapping,
+        }
+    }
+}
+
+/// A baby step giant step table in the format described in the [module
+/// documentation](self). In contrast to
+/// [`BabyStepGiantStep`](crate::BabyStepGiantStep) loading the table does not
+/// require building a hash map, and when the table is mapped from a file only
+/// the parts of it that are used in lookups are read.
+///
+/// The header of the table is checked when it is loaded, but the entries are
+/// not, so the table should come from a trusted source, e.g., it should be
+/// generated by [`write_table`](Self::write_table) on the same machine.
+pub struct MappedBabyStepGiantStep<C: Curve> {
+    bytes:         TableBytes,
+    /// Generator the table is for.
+    generator:     C,
+    /// Point generator^{-m}
+    inverse_point: C,
+    /// Chunk size of the values the table is intended for.
+    chunk_size:    ChunkSize,
+    /// Number of entries in the table.
+    m:             u64,
+}
+
+fn chunk_size_from_bits(bits: u8) -> Option<ChunkSize> {
+    use ChunkSize::*;
+    match bits {
+        1 => Some(One),
+        2 => Some(Two),
+        4 => Some(Four),
+        8 => Some(Eight),
+        16 => Some(Sixteen),
+        32 => Some(ThirtyTwo),
+        64 => Some(SixtyFour),
+        _ => None,
+    }
+}
+
+/// The key under which the point is stored in the table.
+fn point_key<C: Curve>(point: &C) -> u64 {
+    let bytes = to_bytes(point);
+    let mut key = [0u8; 8];
+    key.copy_from_slice(&bytes[bytes.len() - 8..]);
+    u64::from_be_bytes(key)
+}
+
+fn header_length<C: Curve>() -> usize { MAGIC.len() + 1 + 2 * C::GROUP_ELEMENT_LENGTH + 1 + 8 }
+
+impl<C: Curve> MappedBabyStepGiantStep<C> {
+    /// Compute the table for the given generator with `m` entries and write it
+    /// to `out`. The table can then be loaded with [`load`](Self::load),
+    /// [`map`](Self::map) or [`from_bytes`](Self::from_bytes). The table size
+    /// `m` must be between 1 and 2^32.
+    pub fn write_table<W: Write>(
+        out: &mut W,
+        generator: &C,
+        m: u64,
+        chunk_size: ChunkSize,
+    ) -> Result<(), BsgsTableError> {
+        if m == 0 || m > 1 << 32 {
+            return Err(BsgsTableError::InvalidSize(m));
+        }
+        let mut entries = Vec::with_capacity(m as usize);
+        let mut base_j = C::zero_point();
+        for j in 0..m {
+            entries.push((point_key(&base_j), j as u32));
+            base_j = base_j.plus_point(generator);
+        }
+        entries.sort_unstable();
+        let mut header = Vec::with_capacity(header_length::<C>());
+        header.extend_from_slice(&MAGIC);
+        header.push(VERSION);
+        header.put(&C::one_point());
+        header.put(generator);
+        header.push(u8::from(chunk_size));
+        header.put(&m);
+        out.write_all(&header)?;
+        let mut entry = [0u8; ENTRY_LENGTH];
+        for (key, value) in entries {
+            entry[..8].copy_from_slice(&key.to_be_bytes());
+            entry[8..].copy_from_slice(&value.to_be_bytes());
+            out.write_all(&entry)?;
+        }
+        Ok(())
+    }
+
+    /// Construct the table from its serialization, checking the header.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, BsgsTableError> {
+        Self::from_table_bytes(TableBytes::Owned(bytes))
+    }
+
+    /// Construct the table from a serialization that is embedded in the
+    /// binary, e.g., with `include_bytes!`, checking the header.
+    pub fn from_static_bytes(bytes: &'static [u8]) -> Result<Self, BsgsTableError> {
+        Self::from_table_bytes(TableBytes::Static(bytes))
+    }
+
+    /// Read the table from the given file into memory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BsgsTableError> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Map the table from the given file into memory. Only the header is read
+    /// when the table is loaded, and the entries are read by the operating
+    /// system as they are needed in lookups.
+    ///
+    /// # Safety
+    /// The file must not be modified while the table is in use.
+    pub unsafe fn map<P: AsRef<Path>>(path: P) -> Result<Self, BsgsTableError> {
+        let file = File::open(path)?;
+        Self::from_table_bytes(TableBytes::Mapped(memmap2::Mmap::map(&file)?))
+    }
+
+    fn from_table_bytes(bytes: TableBytes) -> Result<Self, BsgsTableError> {
+        let data = bytes.as_ref();
+        let header_length = header_length::<C>();
+        if data.len() < header_length || data[..MAGIC.len()] != MAGIC {
+            return Err(BsgsTableError::InvalidHeader);
+        }
+        if data[MAGIC.len()] != VERSION {
+            return Err(BsgsTableError::InvalidHeader);
+        }
+        let curve_start = MAGIC.len() + 1;
+        let generator_start = curve_start + C::GROUP_ELEMENT_LENGTH;
+        let chunk_size_start = generator_start + C::GROUP_ELEMENT_LENGTH;
+        if data[curve_start..generator_start] != to_bytes(&C::one_point())[..] {
+            return Err(BsgsTableError::WrongCurve);
+        }
+        let generator: C = from_bytes(&mut Cursor::new(&data[generator_start..chunk_size_start]))
+            .map_err(|_| BsgsTableError::InvalidGenerator)?;
+        let chunk_size = chunk_size_from_bits(data[chunk_size_start])
+            .ok_or(BsgsTableError::InvalidChunkSize(data[chunk_size_start]))?;
+        let m = u64::from_be_bytes(
+            data[chunk_size_start + 1..header_length]
+                .try_into()
+                .expect("Slice has length 8."),
+        );
+        if m == 0 || m > 1 << 32 {
+            return Err(BsgsTableError::InvalidSize(m));
+        }
+        let expected = header_length as u64 + m * ENTRY_LENGTH as u64;
+        if data.len() as u64 != expected {
+            return Err(BsgsTableError::WrongLength {
+                expected,
+                actual: data.len() as u64,
+            });
+        }
+        let inverse_point = generator
+            .mul_by_scalar(&C::scalar_from_u64(m))
+            .inverse_point();
+        Ok(Self {
+            bytes,
+            generator,
+            inverse_point,
+            chunk_size,
+            m,
+        })
+    }
+
+    /// The generator the table is for.
+    pub fn generator(&self) -> &C { &self.generator }
+
+    /// The chunk size of the values the table is intended for.
+    pub fn chunk_size(&self) -> ChunkSize { self.chunk_size }
+
+    /// The number of entries in the table.
+    pub fn size(&self) -> u64 { self.m }
+
+    /// Look up the point in the table, returning `j` such that `point = j *
+    /// generator` if `j < m`.
+    fn lookup(&self, point: &C) -> Option<u64> {
+        let entries = &self.bytes.as_ref()[header_length::<C>()..];
+        let key_at = |i: usize| {
+            let start = i * ENTRY_LENGTH;
+            u64::from_be_bytes(
+                entries[start..start + 8]
+                    .try_into()
+                    .expect("Slice has length 8."),
+            )
+        };
+        let key = point_key(point);
+        // Find the first entry with a key that is not less than the key.
+        let (mut low, mut high) = (0, self.m as usize);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if key_at(mid) < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        // Since keys are truncated several points can have the same key.
+        for i in low..self.m as usize {
+            if key_at(i) != key {
+                break;
+            }
+            let start = i * ENTRY_LENGTH + 8;
+            let j = u32::from_be_bytes(
+                entries[start..start + 4]
+                    .try_into()
+                    .expect("Slice has length 4."),
+            );
+            if self.generator.mul_by_scalar(&C::scalar_from_u64(j.into())) == *point {
+                return Some(j.into());
+            }
+        }
+        None
+    }
+
+    /// Compute the discrete log of `v` if it is a value of at most the chunk
+    /// size of the table, returning `None` otherwise. The performance is
+    /// linear in `l / m` where `l` is the value stored in the exponent of `v`,
+    /// and `m` is the size of the table.
+    pub fn try_discrete_log(&self, v: &C) -> Option<u64> {
+        let bound = 1u128 << u8::from(self.chunk_size);
+        let giant_steps = (bound + u128::from(self.m) - 1) / u128::from(self.m);
+        let mut y = *v;
+        for i in 0..giant_steps as u64 {
+            if let Some(j) = self.lookup(&y) {
+                return Some(i * self.m + j);
+            }
+            y = y.plus_point(&self.inverse_point);
+        }
+        None
+    }
+}
+
+impl<C: Curve> DiscreteLog<C> for MappedBabyStepGiantStep<C> {
+    fn discrete_log(&self, v: &C) -> u64 {
+        let mut y = *v;
+        for i in 0..=u64::MAX {
+            if let Some(j) = self.lookup(&y) {
+                return i * self.m + j;
+            }
+            y = y.plus_point(&self.inverse_point);
+        }
+        unreachable!("It should not be feasible to do 2^64 group additions.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BabyStepGiantStep;
+    use pairing::bls12_381::{G1, G2};
+    use rand::*;
+
+    #[test]
+    fn test_mapped_bsgs() {
+        let mut csprng = thread_rng();
+        let generator = G1::generate(&mut csprng);
+        let m = 1 << 10;
+        let mut bytes = Vec::new();
+        MappedBabyStepGiantStep::write_table(&mut bytes, &generator, m, ChunkSize::Sixteen)
+            .expect("Writing to a vector succeeds.");
+        let table = MappedBabyStepGiantStep::<G1>::from_bytes(bytes.clone())
+            .expect("The table is well-formed.");
+        assert_eq!(table.generator(), &generator);
+        assert_eq!(table.size(), m);
+        let in_memory = BabyStepGiantStep::new(&generator, m);
+        for _ in 0..20 {
+            let x = u64::from(csprng.gen::<u16>());
+            let v = generator.mul_by_scalar(&G1::scalar_from_u64(x));
+            assert_eq!(table.try_discrete_log(&v), Some(x));
+            assert_eq!(DiscreteLog::discrete_log(&table, &v), x);
+            assert_eq!(in_memory.discrete_log(&v), x);
+        }
+        let too_large = generator.mul_by_scalar(&G1::scalar_from_u64(1 << 16));
+        assert_eq!(table.try_discrete_log(&too_large), None);
+
+        let path = std::env::temp_dir().join(format!("bsgs-test-{}.bin", csprng.gen::<u64>()));
+        std::fs::write(&path, &bytes).expect("Can write the table.");
+        let mapped = unsafe { MappedBabyStepGiantStep::<G1>::map(&path) }
+            .expect("The file is a valid table.");
+        let v = generator.mul_by_scalar(&G1::scalar_from_u64(12345));
+        assert_eq!(mapped.try_discrete_log(&v), Some(12345));
+        drop(mapped);
+        std::fs::remove_file(&path).expect("Can remove the table.");
+
+        assert!(matches!(
+            MappedBabyStepGiantStep::<G2>::from_bytes(bytes.clone()),
+            Err(BsgsTableError::InvalidHeader) | Err(BsgsTableError::WrongCurve)
+        ));
+        assert!(matches!(
+            MappedBabyStepGiantStep::<G1>::from_bytes(bytes[..bytes.len() - 1].to_vec()),
+            Err(BsgsTableError::WrongLength { .. })
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 //! Implementation of elgamal public key encryption and decryption over a Curve.
 
+mod bsgs_table;
 mod cipher;
 mod elgamal;
 mod errors;
@@ -7,7 +8,7 @@ mod message;
 mod public;
 mod secret;
 
-pub use crate::{cipher::*, elgamal::*, message::*, public::*, secret::*};
+pub use crate::{bsgs_table::*, cipher::*, elgamal::*, message::*, public::*, secret::*};
 
 #[macro_use]
 extern crate crypto_common_derive;
@@ -114,6 +114,18 @@ impl<C: Curve> BabyStepGiantStep<C> {
     }
 }
 
+/// A precomputed table for computing discrete logarithms of small values with
+/// respect to a fixed generator.
+pub trait DiscreteLog<C: Curve> {
+    /// Compute `l` such that `v` is `l` times the generator of the table. If
+    /// `l` is too large this function will appear not to terminate.
+    fn discrete_log(&self, v: &C) -> u64;
+}
+
+impl<C: Curve> DiscreteLog<C> for BabyStepGiantStep<C> {
+    fn discrete_log(&self, v: &C) -> u64 { BabyStepGiantStep::discrete_log(self, v) }
+}
+
 impl<C: Curve> SecretKey<C> {
     pub fn decrypt(&self, c: &Cipher<C>) -> Message<C> {
         let x = c.0; // k * g
@@ -139,9 +151,9 @@ impl<C: Curve> SecretKey<C> {
     /// be represented in 64 bits, and are small enough. Otherwise this function
     /// will appear to not terminate.
     ///
-    /// This function takes an auxiliary instance of BabyStepGiantStep to speed
-    /// up decryption.
-    pub fn decrypt_exponent(&self, c: &Cipher<C>, bsgs: &BabyStepGiantStep<C>) -> u64 {
+    /// This function takes an auxiliary table, e.g., an instance of
+    /// BabyStepGiantStep, to speed up decryption.
+    pub fn decrypt_exponent(&self, c: &Cipher<C>, bsgs: &impl DiscreteLog<C>) -> u64 {
         let dec = self.decrypt(c).value;
         bsgs.discrete_log(&dec)
     }
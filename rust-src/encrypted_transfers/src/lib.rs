@@ -104,8 +104,12 @@ pub fn aggregate<C: Curve>(
 /// one that is used to contruct the table.
 ///
 /// If not, this function will (almost certainly) appear not to terminate.
-pub fn decrypt_amount<C: Curve>(
-    table: &BabyStepGiantStep<C>,
+///
+/// The table is either a [`BabyStepGiantStep`] computed in memory, or a
+/// [`MappedBabyStepGiantStep`] loaded from a file. In both cases the table
+/// should be reused for all the amounts that are decrypted.
+pub fn decrypt_amount<C: Curve, T: DiscreteLog<C>>(
+    table: &T,
     sk: &SecretKey<C>,
     amount: &EncryptedAmount<C>,
 ) -> Amount {
@@ -116,6 +120,28 @@ pub fn decrypt_amount<C: Curve>(
     )
 }
 
+/// Decrypt a single amount using a table loaded from a file.
+///
+/// In contrast to [`decrypt_amount`] this checks that the table is for the
+/// generator of the secret key and for the chunk size of encrypted amounts,
+/// and it returns `None` if one of the chunks is not a value of the chunk
+/// size, instead of appearing not to terminate.
+pub fn try_decrypt_amount<C: Curve>(
+    table: &MappedBabyStepGiantStep<C>,
+    generator: &C,
+    sk: &SecretKey<C>,
+    amount: &EncryptedAmount<C>,
+) -> Option<Amount> {
+    if table.generator() != generator || table.chunk_size() != CHUNK_SIZE_ENC_TRANS {
+        return None;
+    }
+    let low_chunk = table.try_discrete_log(&sk.decrypt(&amount.encryptions[0]).value)?;
+    let hi_chunk = table.try_discrete_log(&sk.decrypt(&amount.encryptions[1]).value)?;
+    Some(Amount::from_micro_ccd(
+        CHUNK_SIZE_ENC_TRANS.chunks_to_u64([low_chunk, hi_chunk].iter().copied()),
+    ))
+}
+
 impl<C: Curve> EncryptedAmount<C> {
     /// Join chunks of an encrypted amount into a single ciphertext.
     /// The resulting ciphertext will in general not be easily decryptable.
@@ -310,6 +336,35 @@ mod tests {
         );
     }
 
+    // Test decryption with a table that is shared between decryptions.
+    #[test]
+    fn test_decrypt_with_mapped_table() {
+        let mut csprng = thread_rng();
+        let context = GlobalContext::<G1>::generate(String::from("genesis_string"));
+        let sk = SecretKey::generate(context.elgamal_generator(), &mut csprng);
+        let pk = PublicKey::from(&sk);
+        let generator = context.encryption_in_exponent_generator();
+
+        let mut bytes = Vec::new();
+        MappedBabyStepGiantStep::write_table(&mut bytes, generator, 1 << 16, CHUNK_SIZE_ENC_TRANS)
+            .expect("Writing the table succeeds.");
+        let table = MappedBabyStepGiantStep::from_bytes(bytes).expect("The table is valid.");
+        for _ in 0..5 {
+            let amount = Amount::from_micro_ccd(csprng.gen::<u64>());
+            let (enc_amount, _) = encrypt_amount(&context, &pk, amount, &mut csprng);
+            assert_eq!(decrypt_amount(&table, &sk, &enc_amount), amount);
+            assert_eq!(
+                try_decrypt_amount(&table, generator, &sk, &enc_amount),
+                Some(amount)
+            );
+            assert_eq!(
+                try_decrypt_amount(&table, context.elgamal_generator(), &sk, &enc_amount),
+                None,
+                "A table for a different generator must be rejected."
+            );
+        }
+    }
+
     #[test]
     fn test_scale() {
         let mut csprng = thread_rng();
@@ -490,26 +490,14 @@ pub fn compute_sharing_data<'a, C: Curve>(
 ) -> SharingData<'a, C> {
     let n = ar_parameters.len() as u32;
     let mut csprng = thread_rng();
-    // first commit to the scalar
-    let (cmm_scalar, cmm_scalar_rand) = commitment_key.commit(&shared_scalar, &mut csprng);
     // We evaluate the polynomial at ar_identities.
     let share_points = ar_parameters.keys().copied();
     // share the scalar on ar_identity points.
     let sharing_data = share::<C, _, _, _>(shared_scalar, share_points, threshold, &mut csprng);
-    // commitments to the sharing coefficients
-    let mut cmm_sharing_coefficients: Vec<Commitment<C>> = Vec::with_capacity(threshold.into());
-    // first coefficient is the shared scalar
-    cmm_sharing_coefficients.push(cmm_scalar);
-    // randomness values corresponding to the commitments
-    let mut cmm_coeff_randomness = Vec::with_capacity(threshold.into());
-    // first randomness is the one used in commiting to the scalar
-    cmm_coeff_randomness.push(cmm_scalar_rand);
-    // fill the rest
-    for coeff in sharing_data.coefficients.iter() {
-        let (cmm, rnd) = commitment_key.commit(coeff, &mut csprng);
-        cmm_sharing_coefficients.push(cmm);
-        cmm_coeff_randomness.push(rnd);
-    }
+    // commitments to the sharing coefficients, the first of which is the shared
+    // scalar, and the randomness values corresponding to the commitments
+    let (cmm_sharing_coefficients, cmm_coeff_randomness) =
+        pedersen_commitments(commitment_key, shared_scalar, &sharing_data, &mut csprng);
     // a vector of Ar data
     let mut ar_data: Vec<SingleArData<C>> = Vec::with_capacity(n as usize);
     // The correctness of this relies on the invariant that the map of anonymity
@@ -572,26 +560,14 @@ pub fn compute_sharing_data_prf<'a, C: Curve>(
     let commitment_key = &global_context.on_chain_commitment_key;
     let n = ar_parameters.len() as u32;
     let mut csprng = thread_rng();
-    // first commit to the scalar
-    let (cmm_scalar, cmm_scalar_rand) = commitment_key.commit(&shared_scalar, &mut csprng);
     // We evaluate the polynomial at ar_identities.
     let share_points = ar_parameters.keys().copied();
     // share the scalar on ar_identity points.
     let sharing_data = share::<C, _, _, _>(shared_scalar, share_points, threshold, &mut csprng);
-    // commitments to the sharing coefficients
-    let mut cmm_sharing_coefficients: Vec<Commitment<C>> = Vec::with_capacity(threshold.into());
-    // first coefficient is the shared scalar
-    cmm_sharing_coefficients.push(cmm_scalar);
-    // randomness values corresponding to the commitments
-    let mut cmm_coeff_randomness = Vec::with_capacity(threshold.into());
-    // first randomness is the one used in commiting to the scalar
-    cmm_coeff_randomness.push(cmm_scalar_rand);
-    // fill the rest
-    for coeff in sharing_data.coefficients.iter() {
-        let (cmm, rnd) = commitment_key.commit(coeff, &mut csprng);
-        cmm_sharing_coefficients.push(cmm);
-        cmm_coeff_randomness.push(rnd);
-    }
+    // commitments to the sharing coefficients, the first of which is the shared
+    // scalar, and the randomness values corresponding to the commitments
+    let (cmm_sharing_coefficients, cmm_coeff_randomness) =
+        pedersen_commitments(commitment_key, shared_scalar, &sharing_data, &mut csprng);
     // a vector of Ar data
     let mut ar_data: Vec<SingleArDataPrf<C>> = Vec::with_capacity(n as usize);
     // The correctness of this relies on the invariant that the map of anonymity
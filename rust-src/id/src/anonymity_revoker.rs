@@ -1,7 +1,16 @@
 //! Functionality needed by the anonymity revoker.
-use crate::{secret_sharing::*, types::*};
+use crate::{
+    secret_sharing::*,
+    sigma_protocols::{common::*, dlog::*, dlog_eq},
+    types::*,
+};
+use crypto_common::*;
 use curve_arithmetic::*;
-use elgamal::Message;
+use elgamal::{Cipher, Message, SecretKey};
+use rand::Rng;
+use random_oracle::RandomOracle;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
 
 /// Reveal the `idCredPub` based on the given shares.
 /// It is important to remember that this always succeeds in computing
@@ -23,3 +32,258 @@ pub fn reveal_id_cred_pub<C: Curve>(shares: &[(ArIdentity, Message<C>)]) -> C {
 /// value is meaningful must be ensured by the caller, e.g., by making sure that
 /// the threshold is compatible with the number of shares.
 pub fn reveal_prf_key<C: Curve>(shares: &[(ArIdentity, Value<C>)]) -> C::Scalar { reveal(shares) }
+
+/// Proof that a decryption share is the decryption of the encrypted share with
+/// the secret key of the anonymity revoker.
+pub type DecryptionShareProof<C> = dlog_eq::Proof<C>;
+
+/// The decryption of the encrypted share of `idCredPub` of a single anonymity
+/// revoker, together with a proof that it was decrypted correctly. In contrast
+/// to [`ChainArDecryptedData`] this can be checked by whoever combines the
+/// shares, see [`combine_id_cred_pub_shares`].
+#[derive(Debug, Clone, Serialize, SerdeSerialize, SerdeDeserialize)]
+#[serde(bound(serialize = "C: Curve", deserialize = "C: Curve"))]
+pub struct IdCredPubDecryptionShare<C: Curve> {
+    /// identity of the anonymity revoker
+    #[serde(rename = "arIdentity")]
+    pub ar_identity:       ArIdentity,
+    /// share of id cred pub
+    #[serde(rename = "idCredPubShare")]
+    pub id_cred_pub_share: Message<C>,
+    /// proof that the share is the decryption of the encrypted share
+    #[serde(rename = "proof")]
+    pub proof:             DecryptionShareProof<C>,
+}
+
+/// The statement that `share` is the decryption of `enc_share` under the
+/// secret key of `public_key`. With the encryption `(c_1, c_2)` this is
+/// equality of the discrete logarithms of the public key with respect to the
+/// generator, and of `c_2 - share` with respect to `c_1`.
+fn decryption_statement<C: Curve>(
+    public_key: &ArPublicKey<C>,
+    enc_share: &Cipher<C>,
+    share: &Message<C>,
+) -> dlog_eq::DlogEqual<C> {
+    dlog_eq::DlogEqual {
+        dlog1: Dlog {
+            public: public_key.key,
+            coeff:  public_key.generator,
+        },
+        dlog2: Dlog {
+            public: enc_share.1.minus_point(&share.value),
+            coeff:  enc_share.0,
+        },
+    }
+}
+
+/// The context of the proof of a decryption share.
+fn decryption_share_ro<C: Curve>(ar_identity: ArIdentity, enc_share: &Cipher<C>) -> RandomOracle {
+    let mut ro = RandomOracle::domain("IdCredPubDecryptionShare");
+    ro.append_message("arIdentity", &ar_identity);
+    ro.append_message("encIdCredPubShare", enc_share);
+    ro
+}
+
+/// Decrypt the encrypted share of `idCredPub` of the anonymity revoker with the
+/// given identity and secret key, and prove that the decryption is correct.
+/// The encrypted share is found in the `ar_data` of the credential.
+pub fn make_id_cred_pub_decryption_share<C: Curve, R: Rng>(
+    csprng: &mut R,
+    ar_identity: ArIdentity,
+    ar_secret_key: &SecretKey<C>,
+    enc_share: &Cipher<C>,
+) -> Option<IdCredPubDecryptionShare<C>> {
+    let id_cred_pub_share = ar_secret_key.decrypt(enc_share);
+    let public_key = ArPublicKey::from(ar_secret_key);
+    let statement = decryption_statement(&public_key, enc_share, &id_cred_pub_share);
+    let secret = DlogSecret {
+        secret: Value::new(ar_secret_key.scalar),
+    };
+    let proof = prove(
+        &mut decryption_share_ro(ar_identity, enc_share),
+        &statement,
+        secret,
+        csprng,
+    )?;
+    Some(IdCredPubDecryptionShare {
+        ar_identity,
+        id_cred_pub_share,
+        proof,
+    })
+}
+
+/// Check that the decryption share is the decryption of the encrypted share
+/// under the secret key corresponding to the public key of the anonymity
+/// revoker.
+pub fn verify_id_cred_pub_decryption_share<C: Curve>(
+    ar_public_key: &ArPublicKey<C>,
+    enc_share: &Cipher<C>,
+    share: &IdCredPubDecryptionShare<C>,
+) -> bool {
+    let statement = decryption_statement(ar_public_key, enc_share, &share.id_cred_pub_share);
+    verify(
+        &mut decryption_share_ro(share.ar_identity, enc_share),
+        &statement,
+        &share.proof,
+    )
+}
+
+/// Reasons why decryption shares cannot be combined into `idCredPub`.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CombineSharesError {
+    #[error("Anonymity revoker {0} is not an anonymity revoker of the credential.")]
+    UnknownAr(ArIdentity),
+    #[error("The public key of anonymity revoker {0} is not known.")]
+    MissingPublicKey(ArIdentity),
+    #[error("Anonymity revoker {0} submitted more than one share.")]
+    DuplicateShare(ArIdentity),
+    #[error("The decryption shares of anonymity revokers {0:?} are invalid.")]
+    InvalidShares(Vec<ArIdentity>),
+    #[error("There are {actual} shares, but the revocation threshold is {threshold}.")]
+    InsufficientShares {
+        threshold: Threshold,
+        actual:    usize,
+    },
+}
+
+/// Combine decryption shares of the anonymity revokers of a credential into
+/// `idCredPub`. The `ar_data` and `threshold` are the ones of the credential,
+/// and `ar_keys` must contain the public keys of the anonymity revokers that
+/// submitted shares.
+///
+/// In contrast to [`reveal_id_cred_pub`] every share is checked before the
+/// shares are combined, so that a malicious anonymity revoker cannot make
+/// the result wrong by submitting an incorrect share. If some shares are
+/// invalid the error lists all the anonymity revokers that submitted them, so
+/// that the remaining shares can be combined if there are enough of them.
+pub fn combine_id_cred_pub_shares<C: Curve, A: HasArPublicKey<C>>(
+    ar_data: &BTreeMap<ArIdentity, ChainArData<C>>,
+    threshold: Threshold,
+    ar_keys: &BTreeMap<ArIdentity, A>,
+    shares: &[IdCredPubDecryptionShare<C>],
+) -> Result<C, CombineSharesError> {
+    let mut seen = BTreeSet::new();
+    let mut invalid = Vec::new();
+    for share in shares {
+        let ar_identity = share.ar_identity;
+        if !seen.insert(ar_identity) {
+            return Err(CombineSharesError::DuplicateShare(ar_identity));
+        }
+        let enc_share = &ar_data
+            .get(&ar_identity)
+            .ok_or(CombineSharesError::UnknownAr(ar_identity))?
+            .enc_id_cred_pub_share;
+        let public_key = ar_keys
+            .get(&ar_identity)
+            .ok_or(CombineSharesError::MissingPublicKey(ar_identity))?
+            .get_public_key();
+        if !verify_id_cred_pub_decryption_share(public_key, enc_share, share) {
+            invalid.push(ar_identity);
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(CombineSharesError::InvalidShares(invalid));
+    }
+    if shares.len() < usize::from(u8::from(threshold)) {
+        return Err(CombineSharesError::InsufficientShares {
+            threshold,
+            actual: shares.len(),
+        });
+    }
+    Ok(reveal_in_group(
+        &shares
+            .iter()
+            .map(|share| (share.ar_identity, share.id_cred_pub_share.value))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elgamal::PublicKey;
+    use pairing::bls12_381::G1;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_combine_id_cred_pub_shares() {
+        let mut csprng = thread_rng();
+        let generator = G1::generate(&mut csprng);
+        let id_cred_sec = G1::generate_scalar(&mut csprng);
+        let threshold = Threshold(3);
+        let ar_identities = (1..=5).map(ArIdentity::new).collect::<Vec<_>>();
+        let sharing = share::<G1, _, _, _>(
+            &id_cred_sec,
+            ar_identities.iter().copied(),
+            threshold,
+            &mut csprng,
+        );
+        let mut ar_secret_keys = BTreeMap::new();
+        let mut ar_keys = BTreeMap::new();
+        let mut ar_data = BTreeMap::new();
+        for (&ar_identity, share) in ar_identities.iter().zip(sharing.shares.iter()) {
+            let sk = SecretKey::generate(&generator, &mut csprng);
+            let pk = PublicKey::from(&sk);
+            let message = Message {
+                value: generator.mul_by_scalar(share),
+            };
+            ar_data.insert(ar_identity, ChainArData {
+                enc_id_cred_pub_share: pk.encrypt(&mut csprng, &message),
+            });
+            ar_secret_keys.insert(ar_identity, sk);
+            ar_keys.insert(ar_identity, pk);
+        }
+        let mut shares = ar_identities[1..]
+            .iter()
+            .map(|ar_identity| {
+                make_id_cred_pub_decryption_share(
+                    &mut csprng,
+                    *ar_identity,
+                    &ar_secret_keys[ar_identity],
+                    &ar_data[ar_identity].enc_id_cred_pub_share,
+                )
+                .expect("Proving should succeed.")
+            })
+            .collect::<Vec<_>>();
+        let id_cred_pub = generator.mul_by_scalar(&id_cred_sec);
+        assert_eq!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &shares),
+            Ok(id_cred_pub)
+        );
+        assert_eq!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &shares[..2]),
+            Err(CombineSharesError::InsufficientShares {
+                threshold,
+                actual: 2,
+            })
+        );
+
+        // A revoker that submits a wrong share is detected.
+        shares[1].id_cred_pub_share = Message {
+            value: shares[1].id_cred_pub_share.value.plus_point(&generator),
+        };
+        assert_eq!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &shares),
+            Err(CombineSharesError::InvalidShares(vec![ar_identities[2]]))
+        );
+        let honest = vec![shares[0].clone(), shares[2].clone(), shares[3].clone()];
+        assert_eq!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &honest),
+            Ok(id_cred_pub)
+        );
+
+        // The proof is bound to the revoker that made it.
+        let mut stolen = honest.clone();
+        stolen[0].ar_identity = ar_identities[0];
+        assert!(matches!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &stolen),
+            Err(CombineSharesError::InvalidShares(_))
+        ));
+        let mut duplicate = honest;
+        duplicate.push(duplicate[0].clone());
+        assert_eq!(
+            combine_id_cred_pub_shares(&ar_data, threshold, &ar_keys, &duplicate),
+            Err(CombineSharesError::DuplicateShare(ar_identities[1]))
+        );
+    }
+}
@@ -5,13 +5,17 @@ use crate::{
     types::*,
     utils,
 };
-use bulletproofs::range_proof::verify_less_than_or_equal;
+use bulletproofs::{
+    range_proof::{verification_check_less_than_or_equal, verify_less_than_or_equal},
+    utils::MultiExpCheck,
+};
 use core::fmt::{self, Display};
 use crypto_common::{to_bytes, types::TransactionTime};
 use curve_arithmetic::{Curve, Pairing};
 use ed25519_dalek::Verifier;
 use either::Either;
 use pedersen_scheme::{Commitment, CommitmentKey, Randomness, Value};
+use rand::{CryptoRng, Rng};
 use random_oracle::RandomOracle;
 use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, BTreeSet};
@@ -59,6 +63,124 @@ pub fn verify_cdi<
     cdi: &CredentialDeploymentInfo<P, C, AttributeType>,
     new_or_existing: &Either<TransactionTime, AccountAddress>,
 ) -> Result<(), CdiVerificationError> {
+    let mut ro = verify_cdi_sigma_proof(global_context, ip_info, known_ars, cdi, new_or_existing)?;
+
+    if !verify_less_than_or_equal(
+        &mut ro,
+        8,
+        &cdi.proofs.id_proofs.commitments.cmm_cred_counter,
+        &cdi.proofs.id_proofs.commitments.cmm_max_accounts,
+        &cdi.proofs.id_proofs.cred_counter_less_than_max_accounts,
+        global_context.bulletproof_generators(),
+        &global_context.on_chain_commitment_key,
+    ) {
+        return Err(CdiVerificationError::Proof);
+    }
+    verify_cdi_ownership_and_policy(global_context, cdi, new_or_existing)
+}
+
+/// Verify many credential deployments at once. The result is the same as
+/// verifying each of them with [`verify_cdi`], including the reported errors,
+/// but the range proofs of all the credentials are checked together in a
+/// single multi-exponentiation. If the combined check fails the range proofs
+/// are checked one by one to find the credentials with invalid proofs. The
+/// errors of all credentials that are not valid are returned together with
+/// their index, in increasing order.
+///
+/// Only the range proofs are batched. The sigma protocol proof of each
+/// credential is verified on its own when its range proof check is computed,
+/// since the range proof is verified in the state of the random oracle after
+/// the sigma protocol proof. The sigma protocol proofs consist of challenges
+/// and responses, so the commitments that a combined check would need are not
+/// part of the proof.
+#[allow(clippy::type_complexity)]
+pub fn verify_cdi_batch<
+    P: Pairing,
+    C: Curve<Scalar = P::ScalarField>,
+    AttributeType: Attribute<C::Scalar>,
+    A: HasArPublicKey<C>,
+    R: Rng + CryptoRng,
+>(
+    csprng: &mut R,
+    global_context: &GlobalContext<C>,
+    // NB: The following map only needs to be a superset of the ars
+    // in the cdis.
+    known_ars: &BTreeMap<ArIdentity, A>,
+    cdis: &[(
+        &IpInfo<P>,
+        &CredentialDeploymentInfo<P, C, AttributeType>,
+        &Either<TransactionTime, AccountAddress>,
+    )],
+) -> Result<(), Vec<(usize, CdiVerificationError)>> {
+    let mut errors = Vec::new();
+    // The range proof checks of the credentials that passed all other checks.
+    let mut checks = Vec::new();
+    for (i, &(ip_info, cdi, new_or_existing)) in cdis.iter().enumerate() {
+        let check =
+            verify_cdi_sigma_proof(global_context, ip_info, known_ars, cdi, new_or_existing)
+                .and_then(|mut ro| {
+                    verification_check_less_than_or_equal(
+                        csprng,
+                        &mut ro,
+                        8,
+                        &cdi.proofs.id_proofs.commitments.cmm_cred_counter,
+                        &cdi.proofs.id_proofs.commitments.cmm_max_accounts,
+                        &cdi.proofs.id_proofs.cred_counter_less_than_max_accounts,
+                        global_context.bulletproof_generators(),
+                        &global_context.on_chain_commitment_key,
+                    )
+                    .ok_or(CdiVerificationError::Proof)
+                })
+                .and_then(|check| {
+                    match verify_cdi_ownership_and_policy(global_context, cdi, new_or_existing) {
+                        Ok(()) => Ok(check),
+                        // [`verify_cdi`] reports an invalid range proof before
+                        // the other errors, so the check is evaluated on its
+                        // own to report the same error.
+                        Err(_) if !check.holds() => Err(CdiVerificationError::Proof),
+                        Err(e) => Err(e),
+                    }
+                });
+        match check {
+            Ok(check) => checks.push((i, check)),
+            Err(e) => errors.push((i, e)),
+        }
+    }
+    let mut combined = MultiExpCheck::default();
+    for (_, check) in checks.iter() {
+        combined.add_random(csprng, check);
+    }
+    if !combined.holds() {
+        errors.extend(
+            checks
+                .iter()
+                .filter(|(_, check)| !check.holds())
+                .map(|(i, _)| (*i, CdiVerificationError::Proof)),
+        );
+        errors.sort_unstable_by_key(|(i, _)| *i);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check the consistency of the credential and verify its sigma protocol
+/// proof. This returns the random oracle in the state in which the range proof
+/// of the credential counter must be verified.
+fn verify_cdi_sigma_proof<
+    P: Pairing,
+    C: Curve<Scalar = P::ScalarField>,
+    AttributeType: Attribute<C::Scalar>,
+    A: HasArPublicKey<C>,
+>(
+    global_context: &GlobalContext<C>,
+    ip_info: &IpInfo<P>,
+    known_ars: &BTreeMap<ArIdentity, A>,
+    cdi: &CredentialDeploymentInfo<P, C, AttributeType>,
+    new_or_existing: &Either<TransactionTime, AccountAddress>,
+) -> Result<RandomOracle, CdiVerificationError> {
     // We need to check that the threshold is actually equal to
     // the number of coefficients in the sharing polynomial
     // (corresponding to the degree+1)
@@ -75,7 +197,6 @@ pub fn verify_cdi<
         return Err(CdiVerificationError::Ar);
     }
     let on_chain_commitment_key = global_context.on_chain_commitment_key;
-    let gens = global_context.bulletproof_generators();
     let ip_verify_key = &ip_info.ip_verify_key;
     // Compute the challenge prefix by hashing the values.
     let mut ro = RandomOracle::domain("credential");
@@ -99,9 +220,6 @@ pub fn verify_cdi<
     // FIXME: Figure out a pattern to get rid of these clone's.
     let witness_reg_id = cdi.proofs.id_proofs.proof_reg_id.clone();
 
-    let cdv = &cdi.values;
-    let proofs = &cdi.proofs;
-
     let verifier_sig = pok_sig_verifier(
         &on_chain_commitment_key,
         cdi.values.threshold,
@@ -152,17 +270,22 @@ pub fn verify_cdi<
         return Err(CdiVerificationError::Proof);
     }
 
-    if !verify_less_than_or_equal(
-        &mut ro,
-        8,
-        &cdi.proofs.id_proofs.commitments.cmm_cred_counter,
-        &cdi.proofs.id_proofs.commitments.cmm_max_accounts,
-        &cdi.proofs.id_proofs.cred_counter_less_than_max_accounts,
-        gens,
-        &on_chain_commitment_key,
-    ) {
-        return Err(CdiVerificationError::Proof);
-    }
+    Ok(ro)
+}
+
+/// Verify the proof of ownership of the account keys of the credential, and
+/// that the policy matches the commitments to the attributes.
+fn verify_cdi_ownership_and_policy<
+    P: Pairing,
+    C: Curve<Scalar = P::ScalarField>,
+    AttributeType: Attribute<C::Scalar>,
+>(
+    global_context: &GlobalContext<C>,
+    cdi: &CredentialDeploymentInfo<P, C, AttributeType>,
+    new_or_existing: &Either<TransactionTime, AccountAddress>,
+) -> Result<(), CdiVerificationError> {
+    let cdv = &cdi.values;
+    let proofs = &cdi.proofs;
     let signed = utils::credential_hash_to_sign(cdv, &proofs.id_proofs, new_or_existing);
     // Notice that here we provide all the verification keys, and the
     // function `verify_accunt_ownership_proof` assumes that
@@ -176,7 +299,11 @@ pub fn verify_cdi<
         return Err(CdiVerificationError::AccountOwnership);
     }
 
-    let check_policy = verify_policy(&on_chain_commitment_key, commitments, &cdi.values.policy);
+    let check_policy = verify_policy(
+        &global_context.on_chain_commitment_key,
+        &proofs.id_proofs.commitments,
+        &cdi.values.policy,
+    );
 
     if !check_policy {
         return Err(CdiVerificationError::Policy);
@@ -484,6 +611,155 @@ mod tests {
         assert_eq!(cdi_check, Ok(()));
     }
 
+    #[test]
+    fn test_verify_cdi_batch() {
+        let mut csprng = thread_rng();
+
+        let max_attrs = 10;
+        let num_ars = 5;
+        let IpData {
+            public_ip_info: ip_info,
+            ip_secret_key,
+            ..
+        } = test_create_ip_info(&mut csprng, num_ars, max_attrs);
+        let global_ctx = GlobalContext::<G1>::generate(String::from("genesis_string"));
+        let (ars_infos, _) =
+            test_create_ars(&global_ctx.on_chain_commitment_key.g, num_ars, &mut csprng);
+        let id_use_data = test_create_id_use_data(&mut csprng);
+        let (context, pio, _) =
+            test_create_pio_v1(&id_use_data, &ip_info, &ars_infos, &global_ctx, num_ars);
+        let alist = test_create_attributes();
+        let ip_sig = verify_credentials_v1(&pio, context, &alist, &ip_secret_key)
+            .expect("The identity object should be signed.");
+        let id_object = IdentityObjectV1 {
+            pre_identity_object: pio,
+            alist,
+            signature: ip_sig,
+        };
+        let policy = Policy {
+            valid_to:   YearMonth::new(2022, 5).unwrap(),
+            created_at: YearMonth::new(2020, 5).unwrap(),
+            policy_vec: {
+                let mut tree = BTreeMap::new();
+                tree.insert(AttributeTag::from(8u8), AttributeKind::from(31));
+                tree
+            },
+            _phantom:   Default::default(),
+        };
+        let context = IpContext::new(&ip_info, &ars_infos, &global_ctx);
+        let new_account = Left(EXPIRY);
+        let cred_datas = (0..3)
+            .map(|_| CredentialData {
+                keys:      {
+                    let mut keys = BTreeMap::new();
+                    keys.insert(KeyIndex(0), KeyPair::generate(&mut csprng));
+                    keys
+                },
+                threshold: SignatureThreshold(1),
+            })
+            .collect::<Vec<_>>();
+        let mut cdis = cred_datas
+            .iter()
+            .enumerate()
+            .map(|(index, cred_data)| {
+                let (cdi, _) = create_credential(
+                    context,
+                    &id_object,
+                    &id_use_data,
+                    index as u8,
+                    policy.clone(),
+                    cred_data,
+                    &SystemAttributeRandomness {},
+                    &new_account,
+                )
+                .expect("Should generate the credential successfully.");
+                cdi
+            })
+            .collect::<Vec<_>>();
+        let batch = cdis
+            .iter()
+            .map(|cdi| (&ip_info, cdi, &new_account))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_cdi_batch(&mut csprng, &global_ctx, &ars_infos, &batch),
+            Ok(())
+        );
+
+        // The credentials are for new accounts, so their proofs do not verify
+        // for an existing account.
+        let existing_account = Right(account_address_from_registration_id(
+            &cdis[0].values.cred_id,
+        ));
+        let mut batch = batch;
+        batch[1].2 = &existing_account;
+        assert_eq!(
+            verify_cdi_batch(&mut csprng, &global_ctx, &ars_infos, &batch),
+            Err(vec![(1, CdiVerificationError::Proof)])
+        );
+        for (i, &(ip_info, cdi, new_or_existing)) in batch.iter().enumerate() {
+            assert_eq!(
+                verify_cdi(&global_ctx, ip_info, &ars_infos, cdi, new_or_existing).is_ok(),
+                i != 1,
+                "Batch verification agrees with individual verification."
+            );
+        }
+
+        // A credential whose range proof alone is wrong passes all other
+        // checks, so that only the combined check fails. The account keys sign
+        // the proofs, so the credential is signed again.
+        let mut unsigned = UnsignedCredentialDeploymentInfo {
+            values: cdis[2].values.clone(),
+            proofs: cdis[2].proofs.id_proofs.clone(),
+        };
+        unsigned.proofs.cred_counter_less_than_max_accounts = cdis[0]
+            .proofs
+            .id_proofs
+            .cred_counter_less_than_max_accounts
+            .clone();
+        cdis[2] = CredentialDeploymentInfo {
+            values: unsigned.values.clone(),
+            proofs: CredDeploymentProofs {
+                proof_acc_sk: AccountOwnershipProof {
+                    sigs: cred_datas[2].sign(&new_account, &unsigned),
+                },
+                id_proofs:    unsigned.proofs,
+            },
+        };
+        let batch = cdis
+            .iter()
+            .map(|cdi| (&ip_info, cdi, &new_account))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_cdi_batch(&mut csprng, &global_ctx, &ars_infos, &batch),
+            Err(vec![(2, CdiVerificationError::Proof)])
+        );
+        assert_eq!(
+            verify_cdi(&global_ctx, &ip_info, &ars_infos, &cdis[2], &new_account),
+            Err(CdiVerificationError::Proof)
+        );
+
+        // If the credential is not signed again both the range proof and the
+        // account ownership proof are wrong, and the same error as for
+        // individual verification is reported.
+        cdis[2].proofs.id_proofs.cred_counter_less_than_max_accounts = cdis[1]
+            .proofs
+            .id_proofs
+            .cred_counter_less_than_max_accounts
+            .clone();
+        let batch = cdis
+            .iter()
+            .map(|cdi| (&ip_info, cdi, &new_account))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_cdi_batch(&mut csprng, &global_ctx, &ars_infos, &batch),
+            Err(vec![(2, CdiVerificationError::Proof)])
+        );
+        assert_eq!(
+            verify_cdi(&global_ctx, &ip_info, &ars_infos, &cdis[2], &new_account),
+            Err(CdiVerificationError::Proof)
+        );
+    }
+
     /// This tests the credential creation flow, where no initial account was
     /// involved in the identity creation process.
     #[test]
@@ -1,9 +1,13 @@
 //! Implementation of Shamir secret sharing.
+use crate::utils;
 use anyhow::bail;
 use crypto_common::*;
 use curve_arithmetic::*;
 use ff::Field;
-use pedersen_scheme::Value as PedersenValue;
+use pedersen_scheme::{
+    Commitment, CommitmentKey as PedersenKey, Randomness as PedersenRandomness,
+    Value as PedersenValue,
+};
 use rand::*;
 use serde_json::{json, Value};
 use std::convert::TryFrom;
@@ -194,6 +198,302 @@ pub fn reveal_in_group<P: Into<u64> + Copy, C: Curve>(shares: &[(P, C)]) -> C {
     })
 }
 
+/// Evaluate the polynomial with the given coefficients in the group at the
+/// point, i.e., compute `sum coefficients[k] * point^k`.
+fn evaluate_in_group<C: Curve>(point: &C::Scalar, coefficients: &[C]) -> C {
+    let mut exponents = Vec::with_capacity(coefficients.len());
+    let mut exponent = C::Scalar::one();
+    for _ in 0..coefficients.len() {
+        exponents.push(exponent);
+        exponent.mul_assign(point);
+    }
+    multiexp(coefficients, &exponents)
+}
+
+/// Feldman commitments to the coefficients of the sharing polynomial of
+/// `secret`, i.e., `a_k * generator` for the coefficients `a_k`, starting with
+/// the secret. These allow anybody to check a share with
+/// [`verify_share_feldman`], but they reveal `secret * generator`.
+pub fn feldman_commitments<C: Curve>(
+    generator: &C,
+    secret: &C::Scalar,
+    sharing_data: &SharingData<C>,
+) -> Vec<C> {
+    let mut commitments = Vec::with_capacity(sharing_data.coefficients.len() + 1);
+    commitments.push(generator.mul_by_scalar(secret));
+    for coeff in sharing_data.coefficients.iter() {
+        commitments.push(generator.mul_by_scalar(coeff));
+    }
+    commitments
+}
+
+/// Pedersen commitments to the coefficients of the sharing polynomial of
+/// `secret`, starting with the secret, together with the randomness used.
+/// These are the commitments used in credentials. The randomness of the
+/// commitment to a share is the evaluation of the polynomial with the
+/// randomness as coefficients at the point of the share, see
+/// [`verify_share_pedersen`].
+pub fn pedersen_commitments<C: Curve, R: Rng>(
+    commitment_key: &PedersenKey<C>,
+    secret: &PedersenValue<C>,
+    sharing_data: &SharingData<C>,
+    csprng: &mut R,
+) -> (Vec<Commitment<C>>, Vec<PedersenRandomness<C>>) {
+    let n = sharing_data.coefficients.len() + 1;
+    let mut commitments = Vec::with_capacity(n);
+    let mut randomness = Vec::with_capacity(n);
+    for coeff in std::iter::once(secret).chain(sharing_data.coefficients.iter()) {
+        let (cmm, rnd) = commitment_key.commit(coeff, csprng);
+        commitments.push(cmm);
+        randomness.push(rnd);
+    }
+    (commitments, randomness)
+}
+
+/// Check that `share` is the evaluation at `point` of the polynomial whose
+/// coefficients are committed to by the Feldman commitments.
+pub fn verify_share_feldman<C: Curve, P: Into<u64>>(
+    generator: &C,
+    point: P,
+    share: &PedersenValue<C>,
+    commitments: &[C],
+) -> bool {
+    let point = C::scalar_from_u64(point.into());
+    generator.mul_by_scalar(share) == evaluate_in_group(&point, commitments)
+}
+
+/// Check that `share` is the evaluation at `point` of the polynomial whose
+/// coefficients are committed to by the Pedersen commitments. The
+/// `randomness` is the randomness of the commitment to the share, i.e., the
+/// evaluation at `point` of the polynomial with the randomness of the
+/// commitments to the coefficients as coefficients.
+pub fn verify_share_pedersen<C: Curve, P: Into<u64>>(
+    commitment_key: &PedersenKey<C>,
+    point: P,
+    share: &PedersenValue<C>,
+    randomness: &PedersenRandomness<C>,
+    commitments: &[Commitment<C>],
+) -> bool {
+    let point = C::scalar_from_u64(point.into());
+    commitment_key.open(
+        share,
+        randomness,
+        &utils::commitment_to_share(&point, commitments),
+    )
+}
+
+/// The data the holder of a share sends to the holders of the new shares when
+/// resharing the secret, see [`reshare`].
+pub struct ResharingData<C: Curve> {
+    /// Shares of the share, one for each of the new points.
+    pub sub_shares:  Vec<PedersenValue<C>>,
+    /// Feldman commitments to the coefficients of the polynomial used to share
+    /// the share, starting with the commitment to the share.
+    pub commitments: Vec<C>,
+}
+
+/// Share a share of a secret among a new set of points with a new threshold.
+/// This is used for proactive resharing, where the secret is shared among a
+/// new set of holders without being reconstructed. The holders of at least
+/// threshold many of the old shares each reshare their share, each new holder
+/// checks the sub-shares it receives with [`verify_share_feldman`] and
+/// [`verify_resharing`], and combines them into its new share with
+/// [`combine_sub_shares`]. The new shares are shares of the original secret
+/// at the new points, and the old shares should then be deleted.
+///
+/// **Warning:** The Feldman commitments publish `generator * share`, and the
+/// old and the combined commitments publish `generator * secret`. For the
+/// sharing of `idCredSec` under the generator of the global context this is
+/// `idCredPub`, which is exactly what anonymity revocation protects. Secrets
+/// that are shared with Pedersen commitments, such as those of credentials,
+/// must be reshared with [`reshare_pedersen`] instead.
+pub fn reshare<C: Curve, P: Into<u64>, I: IntoIterator<Item = P> + ExactSizeIterator, R: Rng>(
+    generator: &C,
+    share: &PedersenValue<C>,
+    new_points: I,
+    new_threshold: Threshold,
+    csprng: &mut R,
+) -> ResharingData<C> {
+    let sharing_data = self::share::<C, _, _, _>(share, new_points, new_threshold, csprng);
+    let commitments = feldman_commitments(generator, share, &sharing_data);
+    ResharingData {
+        sub_shares: sharing_data.shares,
+        commitments,
+    }
+}
+
+/// Check that the Feldman commitments of the resharing of the share at `point`
+/// are for a polynomial of degree less than `new_threshold`, and that the
+/// shared value is the share at `point` of the polynomial with the
+/// `old_commitments`.
+pub fn verify_resharing<C: Curve, P: Into<u64>>(
+    old_commitments: &[C],
+    point: P,
+    commitments: &[C],
+    new_threshold: Threshold,
+) -> bool {
+    let point = C::scalar_from_u64(point.into());
+    commitments.len() == usize::from(new_threshold)
+        && commitments[0] == evaluate_in_group(&point, old_commitments)
+}
+
+/// The data the holder of a share sends to the holders of the new shares when
+/// resharing a secret that is shared with Pedersen commitments, see
+/// [`reshare_pedersen`].
+pub struct PedersenResharingData<C: Curve> {
+    /// Shares of the share, one for each of the new points.
+    pub sub_shares:           Vec<PedersenValue<C>>,
+    /// The randomness of the commitments to the sub-shares, one for each of
+    /// the new points.
+    pub sub_share_randomness: Vec<PedersenRandomness<C>>,
+    /// Pedersen commitments to the coefficients of the polynomial used to
+    /// share the share, starting with the commitment to the share.
+    pub commitments:          Vec<Commitment<C>>,
+}
+
+/// Share a share of a secret that is shared with Pedersen commitments, such as
+/// the commitments in credentials, among a new set of points with a new
+/// threshold. The `share_randomness` is the randomness of the commitment to
+/// the share, see [`verify_share_pedersen`]. It is used as the randomness of
+/// the commitment to the share in the resharing, so that the commitment is
+/// the one computed from the old commitments with
+/// [`utils::commitment_to_share`].
+///
+/// In contrast to [`reshare`] this reveals nothing about the secret. Each new
+/// holder checks the resharings with [`verify_resharing_pedersen`] and the
+/// sub-shares it receives with [`verify_share_pedersen`], and combines them
+/// into its new share with [`combine_sub_shares`] and
+/// [`combine_sub_share_randomness`]. The commitments combined with
+/// [`combine_resharing_pedersen_commitments`] start with the same commitment
+/// to the secret as the old commitments.
+pub fn reshare_pedersen<
+    C: Curve,
+    P: Into<u64>,
+    I: IntoIterator<Item = P> + ExactSizeIterator,
+    R: Rng,
+>(
+    commitment_key: &PedersenKey<C>,
+    share: &PedersenValue<C>,
+    share_randomness: &PedersenRandomness<C>,
+    new_points: I,
+    new_threshold: Threshold,
+    csprng: &mut R,
+) -> PedersenResharingData<C> {
+    let new_points = new_points
+        .into_iter()
+        .map(|p| p.into())
+        .collect::<Vec<u64>>();
+    let sharing_data =
+        self::share::<C, _, _, _>(share, new_points.iter().copied(), new_threshold, csprng);
+    let n = sharing_data.coefficients.len() + 1;
+    let mut commitments = Vec::with_capacity(n);
+    let mut randomness = Vec::with_capacity(n);
+    commitments.push(commitment_key.hide(share, share_randomness));
+    randomness.push(share_randomness.clone());
+    for coeff in sharing_data.coefficients.iter() {
+        let (cmm, rnd) = commitment_key.commit(coeff, csprng);
+        commitments.push(cmm);
+        randomness.push(rnd);
+    }
+    let sub_share_randomness = new_points
+        .iter()
+        .map(|&p| {
+            PedersenRandomness::new(utils::evaluate_poly(&randomness, &C::scalar_from_u64(p)))
+        })
+        .collect();
+    PedersenResharingData {
+        sub_shares: sharing_data.shares,
+        sub_share_randomness,
+        commitments,
+    }
+}
+
+/// Check that the Pedersen commitments of the resharing of the share at
+/// `point` are for a polynomial of degree less than `new_threshold`, and that
+/// the commitment to the shared value is the commitment to the share at
+/// `point` computed from the `old_commitments`.
+pub fn verify_resharing_pedersen<C: Curve, P: Into<u64>>(
+    old_commitments: &[Commitment<C>],
+    point: P,
+    commitments: &[Commitment<C>],
+    new_threshold: Threshold,
+) -> bool {
+    let point = C::scalar_from_u64(point.into());
+    commitments.len() == usize::from(new_threshold)
+        && commitments[0] == utils::commitment_to_share(&point, old_commitments)
+}
+
+/// Combine the sub-shares that the holder of a new share received from the
+/// holders of old shares into the new share. The sub-shares are given together
+/// with the points of the old shares, and there must be at least as many as
+/// the old threshold, with distinct points. This is not checked.
+pub fn combine_sub_shares<P: Into<u64> + Copy, C: Curve>(
+    sub_shares: &[(P, PedersenValue<C>)],
+) -> PedersenValue<C> {
+    PedersenValue::new(reveal(sub_shares))
+}
+
+/// Combine the randomness of the commitments to the sub-shares that the holder
+/// of a new share received into the randomness of the commitment to the new
+/// share, in the same way as [`combine_sub_shares`].
+pub fn combine_sub_share_randomness<P: Into<u64> + Copy, C: Curve>(
+    sub_share_randomness: &[(P, PedersenRandomness<C>)],
+) -> PedersenRandomness<C> {
+    let values = sub_share_randomness
+        .iter()
+        .map(|(point, rnd)| (*point, PedersenValue::new(*rnd.as_ref())))
+        .collect::<Vec<_>>();
+    PedersenRandomness::new(reveal(&values))
+}
+
+/// Combine the Feldman commitments of the resharings of the old shares at the
+/// given points into Feldman commitments for the new shares. This uses that
+/// commitments are homomorphic, so the new commitments are computed in the
+/// same way as the new shares in [`combine_sub_shares`]. Returns `None` if the
+/// commitments do not all have the same length.
+pub fn combine_resharing_commitments<P: Into<u64> + Copy, C: Curve>(
+    commitments: &[(P, &[C])],
+) -> Option<Vec<C>> {
+    let (_, first) = commitments.first()?;
+    if commitments
+        .iter()
+        .any(|(_, cmms)| cmms.len() != first.len())
+    {
+        return None;
+    }
+    let combined = (0..first.len())
+        .map(|k| {
+            reveal_in_group(
+                &commitments
+                    .iter()
+                    .map(|(point, cmms)| (*point, cmms[k]))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    Some(combined)
+}
+
+/// Combine the Pedersen commitments of the resharings of the old shares at the
+/// given points into Pedersen commitments for the new shares, in the same way
+/// as [`combine_resharing_commitments`]. Returns `None` if the commitments do
+/// not all have the same length.
+pub fn combine_resharing_pedersen_commitments<P: Into<u64> + Copy, C: Curve>(
+    commitments: &[(P, &[Commitment<C>])],
+) -> Option<Vec<Commitment<C>>> {
+    let points = commitments
+        .iter()
+        .map(|(point, cmms)| (*point, cmms.iter().map(|cmm| cmm.0).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    let combined = combine_resharing_commitments(
+        &points
+            .iter()
+            .map(|(point, cmms)| (*point, &cmms[..]))
+            .collect::<Vec<_>>(),
+    )?;
+    Some(combined.into_iter().map(Commitment).collect())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -332,4 +632,320 @@ mod test {
             assert_ne!(revealed_data_point, secret_point);
         }
     }
+
+    /// Test that shares verify against the Feldman and Pedersen commitments to
+    /// the sharing polynomial, and that wrong shares do not.
+    #[test]
+    pub fn test_share_verification() {
+        let mut csprng = thread_rng();
+        let generator = G1::generate(&mut csprng);
+        let commitment_key = PedersenKey::<G1>::generate(&mut csprng);
+        let secret = PedersenValue::<G1>::generate(&mut csprng);
+        let xs = (1u32..=7).collect::<Vec<_>>();
+        let sharing_data =
+            share::<G1, _, _, _>(&secret, xs.iter().copied(), Threshold(4), &mut csprng);
+        let feldman = feldman_commitments(&generator, &secret, &sharing_data);
+        let (pedersen, randomness) =
+            pedersen_commitments(&commitment_key, &secret, &sharing_data, &mut csprng);
+        assert_eq!(feldman[0], generator.mul_by_scalar(&secret));
+        assert!(commitment_key.open(&secret, &randomness[0], &pedersen[0]));
+        for (&x, s) in xs.iter().zip(sharing_data.shares.iter()) {
+            let share_randomness = PedersenRandomness::new(utils::evaluate_poly(
+                &randomness,
+                &G1::scalar_from_u64(x.into()),
+            ));
+            assert!(verify_share_feldman(&generator, x, s, &feldman));
+            assert!(verify_share_pedersen(
+                &commitment_key,
+                x,
+                s,
+                &share_randomness,
+                &pedersen
+            ));
+            // The share of another point does not verify.
+            assert!(!verify_share_feldman(&generator, x + 1, s, &feldman));
+            assert!(!verify_share_pedersen(
+                &commitment_key,
+                x + 1,
+                s,
+                &share_randomness,
+                &pedersen
+            ));
+        }
+        let wrong_share = PedersenValue::<G1>::generate(&mut csprng);
+        assert!(!verify_share_feldman(
+            &generator,
+            xs[0],
+            &wrong_share,
+            &feldman
+        ));
+    }
+
+    /// Test that shares and commitments are homomorphic: the sums of the shares
+    /// of two secrets are shares of the sum of the secrets, and they verify
+    /// against the sums of the commitments.
+    #[test]
+    pub fn test_share_homomorphism() {
+        let mut csprng = thread_rng();
+        let generator = G1::generate(&mut csprng);
+        let commitment_key = PedersenKey::<G1>::generate(&mut csprng);
+        let xs = (1u32..=5).collect::<Vec<_>>();
+        let secret_1 = PedersenValue::<G1>::generate(&mut csprng);
+        let secret_2 = PedersenValue::<G1>::generate(&mut csprng);
+        let sharing_1 =
+            share::<G1, _, _, _>(&secret_1, xs.iter().copied(), Threshold(3), &mut csprng);
+        let sharing_2 =
+            share::<G1, _, _, _>(&secret_2, xs.iter().copied(), Threshold(3), &mut csprng);
+        let feldman_1 = feldman_commitments(&generator, &secret_1, &sharing_1);
+        let feldman_2 = feldman_commitments(&generator, &secret_2, &sharing_2);
+        let (pedersen_1, randomness_1) =
+            pedersen_commitments(&commitment_key, &secret_1, &sharing_1, &mut csprng);
+        let (pedersen_2, randomness_2) =
+            pedersen_commitments(&commitment_key, &secret_2, &sharing_2, &mut csprng);
+
+        let feldman_sum = feldman_1
+            .iter()
+            .zip(feldman_2.iter())
+            .map(|(c1, c2)| c1.plus_point(c2))
+            .collect::<Vec<_>>();
+        let pedersen_sum = pedersen_1
+            .iter()
+            .zip(pedersen_2.iter())
+            .map(|(c1, c2)| c1.combine(c2))
+            .collect::<Vec<_>>();
+        let randomness_sum = randomness_1
+            .iter()
+            .zip(randomness_2.iter())
+            .map(|(r1, r2)| {
+                let mut r = *r1.as_ref();
+                r.add_assign(r2.as_ref());
+                PedersenRandomness::new(r)
+            })
+            .collect::<Vec<_>>();
+        let mut share_sums = Vec::new();
+        for ((&x, s1), s2) in xs
+            .iter()
+            .zip(sharing_1.shares.iter())
+            .zip(sharing_2.shares.iter())
+        {
+            let mut sum = *s1.as_ref();
+            sum.add_assign(s2.as_ref());
+            let sum = PedersenValue::new(sum);
+            let share_randomness = PedersenRandomness::new(utils::evaluate_poly(
+                &randomness_sum,
+                &G1::scalar_from_u64(x.into()),
+            ));
+            assert!(verify_share_feldman(&generator, x, &sum, &feldman_sum));
+            assert!(verify_share_pedersen(
+                &commitment_key,
+                x,
+                &sum,
+                &share_randomness,
+                &pedersen_sum
+            ));
+            share_sums.push((x, sum));
+        }
+        let mut secret_sum = *secret_1.as_ref();
+        secret_sum.add_assign(secret_2.as_ref());
+        assert_eq!(reveal::<_, G1>(&share_sums[..3]), secret_sum);
+    }
+
+    /// Test resharing a secret to a new set of points with a new threshold, and
+    /// that the new shares can be verified and used to reveal the secret.
+    #[test]
+    pub fn test_resharing() {
+        let mut csprng = thread_rng();
+        let generator = G1::generate(&mut csprng);
+        let secret = PedersenValue::<G1>::generate(&mut csprng);
+        let old_points = (1u32..=5).collect::<Vec<_>>();
+        let old_threshold = Threshold(3);
+        let sharing_data = share::<G1, _, _, _>(
+            &secret,
+            old_points.iter().copied(),
+            old_threshold,
+            &mut csprng,
+        );
+        let old_commitments = feldman_commitments(&generator, &secret, &sharing_data);
+
+        // Old holders 2, 4 and 5 reshare to 4 new holders with threshold 2.
+        let new_points = (6u32..=9).collect::<Vec<_>>();
+        let new_threshold = Threshold(2);
+        let resharers = [1, 3, 4];
+        let resharings = resharers
+            .iter()
+            .map(|&i| {
+                (
+                    old_points[i],
+                    reshare(
+                        &generator,
+                        &sharing_data.shares[i],
+                        new_points.iter().copied(),
+                        new_threshold,
+                        &mut csprng,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        for (point, resharing) in resharings.iter() {
+            assert!(verify_resharing(
+                &old_commitments,
+                *point,
+                &resharing.commitments,
+                new_threshold
+            ));
+            assert!(!verify_resharing(
+                &old_commitments,
+                *point + 1,
+                &resharing.commitments,
+                new_threshold
+            ));
+            for (&x, sub_share) in new_points.iter().zip(resharing.sub_shares.iter()) {
+                assert!(verify_share_feldman(
+                    &generator,
+                    x,
+                    sub_share,
+                    &resharing.commitments
+                ));
+            }
+        }
+
+        let new_commitments = combine_resharing_commitments(
+            &resharings
+                .iter()
+                .map(|(point, resharing)| (*point, &resharing.commitments[..]))
+                .collect::<Vec<_>>(),
+        )
+        .expect("All resharings have the same threshold.");
+        // The new polynomial has the same constant term, but a new degree.
+        assert_eq!(new_commitments.len(), 2);
+        assert_eq!(new_commitments[0], old_commitments[0]);
+        let new_shares = new_points
+            .iter()
+            .enumerate()
+            .map(|(j, &x)| {
+                let sub_shares = resharings
+                    .iter()
+                    .map(|(point, resharing)| (*point, resharing.sub_shares[j].clone()))
+                    .collect::<Vec<_>>();
+                (x, combine_sub_shares::<_, G1>(&sub_shares))
+            })
+            .collect::<Vec<_>>();
+        for (x, new_share) in new_shares.iter() {
+            assert!(verify_share_feldman(
+                &generator,
+                *x,
+                new_share,
+                &new_commitments
+            ));
+        }
+        // Any two of the new shares reveal the secret, but one does not.
+        assert_eq!(reveal::<_, G1>(&new_shares[1..3]), *secret.as_ref());
+        assert_eq!(
+            reveal::<_, G1>(&[new_shares[0].clone(), new_shares[3].clone()]),
+            *secret.as_ref()
+        );
+        assert_ne!(reveal::<_, G1>(&new_shares[..1]), *secret.as_ref());
+    }
+
+    /// Test resharing a secret that is shared with Pedersen commitments, as in
+    /// credentials. The new commitments have the same commitment to the secret
+    /// as the old ones, and the new shares verify against them.
+    #[test]
+    pub fn test_resharing_pedersen() {
+        let mut csprng = thread_rng();
+        let commitment_key = PedersenKey::<G1>::generate(&mut csprng);
+        let secret = PedersenValue::<G1>::generate(&mut csprng);
+        let old_points = (1u32..=5).collect::<Vec<_>>();
+        let sharing_data = share::<G1, _, _, _>(
+            &secret,
+            old_points.iter().copied(),
+            Threshold(3),
+            &mut csprng,
+        );
+        let (old_commitments, old_randomness) =
+            pedersen_commitments(&commitment_key, &secret, &sharing_data, &mut csprng);
+
+        // Old holders 1, 3 and 4 reshare to 4 new holders with threshold 2.
+        let new_points = (6u32..=9).collect::<Vec<_>>();
+        let new_threshold = Threshold(2);
+        let resharings = [0, 2, 3]
+            .iter()
+            .map(|&i| {
+                let share_randomness = PedersenRandomness::new(utils::evaluate_poly(
+                    &old_randomness,
+                    &G1::scalar_from_u64(old_points[i].into()),
+                ));
+                (
+                    old_points[i],
+                    reshare_pedersen(
+                        &commitment_key,
+                        &sharing_data.shares[i],
+                        &share_randomness,
+                        new_points.iter().copied(),
+                        new_threshold,
+                        &mut csprng,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        for (point, resharing) in resharings.iter() {
+            assert!(verify_resharing_pedersen(
+                &old_commitments,
+                *point,
+                &resharing.commitments,
+                new_threshold
+            ));
+            assert!(!verify_resharing_pedersen(
+                &old_commitments,
+                *point + 1,
+                &resharing.commitments,
+                new_threshold
+            ));
+            for ((&x, sub_share), sub_share_randomness) in new_points
+                .iter()
+                .zip(resharing.sub_shares.iter())
+                .zip(resharing.sub_share_randomness.iter())
+            {
+                assert!(verify_share_pedersen(
+                    &commitment_key,
+                    x,
+                    sub_share,
+                    sub_share_randomness,
+                    &resharing.commitments
+                ));
+            }
+        }
+
+        let new_commitments = combine_resharing_pedersen_commitments(
+            &resharings
+                .iter()
+                .map(|(point, resharing)| (*point, &resharing.commitments[..]))
+                .collect::<Vec<_>>(),
+        )
+        .expect("All resharings have the same threshold.");
+        assert_eq!(new_commitments.len(), 2);
+        assert_eq!(new_commitments[0], old_commitments[0]);
+        let mut new_shares = Vec::new();
+        for (j, &x) in new_points.iter().enumerate() {
+            let sub_shares = resharings
+                .iter()
+                .map(|(point, resharing)| (*point, resharing.sub_shares[j].clone()))
+                .collect::<Vec<_>>();
+            let sub_share_randomness = resharings
+                .iter()
+                .map(|(point, resharing)| (*point, resharing.sub_share_randomness[j].clone()))
+                .collect::<Vec<_>>();
+            let new_share = combine_sub_shares::<_, G1>(&sub_shares);
+            let new_randomness = combine_sub_share_randomness::<_, G1>(&sub_share_randomness);
+            assert!(verify_share_pedersen(
+                &commitment_key,
+                x,
+                &new_share,
+                &new_randomness,
+                &new_commitments
+            ));
+            new_shares.push((x, new_share));
+        }
+        assert_eq!(reveal::<_, G1>(&new_shares[1..3]), *secret.as_ref());
+    }
 }
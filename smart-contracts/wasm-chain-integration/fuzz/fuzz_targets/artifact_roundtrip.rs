@@ -0,0 +1,60 @@
+#![no_main]
+
+/// Fuzz target for serialization of compiled artifacts, both on their own and
+/// inside the versioned container of the artifact cache.
+use concordium_contracts_common::ModuleReference;
+use libfuzzer_sys::fuzz_target;
+use wasm_chain_integration::{
+    artifact_cache::{decode_container, encode_artifact, CompilationParameters},
+    fuzz::*,
+    utils::WasmVersion,
+    v1::{ConcordiumAllowedImports, ProcessedImports},
+};
+use wasm_transform::{
+    artifact::{Artifact, CompiledFunctionBytes},
+    output::Output,
+    parse::parse_skeleton,
+    utils::parse_artifact,
+    validate::{validate_module_with_config, ValidationConfig},
+};
+
+// Creates a random, but type-correct Wasm module. If it is valid, it is
+// compiled with metering, and the artifact is checked to
+// - deserialize and serialize back to identical bytes
+// - be recovered unchanged from a container produced by the artifact cache.
+fuzz_target!(|module: ConfiguredModule<InterpreterConfig>| {
+    let bytes = module.to_bytes();
+    let skeleton = if let Ok(skeleton) = parse_skeleton(&bytes) {
+        skeleton
+    } else {
+        return;
+    };
+    let imports = ConcordiumAllowedImports {
+        support_upgrade: true,
+    };
+    let mut module = if let Ok(module) =
+        validate_module_with_config(&ValidationConfig::V1, &imports, &skeleton)
+    {
+        module
+    } else {
+        return;
+    };
+    module.inject_metering().expect("Metering injection of a validated module failed.");
+    let artifact = module.compile().expect("Compilation of a validated module failed.");
+
+    let mut serialized = Vec::new();
+    artifact.output(&mut serialized).expect("Writing to a vector does not fail.");
+    let parsed: Artifact<ProcessedImports, CompiledFunctionBytes> =
+        parse_artifact(&serialized).expect("A serialized artifact must parse.");
+    let mut reserialized = Vec::new();
+    parsed.output(&mut reserialized).expect("Writing to a vector does not fail.");
+    assert_eq!(serialized, reserialized, "Artifact serialization is not a fixpoint.");
+
+    let parameters = CompilationParameters::new(WasmVersion::V1, true, ValidationConfig::V1);
+    let module_ref = ModuleReference::from([7u8; 32]);
+    let container = encode_artifact(module_ref, &parameters, &artifact);
+    let (header, body) = decode_container(&container).expect("An encoded container must decode.");
+    assert_eq!(header.module_ref, module_ref);
+    assert_eq!(header.parameters, parameters);
+    assert_eq!(body, &serialized[..], "The container must hold the serialized artifact.");
+});
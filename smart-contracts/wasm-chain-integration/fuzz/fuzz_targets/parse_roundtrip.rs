@@ -0,0 +1,86 @@
+#![no_main]
+
+/// Differential fuzz target for decoding and encoding of Wasm modules.
+/// A validated module that is written out and validated again must be the same
+/// module, and must compile to the same artifact.
+use libfuzzer_sys::fuzz_target;
+use wasm_chain_integration::{
+    fuzz::*,
+    v1::{ConcordiumAllowedImports, ProcessedImports},
+};
+use wasm_transform::{
+    artifact::{Artifact, CompiledFunction},
+    output::Output,
+    parse::parse_skeleton,
+    types::Module,
+    validate::{validate_module_with_config, ValidationConfig},
+};
+
+/// Parse and validate the module.
+fn validate(bytes: &[u8]) -> Option<Module> {
+    let skeleton = parse_skeleton(bytes).ok()?;
+    validate_module_with_config(
+        &ValidationConfig::V1,
+        &ConcordiumAllowedImports {
+            support_upgrade: true,
+        },
+        &skeleton,
+    )
+    .ok()
+}
+
+/// Write out the module in the binary format.
+fn write(module: &Module) -> Vec<u8> {
+    let mut out = Vec::new();
+    module.output(&mut out).expect("Writing to a vector does not fail.");
+    out
+}
+
+/// Compile the module and return the serialized artifact.
+fn compile(module: Module) -> Vec<u8> {
+    let artifact: Artifact<ProcessedImports, CompiledFunction> =
+        module.compile().expect("Compilation of a validated module failed.");
+    let mut out = Vec::new();
+    artifact.output(&mut out).expect("Writing to a vector does not fail.");
+    out
+}
+
+// Creates a random, but type-correct Wasm module, and checks that if it is
+// valid then
+// - the module decoded from it can be written out, and the written module is
+//   valid as well
+// - the instructions of all functions are decoded from the written module
+//   exactly as from the original one, and writing the decoded module out again
+//   yields identical bytes
+// - the original and the written module compile to identical artifacts.
+fuzz_target!(|module: ConfiguredModule<InterpreterConfig>| {
+    let original = module.to_bytes();
+    let decoded = if let Some(decoded) = validate(&original) {
+        decoded
+    } else {
+        return;
+    };
+    let written = write(&decoded);
+    let redecoded = if let Some(redecoded) = validate(&written) {
+        redecoded
+    } else {
+        print_module(&original);
+        panic!("A written module must be valid.");
+    };
+    let same_code = decoded.code.impls.len() == redecoded.code.impls.len()
+        && decoded.code.impls.iter().zip(redecoded.code.impls.iter()).all(|(l, r)| {
+            l.expr.instrs == r.expr.instrs && l.num_locals == r.num_locals && l.ty == r.ty
+        });
+    if !same_code {
+        print_module(&original);
+        panic!("Writing out a module changes its instructions.");
+    }
+    if write(&redecoded) != written {
+        print_module(&original);
+        panic!("Decoding and writing out a written module is not a fixpoint.");
+    }
+    if compile(decoded) != compile(redecoded) {
+        print_module(&original);
+        panic!("Writing out a module changes the compiled artifact.");
+    }
+});
@@ -0,0 +1,245 @@
+#![no_main]
+
+use concordium_contracts_common::{
+    AccountAddress, Address, Amount, ChainMetadata, ContractAddress, OwnedEntrypointName,
+    ReceiveName, Timestamp,
+};
+/// Fuzz target for the state host functions of V1 contracts. A sequence of
+/// state operations is executed by a contract, and the output of the contract
+/// and the final state are compared to those of a reference model.
+use libfuzzer_sys::fuzz_target;
+use std::collections::BTreeMap;
+use wasm_chain_integration::{
+    fuzz::{encode_state_operations, StateModel, StateOperation},
+    v0,
+    v1::{
+        invoke_receive_with_metering_from_source,
+        trie::{EmptyCollector, Loader, MutableState},
+        InstanceState, InvokeFromSourceCtx, ReceiveContext, ReceiveParams, ReceiveResult,
+    },
+    InterpreterEnergy,
+};
+use wasm_transform::validate::ValidationConfig;
+
+/// Maximum number of operations executed in one run. This keeps the parameter
+/// within the region of memory the contract reserves for it.
+const MAX_OPERATIONS: usize = 64;
+
+/// The energy is large enough that no run of the contract runs out of it.
+const ENERGY: u64 = 1_000_000_000;
+
+/// The contract reads operations from the parameter and writes the result of
+/// each of them to the return value, in the format produced by
+/// [`StateModel::apply`]. Memory is laid out as
+/// - the parameter at 0
+/// - a scratch buffer for integers written to the output at 4096
+/// - a buffer for keys and values read from the state at 8192
+/// - the ids of the iterators that are kept open at 16384.
+const CONTRACT: &str = r#"
+(module
+  (import "concordium" "get_parameter_size" (func $get_parameter_size (param $index i32) (result i32)))
+  (import "concordium" "get_parameter_section" (func $get_parameter_section (param $index i32) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "write_output" (func $write_output (param $start i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "state_create_entry" (func $state_create_entry (param $key_start i32) (param $key_length i32) (result i64)))
+  (import "concordium" "state_entry_size" (func $state_entry_size (param $entry i64) (result i32)))
+  (import "concordium" "state_entry_read" (func $state_entry_read (param $entry i64) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "state_entry_write" (func $state_entry_write (param $entry i64) (param $read_location i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "state_delete_prefix" (func $state_delete_prefix (param $key_start i32) (param $key_length i32) (result i32)))
+  (import "concordium" "state_iterate_prefix" (func $state_iterate_prefix (param $key_start i32) (param $key_length i32) (result i64)))
+  (import "concordium" "state_iterator_next" (func $state_iterator_next (param $iter i64) (result i64)))
+  (import "concordium" "state_iterator_delete" (func $state_iterator_delete (param $iter i64) (result i32)))
+  (import "concordium" "state_iterator_key_size" (func $state_iterator_key_size (param $iter i64) (result i32)))
+  (import "concordium" "state_iterator_key_read" (func $state_iterator_key_read (param $iter i64) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+
+  (memory 1)
+
+  ;; Length of the output written so far.
+  (global $out (mut i32) (i32.const 0))
+  ;; Number of iterators that are kept open.
+  (global $open (mut i32) (i32.const 0))
+
+  (func $emit (param $start i32) (param $length i32)
+    (drop (call $write_output (local.get $start) (local.get $length) (global.get $out)))
+    (global.set $out (i32.add (global.get $out) (local.get $length))))
+
+  (func $emit_byte (param $byte i32)
+    (i32.store8 (i32.const 4096) (local.get $byte))
+    (call $emit (i32.const 4096) (i32.const 1)))
+
+  (func $emit_u32 (param $n i32)
+    (i32.store (i32.const 4096) (local.get $n))
+    (call $emit (i32.const 4096) (i32.const 4)))
+
+  (func $create (param $key i32) (param $key_length i32) (param $value i32) (param $value_length i32)
+    (local $entry i64)
+    (local.set $entry (call $state_create_entry (local.get $key) (local.get $key_length)))
+    (if (i64.lt_s (local.get $entry) (i64.const 0))
+      (then (call $emit_byte (i32.const 0)))
+      (else
+        (drop (call $state_entry_write (local.get $entry) (local.get $value) (local.get $value_length) (i32.const 0)))
+        (call $emit_byte (i32.const 1)))))
+
+  (func $iterate (param $prefix i32) (param $length i32) (param $keep_open i32)
+    (local $iter i64)
+    (local $entry i64)
+    (local $size i32)
+    (local.set $iter (call $state_iterate_prefix (local.get $prefix) (local.get $length)))
+    (if (i64.eq (local.get $iter) (i64.const -1))
+      (then
+        (call $emit_byte (i32.const 0))
+        (return)))
+    (if (i64.lt_s (local.get $iter) (i64.const 0))
+      (then
+        (call $emit_byte (i32.const 2))
+        (return)))
+    (call $emit_byte (i32.const 1))
+    (block $exhausted
+      (loop $entries
+        (local.set $entry (call $state_iterator_next (local.get $iter)))
+        (br_if $exhausted (i64.lt_s (local.get $entry) (i64.const 0)))
+        (call $emit_byte (i32.const 1))
+        (local.set $size (call $state_iterator_key_size (local.get $iter)))
+        (drop (call $state_iterator_key_read (local.get $iter) (i32.const 8192) (local.get $size) (i32.const 0)))
+        (call $emit_u32 (local.get $size))
+        (call $emit (i32.const 8192) (local.get $size))
+        (local.set $size (call $state_entry_size (local.get $entry)))
+        (drop (call $state_entry_read (local.get $entry) (i32.const 8192) (local.get $size) (i32.const 0)))
+        (call $emit_u32 (local.get $size))
+        (call $emit (i32.const 8192) (local.get $size))
+        (br $entries)))
+    (call $emit_byte (i32.const 0))
+    (if (local.get $keep_open)
+      (then
+        (i64.store (i32.add (i32.const 16384) (i32.mul (global.get $open) (i32.const 8))) (local.get $iter))
+        (global.set $open (i32.add (global.get $open) (i32.const 1))))
+      (else
+        (drop (call $state_iterator_delete (local.get $iter))))))
+
+  (func $close_iterators
+    (local $i i32)
+    (block $closed
+      (loop $iterators
+        (br_if $closed (i32.ge_u (local.get $i) (global.get $open)))
+        (drop (call $state_iterator_delete (i64.load (i32.add (i32.const 16384) (i32.mul (local.get $i) (i32.const 8))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $iterators)))
+    (call $emit_byte (global.get $open))
+    (global.set $open (i32.const 0)))
+
+  (func $init (export "init_fuzz") (param i64) (result i32)
+    (i32.const 0))
+
+  (func $run (export "fuzz.run") (param i64) (result i32)
+    (local $pos i32)
+    (local $end i32)
+    (local $tag i32)
+    (local $len i32)
+    (local $value_len i32)
+    (local.set $end (call $get_parameter_size (i32.const 0)))
+    (drop (call $get_parameter_section (i32.const 0) (i32.const 0) (local.get $end) (i32.const 0)))
+    (block $done
+      (loop $operations
+        (br_if $done (i32.ge_u (local.get $pos) (local.get $end)))
+        (local.set $tag (i32.load8_u (local.get $pos)))
+        (local.set $len (i32.load8_u (i32.add (local.get $pos) (i32.const 1))))
+        (if (i32.eq (local.get $tag) (i32.const 0))
+          (then
+            (local.set $value_len (i32.load8_u (i32.add (local.get $pos) (i32.add (local.get $len) (i32.const 2)))))
+            (call $create
+              (i32.add (local.get $pos) (i32.const 2))
+              (local.get $len)
+              (i32.add (local.get $pos) (i32.add (local.get $len) (i32.const 3)))
+              (local.get $value_len))
+            (local.set $pos (i32.add (local.get $pos) (i32.add (i32.add (local.get $len) (local.get $value_len)) (i32.const 3))))))
+        (if (i32.eq (local.get $tag) (i32.const 1))
+          (then
+            (call $emit_byte (call $state_delete_prefix (i32.add (local.get $pos) (i32.const 2)) (local.get $len)))
+            (local.set $pos (i32.add (local.get $pos) (i32.add (local.get $len) (i32.const 2))))))
+        (if (i32.eq (local.get $tag) (i32.const 2))
+          (then
+            (call $iterate
+              (i32.add (local.get $pos) (i32.const 2))
+              (local.get $len)
+              (i32.load8_u (i32.add (local.get $pos) (i32.add (local.get $len) (i32.const 2)))))
+            (local.set $pos (i32.add (local.get $pos) (i32.add (local.get $len) (i32.const 3))))))
+        (if (i32.eq (local.get $tag) (i32.const 3))
+          (then
+            (call $close_iterators)
+            (local.set $pos (i32.add (local.get $pos) (i32.const 1)))))
+        (br $operations)))
+    (i32.const 0))
+)
+"#;
+
+// Executes a sequence of random state operations both in a contract and in
+// the reference model, and checks that the return value of the contract and
+// the resulting state agree with the model.
+fuzz_target!(|ops: Vec<StateOperation>| {
+    let ops: Vec<StateOperation> =
+        ops.iter().take(MAX_OPERATIONS).map(StateOperation::normalize).collect();
+    let parameter = encode_state_operations(&ops);
+    let mut model = StateModel::default();
+    let mut expected = Vec::new();
+    for op in ops.iter() {
+        model.apply(op, &mut expected);
+    }
+
+    let source = wat::parse_str(CONTRACT).expect("The fuzzing contract is valid.");
+    let owner = AccountAddress([0u8; 32]);
+    let receive_ctx: ReceiveContext<&[u8]> = ReceiveContext {
+        common:     v0::ReceiveContext {
+            metadata: ChainMetadata {
+                slot_time: Timestamp::from_timestamp_millis(0),
+            },
+            invoker: owner,
+            self_address: ContractAddress {
+                index:    0,
+                subindex: 0,
+            },
+            self_balance: Amount::from_micro_ccd(0),
+            sender: Address::Account(owner),
+            owner,
+            sender_policies: &[],
+        },
+        entrypoint: OwnedEntrypointName::new_unchecked("run".into()),
+    };
+    let mut mutable_state = MutableState::initial_state();
+    let mut loader = Loader {
+        inner: Vec::new(),
+    };
+    let inner = mutable_state.get_inner(&mut loader);
+    let instance_state = InstanceState::new(loader, inner);
+    let result: ReceiveResult<_, ReceiveContext<&[u8]>> = invoke_receive_with_metering_from_source(
+        InvokeFromSourceCtx {
+            source:          &source,
+            amount:          Amount::from_micro_ccd(0),
+            parameter:       &parameter,
+            energy:          InterpreterEnergy::from(ENERGY),
+            support_upgrade: true,
+            validation:      ValidationConfig::V0,
+        },
+        receive_ctx,
+        ReceiveName::new_unchecked("fuzz.run"),
+        instance_state,
+        ReceiveParams::new_p5(),
+    )
+    .expect("Invocation should succeed.");
+    match result {
+        ReceiveResult::Success {
+            return_value,
+            ..
+        } => assert_eq!(return_value, expected, "Output differs from the model for {:?}.", ops),
+        ReceiveResult::Trap {
+            error,
+            ..
+        } => panic!("Execution trapped: {:?}", error),
+        _ => panic!("Execution should succeed."),
+    }
+
+    let mut loader = Loader {
+        inner: Vec::new(),
+    };
+    let state = mutable_state.freeze(&mut loader, &mut EmptyCollector);
+    let entries: BTreeMap<Vec<u8>, Vec<u8>> = state.into_iterator(&mut loader).collect();
+    assert_eq!(entries, model.entries, "State differs from the model for {:?}.", ops);
+});
@@ -0,0 +1,485 @@
+//! Static analysis of smart contract modules.
+//!
+//! The analysis produces a [`ModuleReport`] that describes what a module can do
+//! without running it. It lists the contracts and entrypoints of the module,
+//! and for each entrypoint the host functions it can reach through the call
+//! graph, and the maximum depth of calls it can make. The report can be
+//! rendered as JSON.
+//!
+//! The call graph is over-approximated. A `call_indirect` instruction is
+//! assumed to be able to call any function in the table that has the right
+//! type, so the reported host functions are the ones that can possibly be
+//! called, not necessarily the ones that will be.
+use crate::{utils::WasmVersion, v0, v1, ExecResult};
+use concordium_contracts_common::{from_bytes, schema};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use wasm_transform::{
+    artifact::TryFromImport,
+    parse::{parse_custom, parse_skeleton},
+    types::{
+        ExportDescription, FuncIndex, FunctionType, Import, ImportDescription, Module, OpCode,
+    },
+    validate::{validate_module_with_config, ValidationConfig},
+};
+
+/// Report of the analysis of a module.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleReport {
+    /// Version of the module, `V0` or `V1`.
+    pub wasm_version:    String,
+    /// Contracts in the module, keyed by contract name.
+    pub contracts:       BTreeMap<String, ContractReport>,
+    /// Host functions imported by the module.
+    pub imports:         Vec<String>,
+    /// Limits of the linear memory, if the module has one.
+    pub memory:          Option<MemoryReport>,
+    /// The embedded schema, if there is one.
+    pub schema:          Option<SchemaReport>,
+    /// All custom sections of the module, in the order they appear.
+    pub custom_sections: Vec<CustomSectionReport>,
+}
+
+/// Report on a single contract of the module.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractReport {
+    /// The init function of the contract. This is `None` if the module
+    /// exports receive functions for a contract without an init function.
+    pub init:        Option<FunctionReport>,
+    /// The receive functions of the contract, keyed by entrypoint name.
+    pub entrypoints: BTreeMap<String, FunctionReport>,
+}
+
+/// Report on an exported function.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionReport {
+    /// Host functions that can be called by the function, directly or
+    /// through other functions.
+    pub host_functions: BTreeSet<String>,
+    /// Whether the function can invoke other contracts or transfer to
+    /// accounts.
+    pub may_invoke:     bool,
+    /// Whether the function can upgrade the contract.
+    pub may_upgrade:    bool,
+    /// Maximum number of nested calls of functions defined in the module,
+    /// counting the function itself. This is `None` if the function can reach
+    /// a recursive function, in which case the depth is only bounded at
+    /// runtime.
+    pub max_call_depth: Option<u32>,
+}
+
+/// Limits of the linear memory, in pages of 64kB.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReport {
+    pub initial_pages: u32,
+    pub max_pages:     Option<u32>,
+}
+
+/// The schema embedded in a module.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaReport {
+    /// Name of the custom section the schema is in.
+    pub section: String,
+    /// Version of the schema, or `None` if the schema could not be parsed.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomSectionReport {
+    pub name: String,
+    /// Size of the contents of the section in bytes.
+    pub size: usize,
+}
+
+impl ModuleReport {
+    /// Render the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Serializing the report does not fail.")
+    }
+}
+
+/// Analyze the module. The module must be valid for the given version, with the
+/// features allowed by the given configuration. The `upgrade` host function is
+/// only allowed for V1 modules if `support_upgrade` is set, as in protocol
+/// version 5 and later.
+pub fn analyze_module(
+    version: WasmVersion,
+    support_upgrade: bool,
+    config: &ValidationConfig,
+    source: &[u8],
+) -> ExecResult<ModuleReport> {
+    let skeleton = parse_skeleton(source)?;
+    let mut custom_sections = Vec::with_capacity(skeleton.custom.len());
+    let mut schema = None;
+    for ucs in skeleton.custom.iter() {
+        let cs = parse_custom(ucs)?;
+        let name = cs.name.as_ref();
+        // The versioned schema section takes precedence over the legacy ones.
+        let replace =
+            schema.as_ref().map_or(true, |s: &SchemaReport| s.section != VERSIONED_SCHEMA_SECTION);
+        if replace {
+            if let Some(report) = schema_report(version, name, cs.contents) {
+                schema = Some(report);
+            }
+        }
+        custom_sections.push(CustomSectionReport {
+            name: name.to_owned(),
+            size: cs.contents.len(),
+        });
+    }
+    let module = match version {
+        WasmVersion::V0 => {
+            validate_module_with_config(config, &v0::ConcordiumAllowedImports, &skeleton)?
+        }
+        WasmVersion::V1 => validate_module_with_config(
+            config,
+            &v1::ConcordiumAllowedImports {
+                support_upgrade,
+            },
+            &skeleton,
+        )?,
+    };
+
+    let graph = CallGraph::new(version, &module)?;
+    let mut depths = BTreeMap::new();
+    let mut contracts: BTreeMap<String, ContractReport> = BTreeMap::new();
+    for export in module.export.exports.iter() {
+        let index = match export.description {
+            ExportDescription::Func {
+                index,
+            } => index,
+            _ => continue,
+        };
+        let name = export.name.as_ref();
+        if let Some(contract_name) = name.strip_prefix("init_").filter(|n| !n.contains('.')) {
+            let report = graph.function_report(index, &mut depths);
+            contracts.entry(contract_name.to_owned()).or_default().init = Some(report);
+        } else if let Some((contract_name, entrypoint)) = name.split_once('.') {
+            let report = graph.function_report(index, &mut depths);
+            contracts
+                .entry(contract_name.to_owned())
+                .or_default()
+                .entrypoints
+                .insert(entrypoint.to_owned(), report);
+        }
+    }
+
+    Ok(ModuleReport {
+        wasm_version: format!("{:?}", version),
+        contracts,
+        imports: graph.imports.iter().map(|i| i.name.clone()).collect(),
+        memory: module.memory.memory_type.as_ref().map(|mt| MemoryReport {
+            initial_pages: mt.limits.min,
+            max_pages:     mt.limits.max,
+        }),
+        schema,
+        custom_sections,
+    })
+}
+
+/// Name of the custom section containing a versioned schema.
+const VERSIONED_SCHEMA_SECTION: &str = "concordium-schema";
+
+/// Determine whether the custom section contains a schema, and if so which
+/// version. This follows the lookup in
+/// [`get_embedded_schema_v0`](crate::utils::get_embedded_schema_v0) and
+/// [`get_embedded_schema_v1`](crate::utils::get_embedded_schema_v1).
+fn schema_report(version: WasmVersion, section: &str, contents: &[u8]) -> Option<SchemaReport> {
+    let version = match (version, section) {
+        (_, VERSIONED_SCHEMA_SECTION) => {
+            from_bytes::<schema::VersionedModuleSchema>(contents).ok().map(|s| match s {
+                schema::VersionedModuleSchema::V0(_) => "V0",
+                schema::VersionedModuleSchema::V1(_) => "V1",
+                schema::VersionedModuleSchema::V2(_) => "V2",
+                schema::VersionedModuleSchema::V3(_) => "V3",
+            })
+        }
+        (WasmVersion::V0, "concordium-schema-v1") => {
+            from_bytes::<schema::ModuleV0>(contents).ok().map(|_| "V0")
+        }
+        (WasmVersion::V1, "concordium-schema-v2") => {
+            from_bytes::<schema::ModuleV1>(contents).ok().map(|_| "V1")
+        }
+        _ => return None,
+    };
+    Some(SchemaReport {
+        section: section.to_owned(),
+        version: version.map(String::from),
+    })
+}
+
+/// An imported host function.
+struct HostFunction {
+    name:    String,
+    invoke:  bool,
+    upgrade: bool,
+}
+
+impl HostFunction {
+    fn new(version: WasmVersion, types: &[FunctionType], import: &Import) -> ExecResult<Self> {
+        let (mut invoke, mut upgrade) = (false, false);
+        // Only V1 modules can invoke other contracts or upgrade.
+        if let WasmVersion::V1 = version {
+            let ImportDescription::Func {
+                type_idx,
+            } = import.description;
+            let processed = v1::ProcessedImports::try_from_import(types, Import {
+                mod_name:    import.mod_name.clone(),
+                item_name:   import.item_name.clone(),
+                description: ImportDescription::Func {
+                    type_idx,
+                },
+            })?;
+            invoke =
+                matches!(processed.tag, v1::ImportFunc::ReceiveOnly(v1::ReceiveOnlyFunc::Invoke));
+            upgrade =
+                matches!(processed.tag, v1::ImportFunc::ReceiveOnly(v1::ReceiveOnlyFunc::Upgrade));
+        }
+        Ok(Self {
+            name: import.item_name.name.clone(),
+            invoke,
+            upgrade,
+        })
+    }
+}
+
+/// The call graph of a module. Functions are identified by their index in the
+/// module, i.e., imported functions come first.
+struct CallGraph {
+    imports: Vec<HostFunction>,
+    /// Functions that can be called by each function defined in the module.
+    callees: Vec<BTreeSet<FuncIndex>>,
+}
+
+/// Maximum call depth of a function, as computed by [`CallGraph::depth`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Depth {
+    /// The depth is being computed. Encountering a function in this state
+    /// means it is recursive.
+    InProgress,
+    Done(Option<u32>),
+}
+
+impl CallGraph {
+    fn new(version: WasmVersion, module: &Module) -> ExecResult<Self> {
+        let types: Vec<FunctionType> = module.ty.types.iter().map(|ty| (**ty).clone()).collect();
+        let imports = module
+            .import
+            .imports
+            .iter()
+            .map(|import| HostFunction::new(version, &types, import))
+            .collect::<ExecResult<Vec<_>>>()?;
+        let type_of = |idx: FuncIndex| -> Option<&FunctionType> {
+            let idx = idx as usize;
+            let type_idx = match module.import.imports.get(idx) {
+                Some(import) => match import.description {
+                    ImportDescription::Func {
+                        type_idx,
+                    } => type_idx,
+                },
+                None => *module.func.types.get(idx - module.import.imports.len())?,
+            };
+            types.get(type_idx as usize)
+        };
+        let table: BTreeSet<FuncIndex> =
+            module.element.elements.iter().flat_map(|elem| elem.inits.iter().copied()).collect();
+        let callees = module
+            .code
+            .impls
+            .iter()
+            .map(|code| {
+                let mut out = BTreeSet::new();
+                for instr in code.expr.instrs.iter() {
+                    match instr {
+                        OpCode::Call(idx) => {
+                            out.insert(*idx);
+                        }
+                        OpCode::CallIndirect(type_idx) => {
+                            let ty = types.get(*type_idx as usize);
+                            out.extend(table.iter().filter(|idx| type_of(**idx) == ty));
+                        }
+                        _ => (),
+                    }
+                }
+                out
+            })
+            .collect();
+        Ok(Self {
+            imports,
+            callees,
+        })
+    }
+
+    /// Callees of the given function. Imported functions have none.
+    fn callees(&self, idx: FuncIndex) -> Option<&BTreeSet<FuncIndex>> {
+        (idx as usize).checked_sub(self.imports.len()).and_then(|i| self.callees.get(i))
+    }
+
+    fn function_report(
+        &self,
+        idx: FuncIndex,
+        depths: &mut BTreeMap<FuncIndex, Depth>,
+    ) -> FunctionReport {
+        let mut host_functions = BTreeSet::new();
+        let (mut may_invoke, mut may_upgrade) = (false, false);
+        let mut visited = BTreeSet::new();
+        let mut todo = vec![idx];
+        while let Some(next) = todo.pop() {
+            if !visited.insert(next) {
+                continue;
+            }
+            if let Some(host) = self.imports.get(next as usize) {
+                host_functions.insert(host.name.clone());
+                may_invoke |= host.invoke;
+                may_upgrade |= host.upgrade;
+            } else if let Some(callees) = self.callees(next) {
+                todo.extend(callees.iter().copied());
+            }
+        }
+        FunctionReport {
+            host_functions,
+            may_invoke,
+            may_upgrade,
+            max_call_depth: self.depth(idx, depths),
+        }
+    }
+
+    /// Compute the maximum depth of calls starting in the given function, or
+    /// `None` if a recursive function can be reached. Results are cached in
+    /// `depths`.
+    ///
+    /// The call graph is traversed depth-first with an explicit stack, since
+    /// modules can have call chains that are long enough to overflow the
+    /// native stack. Each function is pushed a second time, marked as
+    /// expanded, below its callees. When it is popped again the depths of all
+    /// its callees are known. The functions that are in progress are exactly
+    /// the ones on the current path, so reaching one of them means a recursive
+    /// function is reachable.
+    fn depth(&self, idx: FuncIndex, depths: &mut BTreeMap<FuncIndex, Depth>) -> Option<u32> {
+        let mut stack = vec![(idx, false)];
+        while let Some((next, expanded)) = stack.pop() {
+            let callees = match self.callees(next) {
+                Some(callees) => callees,
+                None => continue,
+            };
+            if expanded {
+                let mut max = Some(0);
+                for callee in callees.iter() {
+                    max = max.zip(self.known_depth(*callee, depths)).map(|(a, b)| a.max(b));
+                }
+                depths.insert(next, Depth::Done(max.map(|d| d + 1)));
+            } else if !depths.contains_key(&next) {
+                depths.insert(next, Depth::InProgress);
+                stack.push((next, true));
+                stack.extend(
+                    callees
+                        .iter()
+                        .filter(|callee| !depths.contains_key(*callee))
+                        .map(|c| (*c, false)),
+                );
+            }
+        }
+        self.known_depth(idx, depths)
+    }
+
+    /// The depth of a function whose depth has been computed, or which is in
+    /// progress.
+    fn known_depth(&self, idx: FuncIndex, depths: &BTreeMap<FuncIndex, Depth>) -> Option<u32> {
+        if self.callees(idx).is_none() {
+            // Calls to host functions do not use a frame.
+            return Some(0);
+        }
+        match depths.get(&idx) {
+            Some(Depth::Done(depth)) => *depth,
+            Some(Depth::InProgress) | None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_cis2_wccd() {
+        let data =
+            std::fs::read("../testdata/schemas/cis2-wccd-embedded-schema-v1-unversioned.wasm.v1")
+                .expect("Could not read file.");
+        let report = analyze_module(WasmVersion::V1, true, &ValidationConfig::V0, &data[8..])
+            .expect("Analysis should succeed.");
+        assert_eq!(report.wasm_version, "V1");
+        assert_eq!(report.contracts.len(), 1);
+        let contract = &report.contracts["CIS2-wCCD"];
+        assert!(contract.init.is_some());
+        assert_eq!(contract.entrypoints.keys().collect::<Vec<_>>(), vec![
+            "balanceOf",
+            "operatorOf",
+            "tokenMetadata",
+            "transfer",
+            "unwrap",
+            "updateOperator",
+            "wrap"
+        ]);
+        let transfer = &contract.entrypoints["transfer"];
+        assert!(transfer.may_invoke);
+        assert!(transfer.host_functions.contains("invoke"));
+        assert!(contract.entrypoints.values().all(|f| !f.may_upgrade));
+        assert!(report.imports.iter().any(|i| i == "get_init_origin"));
+        assert_eq!(report.memory.map(|m| m.initial_pages), Some(17));
+        let schema = report.schema.expect("The module has an embedded schema.");
+        assert_eq!(schema.section, "concordium-schema-v2");
+        assert_eq!(schema.version.as_deref(), Some("V1"));
+        assert!(report.to_json().contains("\"mayInvoke\": true"));
+    }
+
+    /// A call graph without imports where function `i` calls the functions in
+    /// `callees[i]`.
+    fn graph(callees: Vec<Vec<FuncIndex>>) -> CallGraph {
+        CallGraph {
+            imports: Vec::new(),
+            callees: callees.into_iter().map(|c| c.into_iter().collect()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_call_depth() {
+        // 0 calls 1 and 2, 1 calls 3, 2 calls 1.
+        let diamond = graph(vec![vec![1, 2], vec![3], vec![1], vec![]]);
+        let mut depths = BTreeMap::new();
+        assert_eq!(diamond.depth(0, &mut depths), Some(4));
+        assert_eq!(diamond.depth(2, &mut depths), Some(3));
+        assert_eq!(depths.get(&3), Some(&Depth::Done(Some(1))));
+
+        // 0 calls 1, which calls 2, which calls 1. 3 calls 0 and is not part
+        // of the cycle, but can reach it.
+        let cycle = graph(vec![vec![1], vec![2], vec![1], vec![0]]);
+        let mut depths = BTreeMap::new();
+        assert_eq!(cycle.depth(3, &mut depths), None);
+        assert_eq!(cycle.depth(0, &mut depths), None);
+        assert!(depths.values().all(|d| *d == Depth::Done(None)));
+    }
+
+    #[test]
+    fn test_call_depth_long_chain() {
+        // A chain that would overflow the stack with a recursive traversal.
+        let n = 100_000;
+        let chain = graph(
+            (0..n)
+                .map(|i| {
+                    if i + 1 < n {
+                        vec![i + 1]
+                    } else {
+                        vec![]
+                    }
+                })
+                .collect(),
+        );
+        assert_eq!(chain.depth(0, &mut BTreeMap::new()), Some(n));
+    }
+}
@@ -0,0 +1,512 @@
+//! A versioned container format for compiled artifacts, and a cache of such
+//! containers stored in a directory.
+//!
+//! The serialization of [`Artifact`] is not self-describing. It does not record
+//! which module it was compiled from, or which host functions and Wasm features
+//! were allowed when the module was validated. An artifact compiled under
+//! different conditions might be rejected, or worse, accepted when it should
+//! not be. The container prefixes the serialized artifact with a header that
+//! records this, together with a SHA-256 hash of the serialized artifact so
+//! that corrupted files are detected.
+//!
+//! The layout of the container is, with all integers in big-endian,
+//!
+//! | field                        | size     |
+//! |------------------------------|----------|
+//! | magic bytes `CCAF`           | 4        |
+//! | format version               | 2        |
+//! | module reference             | 32       |
+//! | Wasm version                 | 1        |
+//! | flags                        | 1        |
+//! | metering cost table version  | 2        |
+//! | length of the artifact       | 8        |
+//! | SHA-256 hash of the artifact | 32       |
+//! | serialized artifact          | variable |
+//!
+//! The flags record whether the upgrade host function was allowed (bit 0),
+//! and the [`ValidationConfig`] the module was validated with (bits 1 and 2).
+use crate::utils::WasmVersion;
+use concordium_contracts_common::ModuleReference;
+use sha2::Digest;
+use std::{
+    convert::TryInto,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use wasm_transform::{
+    artifact::{Artifact, CompiledFunctionBytes, RunnableCode},
+    metering_transformation::COST_TABLE_VERSION,
+    output::Output,
+    parse::Parseable,
+    utils::parse_artifact,
+    validate::ValidationConfig,
+};
+
+/// Magic bytes at the start of each container.
+const MAGIC: [u8; 4] = *b"CCAF";
+
+/// The current version of the container format.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Size of the header in bytes.
+const HEADER_SIZE: usize = 4 + 2 + 32 + 1 + 1 + 2 + 8 + 32;
+
+/// Extension of container files in the cache directory.
+const FILE_EXTENSION: &str = "artifact";
+
+const FLAG_SUPPORT_UPGRADE: u8 = 0b001;
+const FLAG_SIGN_EXTENSION: u8 = 0b010;
+const FLAG_BULK_MEMORY: u8 = 0b100;
+
+/// The conditions under which an artifact was compiled. An artifact can only be
+/// used under the same conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompilationParameters {
+    /// Version of the contract the module is for.
+    pub wasm_version:       WasmVersion,
+    /// Whether the `upgrade` host function was allowed. This is only relevant
+    /// for V1 modules.
+    pub support_upgrade:    bool,
+    /// Wasm features that were allowed by validation.
+    pub validation:         ValidationConfig,
+    /// Version of the cost table used when injecting metering.
+    pub cost_table_version: u16,
+}
+
+impl CompilationParameters {
+    /// Parameters for compiling with the cost table of this version of the
+    /// library.
+    pub fn new(
+        wasm_version: WasmVersion,
+        support_upgrade: bool,
+        validation: ValidationConfig,
+    ) -> Self {
+        Self {
+            wasm_version,
+            support_upgrade,
+            validation,
+            cost_table_version: COST_TABLE_VERSION,
+        }
+    }
+
+    /// Parameters for compiling modules of the given version deployed in the
+    /// given protocol version. Upgrades are supported by V1 modules from
+    /// protocol version 5.
+    pub fn for_protocol_version(wasm_version: WasmVersion, protocol_version: u8) -> Self {
+        let support_upgrade = wasm_version == WasmVersion::V1 && protocol_version >= 5;
+        Self::new(
+            wasm_version,
+            support_upgrade,
+            ValidationConfig::for_protocol_version(protocol_version),
+        )
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.support_upgrade {
+            flags |= FLAG_SUPPORT_UPGRADE;
+        }
+        if self.validation.allow_sign_extension {
+            flags |= FLAG_SIGN_EXTENSION;
+        }
+        if self.validation.allow_bulk_memory {
+            flags |= FLAG_BULK_MEMORY;
+        }
+        flags
+    }
+}
+
+/// The header of a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    /// Version of the container format.
+    pub format_version: u16,
+    /// Reference of the module the artifact was compiled from.
+    pub module_ref:     ModuleReference,
+    pub parameters:     CompilationParameters,
+    /// SHA-256 hash of the serialized artifact.
+    pub body_hash:      [u8; 32],
+}
+
+/// Reasons a container could not be decoded.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArtifactFormatError {
+    #[error("The container is too short.")]
+    TooShort,
+    #[error("The container does not start with the expected magic bytes.")]
+    InvalidMagic,
+    #[error("Unsupported container format version {0}.")]
+    UnsupportedFormatVersion(u16),
+    #[error("Unsupported Wasm version {0}.")]
+    UnsupportedWasmVersion(u8),
+    #[error("Unknown flags {0:#010b}.")]
+    UnknownFlags(u8),
+    #[error("The length of the artifact does not match the header.")]
+    LengthMismatch,
+    #[error("The hash of the artifact does not match the header.")]
+    HashMismatch,
+}
+
+/// Serialize the artifact, compiled from the given module under the given
+/// conditions, into a container.
+pub fn encode_artifact<I: Output, C: RunnableCode>(
+    module_ref: ModuleReference,
+    parameters: &CompilationParameters,
+    artifact: &Artifact<I, C>,
+) -> Vec<u8> {
+    let mut body = Vec::new();
+    artifact.output(&mut body).expect("Artifact serialization does not fail.");
+    let mut out = Vec::with_capacity(HEADER_SIZE + body.len());
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    out.extend_from_slice(&module_ref.bytes);
+    out.push(match parameters.wasm_version {
+        WasmVersion::V0 => 0,
+        WasmVersion::V1 => 1,
+    });
+    out.push(parameters.flags());
+    out.extend_from_slice(&parameters.cost_table_version.to_be_bytes());
+    out.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    out.extend_from_slice(&sha2::Sha256::digest(&body));
+    out.extend_from_slice(&body);
+    out
+}
+
+/// Decode the header of a container, and check that the artifact matches it.
+/// Returns the header and the serialized artifact.
+pub fn decode_container(bytes: &[u8]) -> Result<(ArtifactHeader, &[u8]), ArtifactFormatError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(ArtifactFormatError::TooShort);
+    }
+    let (header, body) = bytes.split_at(HEADER_SIZE);
+    if header[0..4] != MAGIC {
+        return Err(ArtifactFormatError::InvalidMagic);
+    }
+    // The slice lengths are correct by construction, so the conversions below
+    // cannot fail.
+    let format_version = u16::from_be_bytes(header[4..6].try_into().unwrap());
+    if format_version != FORMAT_VERSION {
+        return Err(ArtifactFormatError::UnsupportedFormatVersion(format_version));
+    }
+    let module_ref_bytes: [u8; 32] = header[6..38].try_into().unwrap();
+    let module_ref = ModuleReference::from(module_ref_bytes);
+    let wasm_version = match header[38] {
+        0 => WasmVersion::V0,
+        1 => WasmVersion::V1,
+        n => return Err(ArtifactFormatError::UnsupportedWasmVersion(n)),
+    };
+    let flags = header[39];
+    if flags & !(FLAG_SUPPORT_UPGRADE | FLAG_SIGN_EXTENSION | FLAG_BULK_MEMORY) != 0 {
+        return Err(ArtifactFormatError::UnknownFlags(flags));
+    }
+    let cost_table_version = u16::from_be_bytes(header[40..42].try_into().unwrap());
+    let body_len = u64::from_be_bytes(header[42..50].try_into().unwrap());
+    if body_len != body.len() as u64 {
+        return Err(ArtifactFormatError::LengthMismatch);
+    }
+    let body_hash: [u8; 32] = header[50..82].try_into().unwrap();
+    if <[u8; 32]>::from(sha2::Sha256::digest(body)) != body_hash {
+        return Err(ArtifactFormatError::HashMismatch);
+    }
+    let header = ArtifactHeader {
+        format_version,
+        module_ref,
+        parameters: CompilationParameters {
+            wasm_version,
+            support_upgrade: flags & FLAG_SUPPORT_UPGRADE != 0,
+            validation: ValidationConfig {
+                allow_sign_extension: flags & FLAG_SIGN_EXTENSION != 0,
+                allow_bulk_memory:    flags & FLAG_BULK_MEMORY != 0,
+            },
+            cost_table_version,
+        },
+        body_hash,
+    };
+    Ok((header, body))
+}
+
+/// A container read from the cache. Its integrity has been checked, and it is
+/// known to have been compiled under the parameters of the cache.
+#[derive(Debug, Clone)]
+pub struct CachedArtifact {
+    header: ArtifactHeader,
+    bytes:  Vec<u8>,
+}
+
+impl CachedArtifact {
+    pub fn header(&self) -> &ArtifactHeader { &self.header }
+
+    /// The serialized artifact.
+    pub fn body(&self) -> &[u8] { &self.bytes[HEADER_SIZE..] }
+
+    /// Deserialize the artifact. Function bodies are not deserialized, but
+    /// refer to the container.
+    pub fn artifact<'a, I: Parseable<'a, ()>>(
+        &'a self,
+    ) -> anyhow::Result<Artifact<I, CompiledFunctionBytes<'a>>> {
+        parse_artifact(self.body())
+    }
+}
+
+/// A cache of compiled artifacts in a directory, keyed by the reference of the
+/// module they were compiled from. Each artifact is stored in its own file.
+///
+/// All artifacts in a cache are compiled under the same
+/// [`CompilationParameters`]. The parameters and the format version are part
+/// of the file name, so caches with different parameters can share a
+/// directory, e.g., while nodes running different protocol versions use it.
+/// Files that are corrupted are treated as stale. They are never returned,
+/// and are replaced when the artifact is inserted again. They are not removed
+/// when they are encountered, since the file may have been replaced by a
+/// valid artifact in the meantime, e.g., by another process.
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    dir:        PathBuf,
+    parameters: CompilationParameters,
+}
+
+impl ArtifactCache {
+    /// Open a cache in the given directory, creating the directory if it does
+    /// not exist.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        parameters: CompilationParameters,
+    ) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            parameters,
+        })
+    }
+
+    pub fn dir(&self) -> &Path { &self.dir }
+
+    pub fn parameters(&self) -> &CompilationParameters { &self.parameters }
+
+    /// Path of the file storing the artifact for the given module. The name
+    /// of the file consists of the module reference, the format version, and
+    /// the parameters of the cache.
+    pub fn path(&self, module_ref: ModuleReference) -> PathBuf {
+        let mut name = String::with_capacity(64 + 16 + FILE_EXTENSION.len());
+        for b in module_ref.bytes.iter() {
+            name.push_str(&format!("{:02x}", b));
+        }
+        let wasm_version = match self.parameters.wasm_version {
+            WasmVersion::V0 => 0,
+            WasmVersion::V1 => 1,
+        };
+        name.push_str(&format!(
+            "-{}-{}-{:02x}-{}.{}",
+            FORMAT_VERSION,
+            wasm_version,
+            self.parameters.flags(),
+            self.parameters.cost_table_version,
+            FILE_EXTENSION
+        ));
+        self.dir.join(name)
+    }
+
+    /// Look up the artifact for the given module. Returns `None` if there is no
+    /// artifact, or the artifact is stale. Stale files are left in place.
+    pub fn get(&self, module_ref: ModuleReference) -> std::io::Result<Option<CachedArtifact>> {
+        let path = self.path(module_ref);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match decode_container(&bytes) {
+            Ok((header, _))
+                if header.module_ref == module_ref && header.parameters == self.parameters =>
+            {
+                Ok(Some(CachedArtifact {
+                    header,
+                    bytes,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Store the artifact compiled from the given module, replacing any
+    /// existing artifact. The artifact must have been compiled under the
+    /// parameters of the cache.
+    ///
+    /// The file is written atomically, so concurrent readers see either the
+    /// old or the new artifact.
+    pub fn insert<I: Output, C: RunnableCode>(
+        &self,
+        module_ref: ModuleReference,
+        artifact: &Artifact<I, C>,
+    ) -> std::io::Result<()> {
+        self.write_container(module_ref, &encode_artifact(module_ref, &self.parameters, artifact))
+    }
+
+    /// Write the container to the file for the given module. The container is
+    /// first written to a temporary file which is then renamed. The name of
+    /// the temporary file is unique to the writer, so that concurrent writers
+    /// of the same artifact, in this or another process, do not interfere.
+    fn write_container(&self, module_ref: ModuleReference, bytes: &[u8]) -> std::io::Result<()> {
+        let path = self.path(module_ref);
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(format!(".{}.{:016x}.tmp", std::process::id(), rand::random::<u64>()));
+        let tmp_path = path.with_file_name(tmp_name);
+        let result = std::fs::File::create(&tmp_path).and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        });
+        let result = result.and_then(|()| std::fs::rename(&tmp_path, &path));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// Remove the artifact for the given module. Returns whether there was a
+    /// file to remove.
+    pub fn remove(&self, module_ref: ModuleReference) -> std::io::Result<bool> {
+        match std::fs::remove_file(self.path(module_ref)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Look up the artifact for the given module, compiling and storing it
+    /// with the given function if it is missing or stale.
+    pub fn get_or_insert_with<I: Output, C: RunnableCode>(
+        &self,
+        module_ref: ModuleReference,
+        compile: impl FnOnce() -> anyhow::Result<Artifact<I, C>>,
+    ) -> anyhow::Result<CachedArtifact> {
+        if let Some(cached) = self.get(module_ref)? {
+            return Ok(cached);
+        }
+        let artifact = compile()?;
+        let bytes = encode_artifact(module_ref, &self.parameters, &artifact);
+        self.write_container(module_ref, &bytes)?;
+        // The container was just encoded, so the header does not need to be
+        // decoded and checked.
+        let header = ArtifactHeader {
+            format_version: FORMAT_VERSION,
+            module_ref,
+            parameters: self.parameters,
+            body_hash: bytes[HEADER_SIZE - 32..HEADER_SIZE]
+                .try_into()
+                .expect("The header contains the hash."),
+        };
+        Ok(CachedArtifact {
+            header,
+            bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::{ConcordiumAllowedImports, ProcessedImports};
+    use wasm_transform::{artifact::CompiledFunction, utils};
+
+    const SOURCE: &[u8] = include_bytes!("../test-data/code/v1/crypto-primitives-tests.wasm");
+
+    fn parameters() -> CompilationParameters {
+        CompilationParameters::for_protocol_version(WasmVersion::V1, 5)
+    }
+
+    /// Compile the test module under the given parameters.
+    fn compile_with(
+        parameters: &CompilationParameters,
+    ) -> anyhow::Result<Artifact<ProcessedImports, CompiledFunction>> {
+        utils::instantiate_with_metering_and_config(
+            &parameters.validation,
+            &ConcordiumAllowedImports {
+                support_upgrade: parameters.support_upgrade,
+            },
+            SOURCE,
+        )
+    }
+
+    fn compile() -> anyhow::Result<Artifact<ProcessedImports, CompiledFunction>> {
+        compile_with(&parameters())
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "artifact-cache-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_container_roundtrip() {
+        let module_ref = ModuleReference::from([7u8; 32]);
+        let artifact = compile().expect("Module should compile.");
+        let mut bytes = encode_artifact(module_ref, &parameters(), &artifact);
+        let (header, body) = decode_container(&bytes).expect("Container should decode.");
+        assert_eq!(header.module_ref, module_ref);
+        assert_eq!(header.parameters, parameters());
+        let mut expected = Vec::new();
+        artifact.output(&mut expected).expect("Artifact serialization does not fail.");
+        assert_eq!(body, &expected[..]);
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(decode_container(&bytes).unwrap_err(), ArtifactFormatError::HashMismatch);
+        bytes.truncate(HEADER_SIZE - 1);
+        assert_eq!(decode_container(&bytes).unwrap_err(), ArtifactFormatError::TooShort);
+    }
+
+    #[test]
+    fn test_cache_stale_entries() {
+        let dir = temp_dir("stale");
+        let module_ref = ModuleReference::from([1u8; 32]);
+        let cache = ArtifactCache::open(&dir, parameters()).expect("Cache should open.");
+        assert!(cache.get(module_ref).expect("Lookup should succeed.").is_none());
+
+        let cached =
+            cache.get_or_insert_with(module_ref, compile).expect("Compilation should succeed.");
+        let artifact: Artifact<ProcessedImports, CompiledFunctionBytes> =
+            cached.artifact().expect("Cached artifact should parse.");
+        assert!(artifact.has_entrypoint("hostfn.hash_sha2_256"));
+        assert!(cache.get(module_ref).expect("Lookup should succeed.").is_some());
+
+        // A cache for a different protocol does not see the entry, and does
+        // not remove it.
+        let other_parameters = CompilationParameters::for_protocol_version(WasmVersion::V1, 6);
+        let other = ArtifactCache::open(&dir, other_parameters).expect("Cache should open.");
+        assert_ne!(other.path(module_ref), cache.path(module_ref));
+        assert!(other.get(module_ref).expect("Lookup should succeed.").is_none());
+        assert!(cache.path(module_ref).exists(), "Entries of other caches are kept.");
+        let other_cached = other
+            .get_or_insert_with(module_ref, || compile_with(&other_parameters))
+            .expect("Compilation should succeed.");
+        assert_eq!(other_cached.header().parameters, other_parameters);
+        assert!(cache.get(module_ref).expect("Lookup should succeed.").is_some());
+        assert!(other.get(module_ref).expect("Lookup should succeed.").is_some());
+
+        // Corrupted entries are stale as well.
+        cache.insert(module_ref, &compile().expect("Module should compile.")).unwrap();
+        let mut bytes = std::fs::read(cache.path(module_ref)).unwrap();
+        bytes[HEADER_SIZE] ^= 1;
+        std::fs::write(cache.path(module_ref), bytes).unwrap();
+        assert!(cache.get(module_ref).expect("Lookup should succeed.").is_none());
+        assert!(cache.path(module_ref).exists(), "Corrupted entries are not removed.");
+        cache.get_or_insert_with(module_ref, compile).expect("Compilation should succeed.");
+        assert!(
+            cache.get(module_ref).expect("Lookup should succeed.").is_some(),
+            "Corrupted entries are replaced."
+        );
+
+        // No temporary files are left behind.
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 2, "Only the entries of the two caches should remain.");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,74 @@
+//! Inspect contract states that were serialized with
+//! [`PersistentState::serialize`].
+use anyhow::Context;
+use concordium_contracts_common::{from_bytes, schema};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use wasm_chain_integration::v1::{
+    inspect::{diff_to_json, state_to_json},
+    trie::{Loader, PersistentState},
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(bin_name = "contract-state", about = "Inspect the state of V1 contracts.")]
+enum Command {
+    #[structopt(name = "dump", about = "Output all the entries of a state as JSON.")]
+    Dump {
+        #[structopt(name = "state", long = "state", help = "File with the serialized state.")]
+        state:      PathBuf,
+        #[structopt(
+            name = "value-type",
+            long = "value-type",
+            help = "File with a serialized schema type used to decode all values."
+        )]
+        value_type: Option<PathBuf>,
+    },
+    #[structopt(name = "diff", about = "Output the differences between two states as JSON.")]
+    Diff {
+        #[structopt(name = "old", long = "old", help = "File with the serialized old state.")]
+        old: PathBuf,
+        #[structopt(name = "new", long = "new", help = "File with the serialized new state.")]
+        new: PathBuf,
+    },
+}
+
+fn read_state(path: &Path) -> anyhow::Result<PersistentState> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Could not read {}.", path.display()))?;
+    PersistentState::deserialize(&mut std::io::Cursor::new(bytes))
+        .with_context(|| format!("Could not deserialize the state in {}.", path.display()))
+}
+
+fn main() -> anyhow::Result<()> {
+    // Deserialized states lie entirely in memory, so the loader is never used.
+    let mut loader = Loader::new(Vec::<u8>::new());
+    let output = match Command::from_args() {
+        Command::Dump {
+            state,
+            value_type,
+        } => {
+            let state = read_state(&state)?;
+            let value_type = match value_type {
+                Some(path) => {
+                    let bytes = std::fs::read(&path)
+                        .with_context(|| format!("Could not read {}.", path.display()))?;
+                    let ty: schema::Type = from_bytes(&bytes)
+                        .map_err(|_| anyhow::anyhow!("Could not parse the schema type."))?;
+                    Some(ty)
+                }
+                None => None,
+            };
+            state_to_json(&state, &mut loader, value_type.as_ref())
+        }
+        Command::Diff {
+            old,
+            new,
+        } => {
+            let old = read_state(&old)?;
+            let new = read_state(&new)?;
+            diff_to_json(&old.diff(&new, &mut loader))
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fmt::Debug};
 
 use arbitrary::{Arbitrary, Result, Unstructured};
 use concordium_contracts_common::{
@@ -8,7 +8,10 @@ use concordium_contracts_common::{
 use wasm_smith::Config;
 pub use wasm_smith::{ConfiguredModule, InterpreterConfig};
 
-use crate::{ExecResult, InitContext, ReceiveContext};
+use crate::{
+    v0::{InitContext, ReceiveContext},
+    ExecResult,
+};
 
 #[derive(Arbitrary, Debug)]
 pub struct RandomizedInterpreterInput<C: Config> {
@@ -99,3 +102,181 @@ pub fn print_module(bytes: &[u8]) {
     let prog = wasmprinter::print_bytes(&bytes).unwrap();
     println!("Processed program:\n{}", prog);
 }
+
+/// Maximum length of keys and prefixes used by [`StateOperation`]s.
+pub const MAX_FUZZ_KEY_LEN: usize = 8;
+/// Maximum length of values used by [`StateOperation`]s.
+pub const MAX_FUZZ_VALUE_LEN: usize = 32;
+
+/// An operation on the state of a V1 contract. Sequences of these are encoded
+/// with [`encode_state_operations`] and executed by a contract, and the result
+/// is compared to that of executing them on a [`StateModel`].
+#[derive(Arbitrary, Debug, Clone)]
+pub enum StateOperation {
+    /// Create an entry, and write the value to it if successful.
+    Create {
+        key:   Vec<u8>,
+        value: Vec<u8>,
+    },
+    /// Delete all entries with the given prefix.
+    DeletePrefix {
+        prefix: Vec<u8>,
+    },
+    /// Iterate over all entries with the given prefix, and either delete the
+    /// iterator afterwards or keep it open until the next
+    /// [`StateOperation::CloseIterators`].
+    Iterate {
+        prefix:    Vec<u8>,
+        keep_open: bool,
+    },
+    /// Delete all iterators that were kept open.
+    CloseIterators,
+}
+
+/// Restrict a key to a small alphabet so that generated keys share prefixes
+/// often.
+fn normalize_key(key: &[u8]) -> Vec<u8> {
+    key.iter().take(MAX_FUZZ_KEY_LEN).map(|b| b % 4).collect()
+}
+
+impl StateOperation {
+    /// Restrict the operation to the sizes the fuzzing contract supports.
+    pub fn normalize(&self) -> Self {
+        match self {
+            StateOperation::Create {
+                key,
+                value,
+            } => StateOperation::Create {
+                key:   normalize_key(key),
+                value: value.iter().take(MAX_FUZZ_VALUE_LEN).copied().collect(),
+            },
+            StateOperation::DeletePrefix {
+                prefix,
+            } => StateOperation::DeletePrefix {
+                prefix: normalize_key(prefix),
+            },
+            StateOperation::Iterate {
+                prefix,
+                keep_open,
+            } => StateOperation::Iterate {
+                prefix:    normalize_key(prefix),
+                keep_open: *keep_open,
+            },
+            StateOperation::CloseIterators => StateOperation::CloseIterators,
+        }
+    }
+}
+
+/// Encode normalized operations as the parameter of the fuzzing contract. Each
+/// operation is a tag byte followed by its arguments, with keys and values
+/// prefixed by their length as a single byte.
+pub fn encode_state_operations(ops: &[StateOperation]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for op in ops {
+        match op {
+            StateOperation::Create {
+                key,
+                value,
+            } => {
+                out.push(0);
+                out.push(key.len() as u8);
+                out.extend_from_slice(key);
+                out.push(value.len() as u8);
+                out.extend_from_slice(value);
+            }
+            StateOperation::DeletePrefix {
+                prefix,
+            } => {
+                out.push(1);
+                out.push(prefix.len() as u8);
+                out.extend_from_slice(prefix);
+            }
+            StateOperation::Iterate {
+                prefix,
+                keep_open,
+            } => {
+                out.push(2);
+                out.push(prefix.len() as u8);
+                out.extend_from_slice(prefix);
+                out.push(u8::from(*keep_open));
+            }
+            StateOperation::CloseIterators => out.push(3),
+        }
+    }
+    out
+}
+
+/// A reference model of the contract state host functions.
+#[derive(Debug, Default)]
+pub struct StateModel {
+    /// The entries of the state.
+    pub entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// Prefixes of iterators that are still open.
+    locked:      Vec<Vec<u8>>,
+}
+
+impl StateModel {
+    /// Apply the operation to the model, and append the output the fuzzing
+    /// contract is expected to produce for it.
+    pub fn apply(&mut self, op: &StateOperation, out: &mut Vec<u8>) {
+        match op {
+            StateOperation::Create {
+                key,
+                value,
+            } => {
+                if self.locked.iter().any(|l| key.starts_with(l)) {
+                    out.push(0);
+                } else {
+                    self.entries.insert(key.clone(), value.clone());
+                    out.push(1);
+                }
+            }
+            StateOperation::DeletePrefix {
+                prefix,
+            } => {
+                let result = if self.entries.is_empty() {
+                    1
+                } else if self.locked.iter().any(|l| l.starts_with(prefix) || prefix.starts_with(l))
+                {
+                    0
+                } else {
+                    let before = self.entries.len();
+                    self.entries.retain(|k, _| !k.starts_with(prefix));
+                    if self.entries.len() < before {
+                        2
+                    } else {
+                        1
+                    }
+                };
+                out.push(result);
+            }
+            StateOperation::Iterate {
+                prefix,
+                keep_open,
+            } => {
+                let mut matching =
+                    self.entries.iter().filter(|(k, _)| k.starts_with(prefix)).peekable();
+                if matching.peek().is_none() {
+                    out.push(0);
+                    return;
+                }
+                out.push(1);
+                for (k, v) in matching {
+                    out.push(1);
+                    out.extend_from_slice(&(k.len() as u32).to_le_bytes());
+                    out.extend_from_slice(k);
+                    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                    out.extend_from_slice(v);
+                }
+                out.push(0);
+                if *keep_open {
+                    self.locked.push(prefix.clone());
+                }
+            }
+            StateOperation::CloseIterators => {
+                out.push(self.locked.len() as u8);
+                self.locked.clear();
+            }
+        }
+    }
+}
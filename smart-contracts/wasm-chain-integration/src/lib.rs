@@ -1,3 +1,5 @@
+pub mod analysis;
+pub mod artifact_cache;
 pub mod constants;
 #[cfg(feature = "fuzz")]
 pub mod fuzz;
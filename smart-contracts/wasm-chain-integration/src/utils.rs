@@ -13,7 +13,7 @@ use wasm_transform::{
     utils, validate,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WasmVersion {
     V0,
     V1,
@@ -336,7 +336,10 @@ unsafe extern "C" fn call_receive_v1(
 #[no_mangle]
 /// Validate the module from source and process it into a runnable artifact
 /// that can be used in calls to [call_receive](./fn.call_receive.html) and
-/// [call_init](./fn.call_init.html).
+/// [call_init](./fn.call_init.html). Only the Wasm features of protocol
+/// versions before 6 are allowed, see
+/// [validate_and_process_v1_with_protocol] for selecting them by protocol
+/// version.
 ///
 /// The arguments are as follows
 /// - `support_upgrade` whether to allow (1) the `upgrade` host call or not (0).
@@ -374,9 +377,71 @@ unsafe extern "C" fn validate_and_process_v1(
     // location where the pointer to the artifact will
     // be written.
     output_artifact_bytes: *mut *const u8,
+) -> *mut u8 {
+    validate_and_process_v1_aux(
+        &ValidationConfig::V0,
+        support_upgrade,
+        wasm_bytes_ptr,
+        wasm_bytes_len,
+        output_len,
+        output_artifact_len,
+        output_artifact_bytes,
+    )
+}
+
+#[no_mangle]
+/// Same as [validate_and_process_v1], but the Wasm features that are allowed
+/// are determined by the protocol version, see
+/// [`ValidationConfig::for_protocol_version`].
+///
+/// The arguments are as follows
+/// - `support_upgrade` whether to allow (1) the `upgrade` host call or not (0).
+/// - `protocol_version` the protocol version in which the module is deployed.
+/// - the remaining arguments are as for [validate_and_process_v1].
+///
+/// # Safety
+/// This function is safe provided all the supplied pointers are not null and
+/// the `wasm_bytes_ptr` points to an array of length at least `wasm_bytes_len`.
+unsafe extern "C" fn validate_and_process_v1_with_protocol(
+    // Whether the current protocol version supports smart contract upgrades.
+    support_upgrade: u8,
+    // The current protocol version.
+    protocol_version: u8,
+    wasm_bytes_ptr: *const u8,
+    wasm_bytes_len: size_t,
+    // this is the total length of the output byte array
+    output_len: *mut size_t,
+    // the length of the artifact byte array
+    output_artifact_len: *mut size_t,
+    // location where the pointer to the artifact will
+    // be written.
+    output_artifact_bytes: *mut *const u8,
+) -> *mut u8 {
+    validate_and_process_v1_aux(
+        &ValidationConfig::for_protocol_version(protocol_version),
+        support_upgrade,
+        wasm_bytes_ptr,
+        wasm_bytes_len,
+        output_len,
+        output_artifact_len,
+        output_artifact_bytes,
+    )
+}
+
+/// The implementation of [validate_and_process_v1] and
+/// [validate_and_process_v1_with_protocol].
+unsafe fn validate_and_process_v1_aux(
+    config: &ValidationConfig,
+    support_upgrade: u8,
+    wasm_bytes_ptr: *const u8,
+    wasm_bytes_len: size_t,
+    output_len: *mut size_t,
+    output_artifact_len: *mut size_t,
+    output_artifact_bytes: *mut *const u8,
 ) -> *mut u8 {
     let wasm_bytes = slice_from_c_bytes!(wasm_bytes_ptr, wasm_bytes_len as usize);
-    match utils::instantiate_with_metering::<ProcessedImports, _>(
+    match utils::instantiate_with_metering_and_config::<ProcessedImports, _>(
+        config,
         &ConcordiumAllowedImports {
             support_upgrade: support_upgrade == 1,
         },
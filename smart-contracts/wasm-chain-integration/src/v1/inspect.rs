@@ -0,0 +1,85 @@
+//! Inspection of contract state. This supports rendering the state of a
+//! contract as JSON, and rendering the differences between two states.
+//!
+//! Keys and values are rendered as hex strings. If a schema type is supplied
+//! for the values of the state, values are additionally decoded according to
+//! it.
+use super::trie::{BackingStoreLoad, PersistentState, StateChange, StateDiff};
+use concordium_contracts_common::{schema, Cursor};
+use serde_json::{json, Value as JsonValue};
+use std::fmt::Write;
+
+/// Render bytes as a lowercase hex string.
+fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 * bytes.len());
+    for b in bytes {
+        // Writing to a string cannot fail.
+        let _ = write!(out, "{:02x}", b);
+    }
+    out
+}
+
+/// Render a value, decoding it with the given type if one is supplied. If
+/// decoding fails the error is recorded instead of the decoded value.
+fn value_to_json(value: &[u8], value_type: Option<&schema::Type>) -> JsonValue {
+    let mut out = json!({
+        "size": value.len(),
+        "value": to_hex(value),
+    });
+    if let Some(ty) = value_type {
+        let mut cursor = Cursor::new(value);
+        match ty.to_json(&mut cursor) {
+            Ok(decoded) if cursor.offset == value.len() => out["decoded"] = decoded,
+            Ok(_) => out["decodeError"] = json!("Value has trailing bytes."),
+            Err(_) => out["decodeError"] = json!("Value does not match the schema type."),
+        }
+    }
+    out
+}
+
+/// Render the entire state as a JSON array of entries in increasing order of
+/// keys. Each entry has the fields `key`, `size` and `value`, and, if a type
+/// is supplied, either `decoded` or `decodeError`.
+pub fn state_to_json(
+    state: &PersistentState,
+    loader: &mut impl BackingStoreLoad,
+    value_type: Option<&schema::Type>,
+) -> JsonValue {
+    let entries = state
+        .clone()
+        .into_iterator(loader)
+        .map(|(key, value)| {
+            let mut entry = value_to_json(&value, value_type);
+            entry["key"] = json!(to_hex(&key));
+            entry
+        })
+        .collect::<Vec<_>>();
+    JsonValue::Array(entries)
+}
+
+/// Render the differences between two states as a JSON array of changes in
+/// increasing order of keys.
+pub fn diff_to_json(diff: &StateDiff) -> JsonValue {
+    let changes = diff
+        .changes
+        .iter()
+        .map(|(key, change)| match change {
+            StateChange::Added {
+                size,
+            } => json!({"key": to_hex(key), "change": "added", "newSize": size}),
+            StateChange::Removed {
+                size,
+            } => json!({"key": to_hex(key), "change": "removed", "oldSize": size}),
+            StateChange::Changed {
+                old_size,
+                new_size,
+            } => json!({
+                "key": to_hex(key),
+                "change": "changed",
+                "oldSize": old_size,
+                "newSize": new_size
+            }),
+        })
+        .collect::<Vec<_>>();
+    JsonValue::Array(changes)
+}
@@ -1,10 +1,15 @@
 #[cfg(test)]
 mod crypto_primitives_tests;
 #[cfg(test)]
+mod profiling_tests;
+#[cfg(test)]
 mod tests;
 
 #[cfg(feature = "enable-ffi")]
 mod ffi;
+pub mod inspect;
+pub mod profiling;
+pub mod simulator;
 pub mod trie;
 mod types;
 
@@ -23,6 +28,7 @@ use wasm_transform::{
     artifact::{Artifact, CompiledFunction, CompiledFunctionBytes, RunnableCode},
     machine::{self, ExecutionOutcome, NoInterrupt},
     utils,
+    validate::ValidationConfig,
 };
 
 /// Interrupt triggered by the smart contract to execute an instruction on the
@@ -1264,7 +1270,37 @@ pub fn invoke_init<BackingStore: BackingStoreLoad, R: RunnableCode>(
     init_ctx: impl v0::HasInitContext,
     init_invocation: InitInvocation,
     limit_logs_and_return_values: bool,
+    loader: BackingStore,
+) -> ExecResult<InitResult> {
+    run_init(
+        artifact.borrow(),
+        init_ctx,
+        init_invocation,
+        limit_logs_and_return_values,
+        loader,
+        |artifact, host, name, args| artifact.run(host, name, args),
+    )
+}
+
+/// Invoke an init-function, using the supplied function to execute the
+/// artifact. This allows execution to be instrumented, e.g., for profiling.
+pub(crate) fn run_init<
+    'a,
+    BackingStore: BackingStoreLoad,
+    R: RunnableCode,
+    Ctx: v0::HasInitContext,
+>(
+    artifact: &Artifact<ProcessedImports, R>,
+    init_ctx: Ctx,
+    init_invocation: InitInvocation<'a>,
+    limit_logs_and_return_values: bool,
     mut loader: BackingStore,
+    run: impl FnOnce(
+        &Artifact<ProcessedImports, R>,
+        &mut InitHost<'_, BackingStore, ParameterRef<'a>, Ctx>,
+        &str,
+        &[Value],
+    ) -> machine::RunResult<ExecutionOutcome<NoInterrupt>>,
 ) -> ExecResult<InitResult> {
     let mut initial_state = trie::MutableState::initial_state();
     let inner = initial_state.get_inner(&mut loader);
@@ -1279,7 +1315,7 @@ pub fn invoke_init<BackingStore: BackingStoreLoad, R: RunnableCode>(
         limit_logs_and_return_values,
         init_ctx,
     };
-    let result = artifact.borrow().run(&mut host, init_invocation.init_name, &[Value::I64(
+    let result = run(artifact, &mut host, init_invocation.init_name, &[Value::I64(
         init_invocation.amount.micro_ccd() as i64,
     )]);
     let return_value = std::mem::take(&mut host.return_value);
@@ -1511,6 +1547,30 @@ pub struct InvokeFromSourceCtx<'a> {
     /// `false` then parsing and validation will reject modules that use the
     /// `upgrade` function.
     pub support_upgrade: bool,
+    /// Wasm features that are allowed by validation. This depends on the
+    /// protocol version, see [`ValidationConfig::for_protocol_version`].
+    pub validation:      ValidationConfig,
+}
+
+impl<'a> InvokeFromSourceCtx<'a> {
+    /// Construct the context with the Wasm features that are allowed before
+    /// protocol version 6, i.e., [`ValidationConfig::V0`].
+    pub fn new(
+        source: &'a [u8],
+        amount: Amount,
+        parameter: ParameterRef<'a>,
+        energy: InterpreterEnergy,
+        support_upgrade: bool,
+    ) -> Self {
+        Self {
+            source,
+            amount,
+            parameter,
+            energy,
+            support_upgrade,
+            validation: ValidationConfig::V0,
+        }
+    }
 }
 
 /// Invokes an init-function from Wasm module bytes
@@ -1522,7 +1582,8 @@ pub fn invoke_init_from_source<BackingStore: BackingStoreLoad>(
     loader: BackingStore,
     limit_logs_and_return_values: bool,
 ) -> ExecResult<InitResult> {
-    let artifact = utils::instantiate(
+    let artifact = utils::instantiate_with_config(
+        &ctx.validation,
         &ConcordiumAllowedImports {
             support_upgrade: ctx.support_upgrade,
         },
@@ -1552,7 +1613,8 @@ pub fn invoke_init_with_metering_from_source<BackingStore: BackingStoreLoad>(
     loader: BackingStore,
     limit_logs_and_return_values: bool,
 ) -> ExecResult<InitResult> {
-    let artifact = utils::instantiate_with_metering(
+    let artifact = utils::instantiate_with_metering_and_config(
+        &ctx.validation,
         &ConcordiumAllowedImports {
             support_upgrade: ctx.support_upgrade,
         },
@@ -1721,6 +1783,40 @@ pub fn invoke_receive<
     receive_invocation: ReceiveInvocation,
     instance_state: InstanceState<BackingStore>,
     params: ReceiveParams,
+) -> ExecResult<ReceiveResult<R2, Ctx2>> {
+    run_receive(
+        artifact,
+        receive_ctx,
+        receive_invocation,
+        instance_state,
+        params,
+        |artifact, host, name, args| artifact.run(host, name, args),
+    )
+}
+
+/// Invoke a receive-function, using the supplied function to execute the
+/// artifact. This allows execution to be instrumented, e.g., for profiling.
+pub(crate) fn run_receive<
+    'a,
+    'b,
+    BackingStore: BackingStoreLoad,
+    R1: RunnableCode,
+    R2: RunnableCode,
+    Art: Borrow<Artifact<ProcessedImports, R1>> + Into<Arc<Artifact<ProcessedImports, R2>>>,
+    Ctx1: HasReceiveContext,
+    Ctx2: From<Ctx1>,
+>(
+    artifact: Art,
+    receive_ctx: Ctx1,
+    receive_invocation: ReceiveInvocation<'a>,
+    instance_state: InstanceState<'b, BackingStore>,
+    params: ReceiveParams,
+    run: impl FnOnce(
+        &Artifact<ProcessedImports, R1>,
+        &mut ReceiveHost<'b, BackingStore, ParameterRef<'a>, Ctx1>,
+        &str,
+        &[Value],
+    ) -> machine::RunResult<ExecutionOutcome<Interrupt>>,
 ) -> ExecResult<ReceiveResult<R2, Ctx2>> {
     let mut host = ReceiveHost {
         energy:    receive_invocation.energy,
@@ -1736,7 +1832,7 @@ pub fn invoke_receive<
     };
 
     let result =
-        artifact.borrow().run(&mut host, receive_invocation.receive_name.get_chain_name(), &[
+        run(artifact.borrow(), &mut host, receive_invocation.receive_name.get_chain_name(), &[
             Value::I64(receive_invocation.amount.micro_ccd() as i64),
         ]);
     process_receive_result(artifact, host, result)
@@ -1873,7 +1969,8 @@ pub fn invoke_receive_from_source<
     instance_state: InstanceState<BackingStore>,
     params: ReceiveParams,
 ) -> ExecResult<ReceiveResult<CompiledFunction, Ctx2>> {
-    let artifact = utils::instantiate(
+    let artifact = utils::instantiate_with_config(
+        &ctx.validation,
         &ConcordiumAllowedImports {
             support_upgrade: ctx.support_upgrade,
         },
@@ -1907,7 +2004,8 @@ pub fn invoke_receive_with_metering_from_source<
     instance_state: InstanceState<BackingStore>,
     params: ReceiveParams,
 ) -> ExecResult<ReceiveResult<CompiledFunction, Ctx2>> {
-    let artifact = utils::instantiate_with_metering(
+    let artifact = utils::instantiate_with_metering_and_config(
+        &ctx.validation,
         &ConcordiumAllowedImports {
             support_upgrade: ctx.support_upgrade,
         },
@@ -0,0 +1,325 @@
+//! Profiling of energy use of V1 contracts.
+//!
+//! Modules with injected metering charge for execution by calling the
+//! `account_energy` host function at the beginning of each basic block, and
+//! host functions charge for their own execution according to the costs in
+//! [`constants`](crate::constants). Profiling keeps track of the Wasm call
+//! stack during execution and attributes each charge to the stack at the point
+//! it was made. Charges made by host functions, other than the metering ones,
+//! are attributed to a separate frame naming the host function.
+//!
+//! The resulting [`Profile`] can be written in the folded-stack format, which
+//! is understood by flamegraph tools such as `inferno` and `flamegraph.pl`.
+//!
+//! Profiling a receive function covers execution up to the first interrupt,
+//! since handling the interrupt is the responsibility of the caller.
+use super::{
+    run_init, run_receive, ConcordiumAllowedImports, HasReceiveContext, ImportFunc, InitHost,
+    InitResult, InstanceState, InvokeFromSourceCtx, ProcessedImports, ReceiveHost,
+    ReceiveInvocation, ReceiveParams, ReceiveResult,
+};
+use crate::{trie::BackingStoreLoad, v0, ExecResult};
+use concordium_contracts_common::ReceiveName;
+use std::{cell::RefCell, collections::BTreeMap, io::Write};
+use wasm_transform::{
+    artifact::{Artifact, CompiledFunction, InternalOpcode},
+    machine::{self, Host, RuntimeStack},
+    metering_transformation::NUM_ADDED_FUNCTIONS,
+    parse::parse_skeleton,
+    trace::{FunctionNames, StackFrame, Tracer},
+    types::FuncIndex,
+    utils,
+};
+
+/// Name of the frame that the cost of the initial memory is attributed to.
+const INITIAL_MEMORY_FRAME: &str = "<initial memory>";
+
+/// Name of the frame that the cost of growing the memory is attributed to.
+const MEMORY_GROW_FRAME: &str = "<memory.grow>";
+
+/// Energy used by execution, attributed to call stacks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Energy used, keyed by the call stack, outermost frame first.
+    stacks: BTreeMap<Vec<String>, u64>,
+}
+
+impl Profile {
+    /// Total energy recorded in the profile.
+    pub fn total(&self) -> u64 { self.stacks.values().sum() }
+
+    /// Iterate over all call stacks, outermost frame first, together with the
+    /// energy used directly by the innermost frame of the stack.
+    pub fn stacks(&self) -> impl Iterator<Item = (&[String], u64)> {
+        self.stacks.iter().map(|(stack, energy)| (stack.as_slice(), *energy))
+    }
+
+    /// Energy used directly by each function or host function, excluding the
+    /// energy used by the functions it calls.
+    pub fn self_costs(&self) -> BTreeMap<&str, u64> {
+        let mut out = BTreeMap::new();
+        for (stack, energy) in self.stacks.iter() {
+            if let Some(leaf) = stack.last() {
+                *out.entry(leaf.as_str()).or_insert(0) += energy;
+            }
+        }
+        out
+    }
+
+    /// Write the profile in the folded-stack format, one line per call stack.
+    pub fn write_folded(&self, out: &mut impl Write) -> std::io::Result<()> {
+        for (stack, energy) in self.stacks.iter() {
+            writeln!(out, "{} {}", stack.join(";"), energy)?;
+        }
+        Ok(())
+    }
+
+    /// Render the profile in the folded-stack format.
+    pub fn to_folded(&self) -> String {
+        let mut out = Vec::new();
+        // Writing to a vector cannot fail.
+        let _ = self.write_folded(&mut out);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+}
+
+/// The leaf of a call stack that energy is attributed to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Leaf {
+    /// Energy was used by the Wasm function at the top of the stack.
+    Function,
+    /// Energy was used by the given host function.
+    Host(String),
+    /// Energy was used for memory, which is not attributed to any function.
+    Memory(&'static str),
+}
+
+/// State of profiling that is shared between the tracer and the host.
+#[derive(Default)]
+struct Profiler {
+    /// Current call stack, using indices of functions in the metered module.
+    stack:   Vec<FuncIndex>,
+    /// Energy used, keyed by the call stack and the leaf.
+    charges: BTreeMap<(Vec<FuncIndex>, Leaf), u64>,
+}
+
+impl Profiler {
+    fn record(&mut self, leaf: Leaf, energy: u64) {
+        if energy > 0 {
+            *self.charges.entry((self.stack.clone(), leaf)).or_insert(0) += energy;
+        }
+    }
+
+    /// Construct the profile, naming functions by their names in the `name`
+    /// section of the original module, or their export names if they have no
+    /// name there.
+    fn into_profile<R>(
+        self,
+        names: Option<&FunctionNames>,
+        artifact: &Artifact<ProcessedImports, R>,
+    ) -> Profile {
+        let exports = artifact
+            .export
+            .iter()
+            .map(|(name, idx)| (*idx, name.name.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        let function_name = |idx: FuncIndex| {
+            // Metering inserts its own imports at the beginning of the module,
+            // shifting all the original functions.
+            let original = idx.saturating_sub(NUM_ADDED_FUNCTIONS);
+            if let Some(name) = names.and_then(|names| names.get(original)) {
+                name.to_string()
+            } else if let Some(name) = exports.get(&idx) {
+                name.to_string()
+            } else {
+                format!("func[{}]", original)
+            }
+        };
+        let mut stacks = BTreeMap::new();
+        for ((stack, leaf), energy) in self.charges {
+            let mut frames = stack.into_iter().map(function_name).collect::<Vec<_>>();
+            match leaf {
+                Leaf::Function => (),
+                Leaf::Host(name) => frames.push(name),
+                Leaf::Memory(name) => frames.push(name.into()),
+            }
+            *stacks.entry(frames).or_insert(0) += energy;
+        }
+        Profile {
+            stacks,
+        }
+    }
+}
+
+/// A tracer that maintains the call stack of the profiler.
+struct ProfilingTracer<'p> {
+    profiler: &'p RefCell<Profiler>,
+}
+
+impl<'p> Tracer for ProfilingTracer<'p> {
+    fn start(&mut self, call_stack: &[StackFrame]) {
+        let mut profiler = self.profiler.borrow_mut();
+        profiler.stack.clear();
+        profiler.stack.extend(call_stack.iter().map(|frame| frame.function));
+    }
+
+    fn instruction(&mut self, _: FuncIndex, _: usize, _: InternalOpcode, _: usize) {}
+
+    fn enter(&mut self, function: FuncIndex) { self.profiler.borrow_mut().stack.push(function); }
+
+    fn exit(&mut self) { self.profiler.borrow_mut().stack.pop(); }
+}
+
+/// Access to the remaining energy of a host.
+trait HasEnergy {
+    fn remaining_energy(&self) -> u64;
+}
+
+impl<'a, BackingStore, ParamType, Ctx> HasEnergy for InitHost<'a, BackingStore, ParamType, Ctx> {
+    fn remaining_energy(&self) -> u64 { self.energy.energy }
+}
+
+impl<'a, BackingStore, ParamType, Ctx> HasEnergy for ReceiveHost<'a, BackingStore, ParamType, Ctx> {
+    fn remaining_energy(&self) -> u64 { self.energy.energy }
+}
+
+/// A host that delegates to another host, recording the energy charged by each
+/// host function call.
+struct ProfilingHost<'p, 'h, H> {
+    host:     &'h mut H,
+    profiler: &'p RefCell<Profiler>,
+}
+
+impl<'p, 'h, H: Host<ProcessedImports> + HasEnergy> Host<ProcessedImports>
+    for ProfilingHost<'p, 'h, H>
+{
+    type Interrupt = H::Interrupt;
+
+    fn tick_initial_memory(&mut self, num_pages: u32) -> machine::RunResult<()> {
+        let before = self.host.remaining_energy();
+        let result = self.host.tick_initial_memory(num_pages);
+        let used = before - self.host.remaining_energy();
+        self.profiler.borrow_mut().record(Leaf::Memory(INITIAL_MEMORY_FRAME), used);
+        result
+    }
+
+    fn call(
+        &mut self,
+        f: &ProcessedImports,
+        memory: &mut Vec<u8>,
+        stack: &mut RuntimeStack,
+    ) -> machine::RunResult<Option<Self::Interrupt>> {
+        let before = self.host.remaining_energy();
+        let result = self.host.call(f, memory, stack);
+        let used = before - self.host.remaining_energy();
+        let leaf = match f.tag {
+            ImportFunc::ChargeEnergy | ImportFunc::TrackCall | ImportFunc::TrackReturn => {
+                Leaf::Function
+            }
+            ImportFunc::ChargeMemoryAlloc => Leaf::Memory(MEMORY_GROW_FRAME),
+            ImportFunc::Common(cf) => Leaf::Host(format!("{:?}", cf)),
+            ImportFunc::InitOnly(iof) => Leaf::Host(format!("{:?}", iof)),
+            ImportFunc::ReceiveOnly(rof) => Leaf::Host(format!("{:?}", rof)),
+        };
+        self.profiler.borrow_mut().record(leaf, used);
+        result
+    }
+}
+
+/// Parse the module, inject metering, and compile it. Also extract function
+/// names from the module, if it has a `name` section.
+fn instantiate_for_profiling(
+    ctx: &InvokeFromSourceCtx,
+) -> ExecResult<(Artifact<ProcessedImports, CompiledFunction>, Option<FunctionNames>)> {
+    let names = FunctionNames::from_skeleton(&parse_skeleton(ctx.source)?)?;
+    let artifact = utils::instantiate_with_metering_and_config(
+        &ctx.validation,
+        &ConcordiumAllowedImports {
+            support_upgrade: ctx.support_upgrade,
+        },
+        ctx.source,
+    )?;
+    Ok((artifact, names))
+}
+
+/// Same as
+/// [`invoke_init_with_metering_from_source`](super::invoke_init_with_metering_from_source),
+/// but additionally produces a profile of the energy used by execution.
+pub fn invoke_init_with_profiling_from_source<BackingStore: BackingStoreLoad>(
+    ctx: InvokeFromSourceCtx,
+    init_ctx: impl v0::HasInitContext,
+    init_name: &str,
+    loader: BackingStore,
+    limit_logs_and_return_values: bool,
+) -> ExecResult<(InitResult, Profile)> {
+    let (artifact, names) = instantiate_for_profiling(&ctx)?;
+    let profiler = RefCell::new(Profiler::default());
+    let result = run_init(
+        &artifact,
+        init_ctx,
+        super::InitInvocation {
+            amount: ctx.amount,
+            init_name,
+            parameter: ctx.parameter,
+            energy: ctx.energy,
+        },
+        limit_logs_and_return_values,
+        loader,
+        |artifact, host, name, args| {
+            let mut host = ProfilingHost {
+                host,
+                profiler: &profiler,
+            };
+            let mut tracer = ProfilingTracer {
+                profiler: &profiler,
+            };
+            artifact.run_with_tracer(&mut host, name, args, &mut tracer)
+        },
+    )?;
+    let profile = profiler.into_inner().into_profile(names.as_ref(), &artifact);
+    Ok((result, profile))
+}
+
+/// Same as
+/// [`invoke_receive_with_metering_from_source`](super::invoke_receive_with_metering_from_source),
+/// but additionally produces a profile of the energy used by execution up to
+/// termination or the first interrupt.
+pub fn invoke_receive_with_profiling_from_source<
+    BackingStore: BackingStoreLoad,
+    Ctx1: HasReceiveContext,
+    Ctx2: From<Ctx1>,
+>(
+    ctx: InvokeFromSourceCtx,
+    receive_ctx: Ctx1,
+    receive_name: ReceiveName,
+    instance_state: InstanceState<BackingStore>,
+    params: ReceiveParams,
+) -> ExecResult<(ReceiveResult<CompiledFunction, Ctx2>, Profile)> {
+    let (artifact, names) = instantiate_for_profiling(&ctx)?;
+    let artifact = std::sync::Arc::new(artifact);
+    let profiler = RefCell::new(Profiler::default());
+    let result = run_receive(
+        artifact.clone(),
+        receive_ctx,
+        ReceiveInvocation {
+            amount: ctx.amount,
+            receive_name,
+            parameter: ctx.parameter,
+            energy: ctx.energy,
+        },
+        instance_state,
+        params,
+        |artifact, host, name, args| {
+            let mut host = ProfilingHost {
+                host,
+                profiler: &profiler,
+            };
+            let mut tracer = ProfilingTracer {
+                profiler: &profiler,
+            };
+            artifact.run_with_tracer(&mut host, name, args, &mut tracer)
+        },
+    )?;
+    let profile = profiler.into_inner().into_profile(names.as_ref(), artifact.as_ref());
+    Ok((result, profile))
+}
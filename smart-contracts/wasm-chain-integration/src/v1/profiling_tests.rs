@@ -0,0 +1,81 @@
+//! Tests of attribution of energy by the profiler.
+use crate::{
+    v0,
+    v1::{
+        profiling::invoke_receive_with_profiling_from_source, trie::Loader, InstanceState,
+        InvokeFromSourceCtx, ReceiveContext, ReceiveParams, ReceiveResult,
+    },
+    InterpreterEnergy,
+};
+use concordium_contracts_common::{
+    Address, Amount, ChainMetadata, ContractAddress, OwnedEntrypointName, ReceiveName, Timestamp,
+};
+use wasm_transform::validate::ValidationConfig;
+
+static CONTRACT_BYTES: &[u8] =
+    include_bytes!("../../test-data/code/v1/crypto-primitives-tests.wasm");
+
+#[test]
+fn test_profile_attributes_host_costs() {
+    let owner = concordium_contracts_common::AccountAddress([0u8; 32]);
+    let receive_ctx: ReceiveContext<&[u8]> = ReceiveContext {
+        common:     v0::ReceiveContext {
+            metadata: ChainMetadata {
+                slot_time: Timestamp::from_timestamp_millis(0),
+            },
+            invoker: owner,
+            self_address: ContractAddress {
+                index:    0,
+                subindex: 0,
+            },
+            self_balance: Amount::from_ccd(1000),
+            sender: Address::Account(owner),
+            owner,
+            sender_policies: &[],
+        },
+        entrypoint: OwnedEntrypointName::new_unchecked("hash_sha2_256".into()),
+    };
+    let start_energy = 1_000_000_000;
+    let parameter = 1000u32.to_le_bytes();
+    let mut mutable_state = crate::v1::trie::MutableState::initial_state();
+    let mut loader = Loader {
+        inner: Vec::new(),
+    };
+    let inner = mutable_state.get_inner(&mut loader);
+    let instance_state = InstanceState::new(loader, inner);
+    let (result, profile): (ReceiveResult<_, ReceiveContext<&[u8]>>, _) =
+        invoke_receive_with_profiling_from_source(
+            InvokeFromSourceCtx {
+                source:          CONTRACT_BYTES,
+                amount:          Amount::from_micro_ccd(0),
+                parameter:       &parameter,
+                energy:          InterpreterEnergy::from(start_energy),
+                support_upgrade: true,
+                validation:      ValidationConfig::V0,
+            },
+            receive_ctx,
+            ReceiveName::new_unchecked("hostfn.hash_sha2_256"),
+            instance_state,
+            ReceiveParams::new_p5(),
+        )
+        .expect("Invocation should succeed.");
+    let remaining_energy = match result {
+        ReceiveResult::Success {
+            remaining_energy,
+            ..
+        } => remaining_energy,
+        _ => panic!("Execution should succeed."),
+    };
+    assert_eq!(
+        profile.total(),
+        start_energy - remaining_energy,
+        "All energy should be attributed to some call stack."
+    );
+    let costs = profile.self_costs();
+    assert!(costs.get("HashSHA2_256").map_or(false, |c| *c > 0), "Hashing has a cost.");
+    assert!(costs.get("hostfn.hash_sha2_256").map_or(false, |c| *c > 0), "Execution has a cost.");
+    assert!(
+        profile.to_folded().lines().any(|l| l.starts_with("hostfn.hash_sha2_256;HashSHA2_256 ")),
+        "The host function is called from the entrypoint."
+    );
+}
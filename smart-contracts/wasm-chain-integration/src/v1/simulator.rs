@@ -0,0 +1,1259 @@
+//! An offline, in-memory simulator of a chain running V1 smart contracts.
+//!
+//! The [`Simulator`] keeps track of accounts and their balances, deployed
+//! modules keyed by their [`ModuleReference`], and contract instances keyed
+//! by their [`ContractAddress`]. It executes init and receive functions in the
+//! same way as the scheduler in the node does. In particular it handles all
+//! the [`Interrupt`]s a V1 contract can produce (calls to other contracts,
+//! transfers to accounts, upgrades and queries), resumes the interrupted
+//! contract with the appropriate [`InvokeResponse`], and rolls back all the
+//! changes made by a nested call if that call fails. Rollback of contract state
+//! uses the generations of [`MutableState`].
+//!
+//! The simulator only keeps track of interpreter energy. The conversion to NRG
+//! and the costs the scheduler charges in addition to execution, e.g., for
+//! loading modules or storing the new state, are not modelled.
+use super::{
+    invoke_init, invoke_receive, resume_receive,
+    trie::{EmptyCollector, Loader, MutableState, PersistentState},
+    ConcordiumAllowedImports, InitInvocation, InitResult, InstanceState, Interrupt, InvokeFailure,
+    InvokeResponse, ParameterRef, ParameterVec, ProcessedImports, ReceiveContext,
+    ReceiveInvocation, ReceiveParams, ReceiveResult, ReturnValue,
+};
+use crate::{v0, ExecResult, InterpreterEnergy};
+use anyhow::{bail, ensure};
+use concordium_contracts_common::{
+    AccountAddress, Address, Amount, ChainMetadata, ContractAddress, ModuleReference,
+    OwnedEntrypointName, ReceiveName, Timestamp,
+};
+use sha2::Digest;
+use std::{
+    collections::{btree_map, BTreeMap},
+    sync::Arc,
+};
+use thiserror::Error;
+use wasm_transform::{
+    artifact::{Artifact, CompiledFunction},
+    utils,
+    validate::ValidationConfig,
+};
+
+/// A loader for contract states that live entirely in memory. All the states
+/// maintained by the simulator are of this kind, so nothing is ever loaded
+/// through it.
+type MemoryLoader = Loader<&'static [u8]>;
+
+fn memory_loader() -> MemoryLoader {
+    Loader {
+        inner: &[],
+    }
+}
+
+/// An artifact of a deployed module, ready to be executed.
+pub type SimulatorArtifact = Arc<Artifact<ProcessedImports, CompiledFunction>>;
+
+/// Exchange rates that are returned to contracts that query them. Each rate is
+/// represented as a fraction `(numerator, denominator)`.
+#[derive(Debug, Clone, Copy)]
+pub struct ExchangeRates {
+    pub euro_per_energy:    (u64, u64),
+    pub micro_ccd_per_euro: (u64, u64),
+}
+
+/// A contract instance as recorded in the simulator.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// The module the instance currently uses. This changes on upgrades.
+    pub module_ref:    ModuleReference,
+    /// Name of the contract, without the `init_` prefix.
+    pub contract_name: String,
+    /// The account that created the instance.
+    pub owner:         AccountAddress,
+    /// Current balance of the instance.
+    pub balance:       Amount,
+    /// Current state of the instance.
+    pub state:         PersistentState,
+}
+
+/// An element of the trace of execution of a transaction. The elements are
+/// recorded in the order they occur. Elements produced by nested calls that
+/// failed are not part of the trace, since the effects of those calls are
+/// rolled back.
+#[derive(Debug, Clone)]
+pub enum TraceElement {
+    /// A receive function terminated successfully. This is recorded at the
+    /// end of execution of the entrypoint, after any nested calls.
+    Updated {
+        address:      ContractAddress,
+        /// The entrypoint that was invoked. If the contract's fallback
+        /// entrypoint was executed this is the entrypoint that was intended.
+        entrypoint:   OwnedEntrypointName,
+        sender:       Address,
+        amount:       Amount,
+        parameter:    ParameterVec,
+        return_value: ReturnValue,
+        /// Logs produced since the last interrupt.
+        logs:         Vec<Vec<u8>>,
+        /// Interpreter energy used by the entrypoint, including any nested
+        /// calls it made.
+        energy_used:  u64,
+    },
+    /// Execution of the contract was interrupted to handle a transfer, a call,
+    /// or an upgrade. Queries do not produce this element.
+    Interrupted {
+        address: ContractAddress,
+        /// Logs produced since the last interrupt.
+        logs:    Vec<Vec<u8>>,
+    },
+    /// Execution of the contract resumed after the operation was handled.
+    Resumed {
+        address: ContractAddress,
+        /// Whether the operation succeeded.
+        success: bool,
+    },
+    /// A contract transferred CCD to an account.
+    Transferred {
+        from:   ContractAddress,
+        amount: Amount,
+        to:     AccountAddress,
+    },
+    /// A contract upgraded itself to a new module.
+    Upgraded {
+        address: ContractAddress,
+        from:    ModuleReference,
+        to:      ModuleReference,
+    },
+}
+
+/// Errors that prevent a transaction from being executed at all. These are
+/// returned (wrapped in [`anyhow::Error`]) by [`Simulator::init`] and
+/// [`Simulator::update`] and can be recovered using `downcast_ref`.
+#[derive(Debug, Error)]
+pub enum SimulationError {
+    #[error("Account {0:?} does not exist.")]
+    UnknownAccount(AccountAddress),
+    #[error("Contract instance {0:?} does not exist.")]
+    UnknownContract(ContractAddress),
+    #[error("Module {0:?} has not been deployed.")]
+    UnknownModule(ModuleReference),
+    #[error("The module does not contain the init function {0}.")]
+    MissingInitFunction(String),
+    #[error("The contract does not have the entrypoint {0}, and no fallback entrypoint.")]
+    MissingEntrypoint(String),
+    #[error("The sender's balance is insufficient to cover the amount.")]
+    InsufficientBalance,
+}
+
+/// Data needed to initialize a new contract instance.
+#[derive(Debug, Clone, Copy)]
+pub struct InitRequest<'a> {
+    /// The account creating the instance. It becomes the owner of the
+    /// instance.
+    pub sender:        AccountAddress,
+    pub module_ref:    ModuleReference,
+    /// Name of the contract, without the `init_` prefix.
+    pub contract_name: &'a str,
+    pub parameter:     ParameterRef<'a>,
+    pub amount:        Amount,
+    pub energy:        InterpreterEnergy,
+}
+
+/// Data needed to invoke an entrypoint of an existing instance.
+#[derive(Debug, Clone)]
+pub struct UpdateRequest<'a> {
+    /// The account that sends the transaction.
+    pub invoker:    AccountAddress,
+    pub address:    ContractAddress,
+    pub entrypoint: OwnedEntrypointName,
+    pub parameter:  ParameterRef<'a>,
+    pub amount:     Amount,
+    pub energy:     InterpreterEnergy,
+}
+
+/// Outcome of [`Simulator::init`]. Only a successful outcome changes the state
+/// of the simulator.
+#[derive(Debug)]
+pub enum InitOutcome {
+    Success {
+        /// Address of the new instance.
+        address:      ContractAddress,
+        logs:         Vec<Vec<u8>>,
+        return_value: ReturnValue,
+        energy_used:  u64,
+    },
+    Reject {
+        reason:       i32,
+        return_value: ReturnValue,
+        energy_used:  u64,
+    },
+    Trap {
+        error:       anyhow::Error,
+        energy_used: u64,
+    },
+    OutOfEnergy,
+}
+
+/// Outcome of [`Simulator::update`]. Only a successful outcome changes the
+/// state of the simulator.
+#[derive(Debug)]
+pub enum UpdateOutcome {
+    Success {
+        /// Return value of the top-level entrypoint.
+        return_value: ReturnValue,
+        /// The trace of all the operations that were performed.
+        trace:        Vec<TraceElement>,
+        energy_used:  u64,
+    },
+    /// The top-level entrypoint rejected. Nested calls that reject only cause
+    /// a rejection here if the top-level contract propagates the error.
+    Reject {
+        reason:       i32,
+        return_value: ReturnValue,
+        energy_used:  u64,
+    },
+    Trap {
+        error:       anyhow::Error,
+        energy_used: u64,
+    },
+    /// Execution, at any level of nesting, ran out of energy.
+    OutOfEnergy,
+}
+
+/// Changes to an instance that are made during a transaction.
+#[derive(Debug, Clone)]
+struct InstanceChanges {
+    module_ref:         ModuleReference,
+    balance:            Amount,
+    state:              MutableState,
+    /// Incremented every time the state of the instance is modified. This is
+    /// used to tell an interrupted contract whether its state changed while
+    /// the operation was handled.
+    modification_index: u64,
+}
+
+/// The changes made by a transaction that is being executed. Cloning the
+/// change set is cheap for contract states since [`MutableState`] is a shallow
+/// handle; a clone is used as a checkpoint to roll back to if a nested call
+/// fails.
+#[derive(Debug, Clone, Default)]
+struct ChangeSet {
+    accounts:  BTreeMap<AccountAddress, Amount>,
+    instances: BTreeMap<ContractAddress, InstanceChanges>,
+}
+
+/// Outcome of executing an entrypoint of an existing contract.
+enum CallOutcome {
+    Success {
+        return_value: ReturnValue,
+    },
+    Reject {
+        reason:       i32,
+        return_value: ReturnValue,
+    },
+    Trap {
+        error: anyhow::Error,
+    },
+    OutOfEnergy,
+}
+
+/// An in-memory chain that can execute V1 contracts. See the module
+/// documentation for details.
+#[derive(Debug)]
+pub struct Simulator {
+    /// The slot time that contracts observe.
+    pub slot_time:       Timestamp,
+    /// Exchange rates returned to contracts that query them.
+    pub exchange_rates:  ExchangeRates,
+    /// Runtime limits that apply to receive functions.
+    pub params:          ReceiveParams,
+    /// Whether modules are allowed to use the `upgrade` host function.
+    pub support_upgrade: bool,
+    /// Wasm features that deployed modules are allowed to use.
+    pub validation:      ValidationConfig,
+    accounts:            BTreeMap<AccountAddress, Amount>,
+    modules:             BTreeMap<ModuleReference, SimulatorArtifact>,
+    instances:           BTreeMap<ContractAddress, Instance>,
+    next_index:          u64,
+}
+
+impl Simulator {
+    /// Construct an empty simulator with the runtime parameters of protocol
+    /// version 5.
+    pub fn new(slot_time: Timestamp) -> Self {
+        Self {
+            slot_time,
+            exchange_rates: ExchangeRates {
+                euro_per_energy:    (1, 50000),
+                micro_ccd_per_euro: (1_000_000, 1),
+            },
+            params: ReceiveParams::new_p5(),
+            support_upgrade: true,
+            validation: ValidationConfig::for_protocol_version(5),
+            accounts: BTreeMap::new(),
+            modules: BTreeMap::new(),
+            instances: BTreeMap::new(),
+            next_index: 0,
+        }
+    }
+
+    /// Create an account with the given balance, or set the balance if the
+    /// account already exists.
+    pub fn create_account(&mut self, address: AccountAddress, balance: Amount) {
+        self.accounts.insert(address, balance);
+    }
+
+    /// Get the balance of an account, if the account exists.
+    pub fn account_balance(&self, address: &AccountAddress) -> Option<Amount> {
+        self.accounts.get(address).copied()
+    }
+
+    /// Get an instance, if it exists.
+    pub fn instance(&self, address: &ContractAddress) -> Option<&Instance> {
+        self.instances.get(address)
+    }
+
+    /// Parse, validate, and compile a V1 module, injecting metering, and add it
+    /// to the simulator. The returned reference is computed in the same way
+    /// as on the chain.
+    pub fn deploy_module(&mut self, source: &[u8]) -> ExecResult<ModuleReference> {
+        let artifact = utils::instantiate_with_metering_and_config::<ProcessedImports, _>(
+            &self.validation,
+            &ConcordiumAllowedImports {
+                support_upgrade: self.support_upgrade,
+            },
+            source,
+        )?;
+        let module_ref = module_reference(source);
+        self.modules.insert(module_ref, Arc::new(artifact));
+        Ok(module_ref)
+    }
+
+    /// Create a new instance by executing the init function of the given
+    /// contract. On success the amount is transferred from the sender to the
+    /// new instance.
+    pub fn init(&mut self, request: InitRequest) -> ExecResult<InitOutcome> {
+        let artifact = self
+            .modules
+            .get(&request.module_ref)
+            .cloned()
+            .ok_or(SimulationError::UnknownModule(request.module_ref))?;
+        let init_name = format!("init_{}", request.contract_name);
+        ensure!(
+            artifact.has_entrypoint(init_name.as_str()),
+            SimulationError::MissingInitFunction(init_name)
+        );
+        let sender_balance = self
+            .account_balance(&request.sender)
+            .ok_or(SimulationError::UnknownAccount(request.sender))?;
+        ensure!(
+            sender_balance.micro_ccd >= request.amount.micro_ccd,
+            SimulationError::InsufficientBalance
+        );
+        let init_ctx: v0::InitContext<v0::OwnedPolicyBytes> = v0::InitContext {
+            metadata:        ChainMetadata {
+                slot_time: self.slot_time,
+            },
+            init_origin:     request.sender,
+            sender_policies: Vec::new(),
+        };
+        let result = invoke_init(
+            artifact.as_ref(),
+            init_ctx,
+            InitInvocation {
+                amount:    request.amount,
+                init_name: &init_name,
+                parameter: request.parameter,
+                energy:    request.energy,
+            },
+            self.params.limit_logs_and_return_values,
+            memory_loader(),
+        )?;
+        let start_energy = request.energy.energy;
+        match result {
+            InitResult::Success {
+                logs,
+                return_value,
+                remaining_energy,
+                mut state,
+            } => {
+                let state = state.freeze(&mut memory_loader(), &mut EmptyCollector);
+                let address = ContractAddress {
+                    index:    self.next_index,
+                    subindex: 0,
+                };
+                self.next_index += 1;
+                self.accounts.insert(
+                    request.sender,
+                    Amount::from_micro_ccd(sender_balance.micro_ccd - request.amount.micro_ccd),
+                );
+                self.instances.insert(address, Instance {
+                    module_ref: request.module_ref,
+                    contract_name: request.contract_name.into(),
+                    owner: request.sender,
+                    balance: request.amount,
+                    state,
+                });
+                Ok(InitOutcome::Success {
+                    address,
+                    logs: logs.logs.into_iter().collect(),
+                    return_value,
+                    energy_used: start_energy - remaining_energy,
+                })
+            }
+            InitResult::Reject {
+                reason,
+                return_value,
+                remaining_energy,
+            } => Ok(InitOutcome::Reject {
+                reason,
+                return_value,
+                energy_used: start_energy - remaining_energy,
+            }),
+            InitResult::Trap {
+                error,
+                remaining_energy,
+            } => Ok(InitOutcome::Trap {
+                error,
+                energy_used: start_energy - remaining_energy,
+            }),
+            InitResult::OutOfEnergy => Ok(InitOutcome::OutOfEnergy),
+        }
+    }
+
+    /// Invoke an entrypoint of an existing instance, handling all the
+    /// operations the contract triggers. If the top-level call succeeds all
+    /// the changes are committed, otherwise the simulator is left unchanged.
+    pub fn update(&mut self, request: UpdateRequest) -> ExecResult<UpdateOutcome> {
+        let invoker_balance = self
+            .account_balance(&request.invoker)
+            .ok_or(SimulationError::UnknownAccount(request.invoker))?;
+        ensure!(
+            invoker_balance.micro_ccd >= request.amount.micro_ccd,
+            SimulationError::InsufficientBalance
+        );
+        let mut changes = ChangeSet::default();
+        changes.accounts.insert(
+            request.invoker,
+            Amount::from_micro_ccd(invoker_balance.micro_ccd - request.amount.micro_ccd),
+        );
+        let mut trace = Vec::new();
+        let mut energy = request.energy;
+        let outcome = self.call(
+            &mut changes,
+            &mut trace,
+            request.invoker,
+            Address::Account(request.invoker),
+            request.address,
+            request.entrypoint.clone(),
+            request.parameter.to_vec(),
+            request.amount,
+            &mut energy,
+        )?;
+        let energy_used = request.energy.energy - energy.energy;
+        match outcome {
+            Ok(CallOutcome::Success {
+                return_value,
+            }) => {
+                self.commit(changes);
+                Ok(UpdateOutcome::Success {
+                    return_value,
+                    trace,
+                    energy_used,
+                })
+            }
+            Ok(CallOutcome::Reject {
+                reason,
+                return_value,
+            }) => Ok(UpdateOutcome::Reject {
+                reason,
+                return_value,
+                energy_used,
+            }),
+            Ok(CallOutcome::Trap {
+                error,
+            }) => Ok(UpdateOutcome::Trap {
+                error,
+                energy_used,
+            }),
+            Ok(CallOutcome::OutOfEnergy) => Ok(UpdateOutcome::OutOfEnergy),
+            Err(InvokeFailure::NonExistentContract) => {
+                bail!(SimulationError::UnknownContract(request.address))
+            }
+            Err(InvokeFailure::NonExistentEntrypoint) => {
+                let name: &str = request.entrypoint.as_entrypoint_name().into();
+                bail!(SimulationError::MissingEntrypoint(name.into()))
+            }
+            Err(failure) => bail!("Unexpected failure of a top-level call: {:?}", failure),
+        }
+    }
+
+    /// Write the changes of a successful transaction to the simulator.
+    fn commit(&mut self, changes: ChangeSet) {
+        self.accounts.extend(changes.accounts);
+        let mut loader = memory_loader();
+        for (address, mut instance_changes) in changes.instances {
+            if let Some(instance) = self.instances.get_mut(&address) {
+                instance.module_ref = instance_changes.module_ref;
+                instance.balance = instance_changes.balance;
+                instance.state = instance_changes.state.freeze(&mut loader, &mut EmptyCollector);
+            }
+        }
+    }
+
+    /// Balance of the account, taking into account the changes made by the
+    /// current transaction.
+    fn current_account_balance(
+        &self,
+        changes: &ChangeSet,
+        address: &AccountAddress,
+    ) -> Option<Amount> {
+        changes.accounts.get(address).copied().or_else(|| self.account_balance(address))
+    }
+
+    /// Get the changes to the instance made in the current transaction. If the
+    /// instance has not yet been touched in this transaction its state is
+    /// thawed from the last committed state.
+    fn instance_changes<'b>(
+        &self,
+        changes: &'b mut ChangeSet,
+        address: ContractAddress,
+    ) -> Option<&'b mut InstanceChanges> {
+        match changes.instances.entry(address) {
+            btree_map::Entry::Occupied(o) => Some(o.into_mut()),
+            btree_map::Entry::Vacant(v) => {
+                let instance = self.instances.get(&address)?;
+                Some(v.insert(InstanceChanges {
+                    module_ref:         instance.module_ref,
+                    balance:            instance.balance,
+                    state:              instance.state.thaw(),
+                    modification_index: 0,
+                }))
+            }
+        }
+    }
+
+    /// Execute an entrypoint of an instance, crediting it with the given
+    /// amount. The amount must already have been debited from the sender.
+    /// Returns `Ok(Err(_))` if the contract or entrypoint does not exist.
+    ///
+    /// The caller is responsible for rolling back the changes if the outcome
+    /// is not [`CallOutcome::Success`].
+    #[allow(clippy::too_many_arguments)]
+    fn call(
+        &self,
+        changes: &mut ChangeSet,
+        trace: &mut Vec<TraceElement>,
+        invoker: AccountAddress,
+        sender: Address,
+        address: ContractAddress,
+        entrypoint: OwnedEntrypointName,
+        parameter: ParameterVec,
+        amount: Amount,
+        energy: &mut InterpreterEnergy,
+    ) -> ExecResult<Result<CallOutcome, InvokeFailure>> {
+        let (contract_name, owner) = match self.instances.get(&address) {
+            Some(instance) => (&instance.contract_name, instance.owner),
+            None => return Ok(Err(InvokeFailure::NonExistentContract)),
+        };
+        let instance = match self.instance_changes(changes, address) {
+            Some(instance) => instance,
+            None => return Ok(Err(InvokeFailure::NonExistentContract)),
+        };
+        let artifact = match self.modules.get(&instance.module_ref) {
+            Some(artifact) => artifact.clone(),
+            None => bail!("Instance {:?} refers to an unknown module.", address),
+        };
+        let entrypoint_str: &str = entrypoint.as_entrypoint_name().into();
+        let receive_name = {
+            let name = format!("{}.{}", contract_name, entrypoint_str);
+            let fallback = format!("{}.", contract_name);
+            if artifact.has_entrypoint(name.as_str()) {
+                name
+            } else if artifact.has_entrypoint(fallback.as_str()) {
+                fallback
+            } else {
+                return Ok(Err(InvokeFailure::NonExistentEntrypoint));
+            }
+        };
+        instance.balance = Amount::from_micro_ccd(instance.balance.micro_ccd + amount.micro_ccd);
+        let mut loader = memory_loader();
+        // Start a new generation of the state so that the changes made by this
+        // call can be rolled back by restoring the previous handle.
+        instance.state = instance.state.make_fresh_generation(&mut loader);
+        let receive_ctx = ReceiveContext {
+            common:     v0::ReceiveContext {
+                metadata: ChainMetadata {
+                    slot_time: self.slot_time,
+                },
+                invoker,
+                self_address: address,
+                self_balance: instance.balance,
+                sender,
+                owner,
+                sender_policies: v0::OwnedPolicyBytes::new(),
+            },
+            entrypoint: entrypoint.clone(),
+        };
+        let start_energy = energy.energy;
+        let inner = instance.state.get_inner(&mut loader);
+        let instance_state = InstanceState::new(loader, inner);
+        let mut result = invoke_receive::<_, CompiledFunction, CompiledFunction, _, _, _>(
+            artifact,
+            receive_ctx,
+            ReceiveInvocation {
+                amount,
+                receive_name: ReceiveName::new_unchecked(&receive_name),
+                parameter: &parameter,
+                energy: *energy,
+            },
+            instance_state,
+            self.params,
+        )?;
+        loop {
+            match result {
+                ReceiveResult::Success {
+                    logs,
+                    state_changed,
+                    return_value,
+                    remaining_energy,
+                } => {
+                    energy.energy = remaining_energy;
+                    if state_changed {
+                        self.mark_modified(changes, address);
+                    }
+                    trace.push(TraceElement::Updated {
+                        address,
+                        entrypoint,
+                        sender,
+                        amount,
+                        parameter,
+                        return_value: return_value.clone(),
+                        logs: logs.logs.into_iter().collect(),
+                        energy_used: start_energy - remaining_energy,
+                    });
+                    return Ok(Ok(CallOutcome::Success {
+                        return_value,
+                    }));
+                }
+                ReceiveResult::Interrupt {
+                    remaining_energy,
+                    state_changed,
+                    logs,
+                    config,
+                    interrupt,
+                } => {
+                    energy.energy = remaining_energy;
+                    if state_changed {
+                        self.mark_modified(changes, address);
+                    }
+                    let record = interrupt.should_clear_logs();
+                    if record {
+                        trace.push(TraceElement::Interrupted {
+                            address,
+                            logs: logs.logs.into_iter().collect(),
+                        });
+                    }
+                    let modification_index = self.modification_index(changes, address);
+                    let response = match self
+                        .handle_interrupt(changes, trace, invoker, address, interrupt, energy)?
+                    {
+                        Some(response) => response,
+                        None => return Ok(Ok(CallOutcome::OutOfEnergy)),
+                    };
+                    let state_updated =
+                        self.modification_index(changes, address) != modification_index;
+                    if record {
+                        trace.push(TraceElement::Resumed {
+                            address,
+                            success: matches!(response, InvokeResponse::Success { .. }),
+                        });
+                    }
+                    let instance = self
+                        .instance_changes(changes, address)
+                        .ok_or(SimulationError::UnknownContract(address))?;
+                    result = resume_receive(
+                        config,
+                        response,
+                        *energy,
+                        &mut instance.state,
+                        state_updated,
+                        memory_loader(),
+                    )?;
+                }
+                ReceiveResult::Reject {
+                    reason,
+                    return_value,
+                    remaining_energy,
+                } => {
+                    energy.energy = remaining_energy;
+                    return Ok(Ok(CallOutcome::Reject {
+                        reason,
+                        return_value,
+                    }));
+                }
+                ReceiveResult::Trap {
+                    error,
+                    remaining_energy,
+                } => {
+                    energy.energy = remaining_energy;
+                    return Ok(Ok(CallOutcome::Trap {
+                        error,
+                    }));
+                }
+                ReceiveResult::OutOfEnergy => {
+                    energy.energy = 0;
+                    return Ok(Ok(CallOutcome::OutOfEnergy));
+                }
+            }
+        }
+    }
+
+    fn mark_modified(&self, changes: &mut ChangeSet, address: ContractAddress) {
+        if let Some(instance) = changes.instances.get_mut(&address) {
+            instance.modification_index += 1;
+        }
+    }
+
+    fn modification_index(&self, changes: &ChangeSet, address: ContractAddress) -> u64 {
+        changes.instances.get(&address).map_or(0, |instance| instance.modification_index)
+    }
+
+    /// Handle an operation triggered by the contract at the given address.
+    /// Returns `None` if execution ran out of energy while handling the
+    /// operation, in which case the entire transaction fails.
+    fn handle_interrupt(
+        &self,
+        changes: &mut ChangeSet,
+        trace: &mut Vec<TraceElement>,
+        invoker: AccountAddress,
+        address: ContractAddress,
+        interrupt: Interrupt,
+        energy: &mut InterpreterEnergy,
+    ) -> ExecResult<Option<InvokeResponse>> {
+        let self_balance = match self.instance_changes(changes, address) {
+            Some(instance) => instance.balance,
+            None => bail!(SimulationError::UnknownContract(address)),
+        };
+        let failure = |kind| {
+            Ok(Some(InvokeResponse::Failure {
+                kind,
+            }))
+        };
+        match interrupt {
+            Interrupt::Transfer {
+                to,
+                amount,
+            } => {
+                if self_balance.micro_ccd < amount.micro_ccd {
+                    return failure(InvokeFailure::InsufficientAmount);
+                }
+                let to_balance = match self.current_account_balance(changes, &to) {
+                    Some(balance) => balance,
+                    None => return failure(InvokeFailure::NonExistentAccount),
+                };
+                let new_balance = Amount::from_micro_ccd(self_balance.micro_ccd - amount.micro_ccd);
+                self.set_instance_balance(changes, address, new_balance);
+                changes
+                    .accounts
+                    .insert(to, Amount::from_micro_ccd(to_balance.micro_ccd + amount.micro_ccd));
+                trace.push(TraceElement::Transferred {
+                    from: address,
+                    amount,
+                    to,
+                });
+                Ok(Some(InvokeResponse::Success {
+                    new_balance,
+                    data: None,
+                }))
+            }
+            Interrupt::Call {
+                address: callee,
+                parameter,
+                name,
+                amount,
+            } => {
+                if self_balance.micro_ccd < amount.micro_ccd {
+                    return failure(InvokeFailure::InsufficientAmount);
+                }
+                let checkpoint = changes.clone();
+                let trace_len = trace.len();
+                self.set_instance_balance(
+                    changes,
+                    address,
+                    Amount::from_micro_ccd(self_balance.micro_ccd - amount.micro_ccd),
+                );
+                let outcome = self.call(
+                    changes,
+                    trace,
+                    invoker,
+                    Address::Contract(address),
+                    callee,
+                    name,
+                    parameter,
+                    amount,
+                    energy,
+                )?;
+                let kind = match outcome {
+                    Ok(CallOutcome::Success {
+                        return_value,
+                    }) => {
+                        let new_balance = self
+                            .instance_changes(changes, address)
+                            .map_or(self_balance, |instance| instance.balance);
+                        return Ok(Some(InvokeResponse::Success {
+                            new_balance,
+                            data: Some(return_value),
+                        }));
+                    }
+                    Ok(CallOutcome::OutOfEnergy) => return Ok(None),
+                    Ok(CallOutcome::Reject {
+                        reason,
+                        return_value,
+                    }) => InvokeFailure::ContractReject {
+                        code: reason,
+                        data: return_value,
+                    },
+                    Ok(CallOutcome::Trap {
+                        ..
+                    }) => InvokeFailure::RuntimeError,
+                    Err(kind) => kind,
+                };
+                // roll back all the changes made by the failed call.
+                *changes = checkpoint;
+                trace.truncate(trace_len);
+                failure(kind)
+            }
+            Interrupt::Upgrade {
+                module_ref,
+            } => {
+                let artifact = match self.modules.get(&module_ref) {
+                    Some(artifact) => artifact,
+                    None => return failure(InvokeFailure::UpgradeInvalidModuleRef),
+                };
+                let contract_name = match self.instances.get(&address) {
+                    Some(instance) => &instance.contract_name,
+                    None => bail!(SimulationError::UnknownContract(address)),
+                };
+                // Only V1 modules can be deployed to the simulator, so the
+                // version of the new module is always valid.
+                if !artifact.has_entrypoint(format!("init_{}", contract_name).as_str()) {
+                    return failure(InvokeFailure::UpgradeInvalidContractName);
+                }
+                if let Some(instance) = self.instance_changes(changes, address) {
+                    trace.push(TraceElement::Upgraded {
+                        address,
+                        from: instance.module_ref,
+                        to: module_ref,
+                    });
+                    instance.module_ref = module_ref;
+                }
+                Ok(Some(InvokeResponse::Success {
+                    new_balance: self_balance,
+                    data:        None,
+                }))
+            }
+            Interrupt::QueryAccountBalance {
+                address: account,
+            } => match self.current_account_balance(changes, &account) {
+                Some(balance) => {
+                    // The response consists of the total, staked, and locked
+                    // balance. The simulator does not model staking or
+                    // releases schedules.
+                    let mut data = Vec::with_capacity(24);
+                    data.extend_from_slice(&balance.micro_ccd.to_le_bytes());
+                    data.extend_from_slice(&0u64.to_le_bytes());
+                    data.extend_from_slice(&0u64.to_le_bytes());
+                    Ok(Some(InvokeResponse::Success {
+                        new_balance: self_balance,
+                        data:        Some(data),
+                    }))
+                }
+                None => failure(InvokeFailure::NonExistentAccount),
+            },
+            Interrupt::QueryContractBalance {
+                address: contract,
+            } => match self.instance_changes(changes, contract) {
+                Some(instance) => Ok(Some(InvokeResponse::Success {
+                    new_balance: self_balance,
+                    data:        Some(instance.balance.micro_ccd.to_le_bytes().to_vec()),
+                })),
+                None => failure(InvokeFailure::NonExistentContract),
+            },
+            Interrupt::QueryExchangeRates => {
+                let rates = self.exchange_rates;
+                let mut data = Vec::with_capacity(32);
+                data.extend_from_slice(&rates.euro_per_energy.0.to_le_bytes());
+                data.extend_from_slice(&rates.euro_per_energy.1.to_le_bytes());
+                data.extend_from_slice(&rates.micro_ccd_per_euro.0.to_le_bytes());
+                data.extend_from_slice(&rates.micro_ccd_per_euro.1.to_le_bytes());
+                Ok(Some(InvokeResponse::Success {
+                    new_balance: self_balance,
+                    data:        Some(data),
+                }))
+            }
+        }
+    }
+
+    fn set_instance_balance(
+        &self,
+        changes: &mut ChangeSet,
+        address: ContractAddress,
+        balance: Amount,
+    ) {
+        if let Some(instance) = self.instance_changes(changes, address) {
+            instance.balance = balance;
+        }
+    }
+}
+
+/// Compute the reference of a V1 module in the same way as the chain does,
+/// i.e., as the SHA256 hash of the versioned module.
+pub fn module_reference(source: &[u8]) -> ModuleReference {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update((source.len() as u32).to_be_bytes());
+    hasher.update(source);
+    ModuleReference::from(<[u8; 32]>::from(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(n: u8) -> AccountAddress { AccountAddress([n; 32]) }
+
+    const SOURCE: &[u8] = include_bytes!("../../test-data/code/v1/simulator-test.wasm");
+
+    const UPGRADED_SOURCE: &[u8] =
+        include_bytes!("../../test-data/code/v1/simulator-test-upgraded.wasm");
+
+    const ENERGY: u64 = 10_000_000;
+
+    /// Mask of the part of the response of `invoke` and `upgrade` that is 0
+    /// if, and only if, the operation succeeded.
+    const FAILURE_MASK: u64 = 0xff_ffff_ffff;
+
+    /// Create a simulator with a funded account, and deploy the test module.
+    fn setup() -> (Simulator, ModuleReference) {
+        let mut simulator = Simulator::new(Timestamp::from_timestamp_millis(0));
+        simulator.create_account(account(0), Amount::from_ccd(1000));
+        let module_ref = simulator.deploy_module(SOURCE).expect("The test module is valid.");
+        (simulator, module_ref)
+    }
+
+    fn init(
+        simulator: &mut Simulator,
+        module_ref: ModuleReference,
+        amount: u64,
+    ) -> ContractAddress {
+        let outcome = simulator
+            .init(InitRequest {
+                sender: account(0),
+                module_ref,
+                contract_name: "test",
+                parameter: &[],
+                amount: Amount::from_micro_ccd(amount),
+                energy: InterpreterEnergy::from(ENERGY),
+            })
+            .expect("The init request is valid.");
+        match outcome {
+            InitOutcome::Success {
+                address,
+                ..
+            } => address,
+            other => panic!("Initialization should succeed, but was {:?}.", other),
+        }
+    }
+
+    fn update(
+        simulator: &mut Simulator,
+        address: ContractAddress,
+        entrypoint: &str,
+        parameter: &[u8],
+    ) -> ExecResult<UpdateOutcome> {
+        simulator.update(UpdateRequest {
+            invoker: account(0),
+            address,
+            entrypoint: OwnedEntrypointName::new_unchecked(entrypoint.into()),
+            parameter,
+            amount: Amount::from_micro_ccd(0),
+            energy: InterpreterEnergy::from(ENERGY),
+        })
+    }
+
+    /// Invoke an entrypoint that is expected to succeed and return the return
+    /// value, the trace, and the energy used.
+    fn update_success(
+        simulator: &mut Simulator,
+        address: ContractAddress,
+        entrypoint: &str,
+        parameter: &[u8],
+    ) -> (ReturnValue, Vec<TraceElement>, u64) {
+        match update(simulator, address, entrypoint, parameter)
+            .expect("The update request is valid.")
+        {
+            UpdateOutcome::Success {
+                return_value,
+                trace,
+                energy_used,
+            } => (return_value, trace, energy_used),
+            other => panic!("Invoking {} should succeed, but was {:?}.", entrypoint, other),
+        }
+    }
+
+    /// Invoke an entrypoint that returns the response of an operation.
+    fn update_response(
+        simulator: &mut Simulator,
+        address: ContractAddress,
+        entrypoint: &str,
+        parameter: &[u8],
+    ) -> (u64, Vec<TraceElement>, u64) {
+        let (return_value, trace, energy_used) =
+            update_success(simulator, address, entrypoint, parameter);
+        let mut response = [0u8; 8];
+        response.copy_from_slice(&return_value);
+        (u64::from_le_bytes(response), trace, energy_used)
+    }
+
+    /// The value in the state of the instance.
+    fn get_state(simulator: &mut Simulator, address: ContractAddress) -> Vec<u8> {
+        update_success(simulator, address, "get", &[]).0
+    }
+
+    fn balance(simulator: &Simulator, address: ContractAddress) -> u64 {
+        simulator.instance(&address).expect("The instance exists.").balance.micro_ccd
+    }
+
+    /// The payload of `invoke` for calling the given entrypoint.
+    fn call_payload(
+        address: ContractAddress,
+        entrypoint: &str,
+        parameter: &[u8],
+        amount: u64,
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&address.index.to_le_bytes());
+        out.extend_from_slice(&address.subindex.to_le_bytes());
+        out.extend_from_slice(&(parameter.len() as u16).to_le_bytes());
+        out.extend_from_slice(parameter);
+        out.extend_from_slice(&(entrypoint.len() as u16).to_le_bytes());
+        out.extend_from_slice(entrypoint.as_bytes());
+        out.extend_from_slice(&amount.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_nested_call() {
+        let (mut simulator, module_ref) = setup();
+        let a = init(&mut simulator, module_ref, 10);
+        let b = init(&mut simulator, module_ref, 20);
+        let (response, trace, energy_used) =
+            update_response(&mut simulator, a, "forward", &call_payload(b, "set", &[9], 5));
+        assert_eq!(response & FAILURE_MASK, 0, "The nested call should succeed.");
+        assert_eq!(balance(&simulator, a), 5);
+        assert_eq!(balance(&simulator, b), 25);
+        assert_eq!(get_state(&mut simulator, a), vec![1]);
+        assert_eq!(get_state(&mut simulator, b), vec![9]);
+
+        assert_eq!(trace.len(), 4, "Unexpected trace {:?}.", trace);
+        assert!(matches!(
+            &trace[0],
+            TraceElement::Interrupted { address, logs } if *address == a && *logs == vec![vec![1]]
+        ));
+        let inner_energy = match &trace[1] {
+            TraceElement::Updated {
+                address,
+                sender,
+                amount,
+                parameter,
+                energy_used,
+                ..
+            } => {
+                assert_eq!(*address, b);
+                assert_eq!(*sender, Address::Contract(a));
+                assert_eq!(amount.micro_ccd, 5);
+                assert_eq!(*parameter, vec![9]);
+                *energy_used
+            }
+            other => panic!("Expected the nested call to be recorded, but was {:?}.", other),
+        };
+        assert!(matches!(
+            &trace[2],
+            TraceElement::Resumed { address, success: true } if *address == a
+        ));
+        match &trace[3] {
+            TraceElement::Updated {
+                address,
+                sender,
+                logs,
+                energy_used: outer_energy,
+                ..
+            } => {
+                assert_eq!(*address, a);
+                assert_eq!(*sender, Address::Account(account(0)));
+                assert!(logs.is_empty(), "Logs before the interrupt are recorded there.");
+                assert!(
+                    inner_energy > 0 && inner_energy < *outer_energy,
+                    "The energy of the caller includes the energy of the nested call."
+                );
+                assert_eq!(*outer_energy, energy_used);
+            }
+            other => panic!("Expected the top-level call to be recorded, but was {:?}.", other),
+        }
+    }
+
+    #[test]
+    fn test_failed_nested_call_is_rolled_back() {
+        let (mut simulator, module_ref) = setup();
+        let a = init(&mut simulator, module_ref, 10);
+        let b = init(&mut simulator, module_ref, 20);
+        update_success(&mut simulator, b, "set", &[7]);
+        let (response, trace, _) =
+            update_response(&mut simulator, a, "forward", &call_payload(b, "fail", &[8], 5));
+        assert_eq!(
+            response & FAILURE_MASK,
+            u64::from(-1i32 as u32),
+            "The nested call should reject with its error code."
+        );
+        // The caller succeeded, so its own changes are kept, but the changes
+        // made by the callee, and the transfer of the amount, are rolled back.
+        assert_eq!(balance(&simulator, a), 10);
+        assert_eq!(balance(&simulator, b), 20);
+        assert_eq!(get_state(&mut simulator, a), vec![1]);
+        assert_eq!(get_state(&mut simulator, b), vec![7]);
+        assert_eq!(trace.len(), 3, "Unexpected trace {:?}.", trace);
+        assert!(matches!(&trace[0], TraceElement::Interrupted { address, .. } if *address == a));
+        assert!(matches!(
+            &trace[1],
+            TraceElement::Resumed { address, success: false } if *address == a
+        ));
+        assert!(matches!(&trace[2], TraceElement::Updated { address, .. } if *address == a));
+
+        // Calling a non-existent entrypoint fails without affecting the caller.
+        let (response, ..) =
+            update_response(&mut simulator, a, "forward", &call_payload(b, "missing", &[], 5));
+        assert_eq!(response & FAILURE_MASK, 0x04_0000_0000);
+        assert_eq!(balance(&simulator, a), 10);
+    }
+
+    #[test]
+    fn test_reentrancy() {
+        let (mut simulator, module_ref) = setup();
+        let a = init(&mut simulator, module_ref, 0);
+        let b = init(&mut simulator, module_ref, 0);
+        // a calls b, which calls back into a and overwrites the state that a
+        // wrote before the call.
+        let payload = call_payload(b, "forward", &call_payload(a, "set", &[9], 0), 0);
+        let (response, trace, _) = update_response(&mut simulator, a, "forward", &payload);
+        assert_eq!(response & FAILURE_MASK, 0, "The nested calls should succeed.");
+        assert_eq!(response >> 63, 1, "The caller is told that its state was modified.");
+        assert_eq!(get_state(&mut simulator, a), vec![9]);
+        assert_eq!(get_state(&mut simulator, b), vec![1]);
+        let updated = trace
+            .iter()
+            .filter_map(|elem| match elem {
+                TraceElement::Updated {
+                    address,
+                    ..
+                } => Some(*address),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(updated, vec![a, b, a], "Calls are recorded in the order they terminate.");
+
+        // If the re-entrant call fails its changes to the state of a are rolled
+        // back, and a is told that its state is unchanged.
+        let payload = call_payload(b, "forward", &call_payload(a, "fail", &[8], 0), 0);
+        let (response, ..) = update_response(&mut simulator, a, "forward", &payload);
+        assert_eq!(response & FAILURE_MASK, 0, "The call to b should succeed.");
+        assert_eq!(response >> 63, 0, "The caller is told that its state is unchanged.");
+        assert_eq!(get_state(&mut simulator, a), vec![1]);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let (mut simulator, module_ref) = setup();
+        let new_module_ref =
+            simulator.deploy_module(UPGRADED_SOURCE).expect("The upgraded module is valid.");
+        let a = init(&mut simulator, module_ref, 0);
+        update_success(&mut simulator, a, "set", &[3]);
+        let error = update(&mut simulator, a, "version", &[])
+            .expect_err("The entrypoint only exists in the new module.");
+        assert!(matches!(
+            error.downcast_ref::<SimulationError>(),
+            Some(SimulationError::MissingEntrypoint(_))
+        ));
+
+        let (response, ..) = update_response(&mut simulator, a, "upgrade", &[0u8; 32]);
+        assert_eq!(response & FAILURE_MASK, 0x07_0000_0000, "The module does not exist.");
+        assert_eq!(simulator.instance(&a).map(|i| i.module_ref), Some(module_ref));
+
+        let (response, trace, _) =
+            update_response(&mut simulator, a, "upgrade", new_module_ref.as_ref().as_slice());
+        assert_eq!(response & FAILURE_MASK, 0, "The upgrade should succeed.");
+        assert!(trace.iter().any(|elem| matches!(
+            elem,
+            TraceElement::Upgraded { address, from, to }
+                if *address == a && *from == module_ref && *to == new_module_ref
+        )));
+        assert_eq!(simulator.instance(&a).map(|i| i.module_ref), Some(new_module_ref));
+        // The state is kept, and the entrypoints of the new module are used.
+        assert_eq!(get_state(&mut simulator, a), vec![3]);
+        assert_eq!(update_success(&mut simulator, a, "version", &[]).0, vec![2]);
+    }
+
+    #[test]
+    fn test_update_unknown_contract() {
+        let mut simulator = Simulator::new(Timestamp::from_timestamp_millis(0));
+        simulator.create_account(account(0), Amount::from_ccd(10));
+        let result = simulator.update(UpdateRequest {
+            invoker:    account(0),
+            address:    ContractAddress {
+                index:    0,
+                subindex: 0,
+            },
+            entrypoint: OwnedEntrypointName::new_unchecked("receive".into()),
+            parameter:  &[],
+            amount:     Amount::from_ccd(1),
+            energy:     InterpreterEnergy::from(1_000_000),
+        });
+        let error = result.expect_err("Updating a non-existent contract should fail.");
+        assert!(matches!(
+            error.downcast_ref::<SimulationError>(),
+            Some(SimulationError::UnknownContract(_))
+        ));
+        assert_eq!(
+            simulator.account_balance(&account(0)).map(|a| a.micro_ccd),
+            Some(Amount::from_ccd(10).micro_ccd),
+            "A failed update should not change balances."
+        );
+    }
+
+    #[test]
+    fn test_update_insufficient_balance() {
+        let mut simulator = Simulator::new(Timestamp::from_timestamp_millis(0));
+        simulator.create_account(account(0), Amount::from_ccd(1));
+        let result = simulator.update(UpdateRequest {
+            invoker:    account(0),
+            address:    ContractAddress {
+                index:    0,
+                subindex: 0,
+            },
+            entrypoint: OwnedEntrypointName::new_unchecked("receive".into()),
+            parameter:  &[],
+            amount:     Amount::from_ccd(2),
+            energy:     InterpreterEnergy::from(1_000_000),
+        });
+        let error = result.expect_err("The invoker cannot afford the amount.");
+        assert!(matches!(
+            error.downcast_ref::<SimulationError>(),
+            Some(SimulationError::InsufficientBalance)
+        ));
+    }
+
+    #[test]
+    fn test_deploy_invalid_module() {
+        let mut simulator = Simulator::new(Timestamp::from_timestamp_millis(0));
+        assert!(simulator.deploy_module(&[0, 1, 2, 3]).is_err(), "Invalid modules are rejected.");
+    }
+}
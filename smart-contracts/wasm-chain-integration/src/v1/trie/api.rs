@@ -28,7 +28,6 @@ use super::{
 use byteorder::{ReadBytesExt, WriteBytesExt};
 #[cfg(feature = "display-state")]
 use ptree::TreeBuilder;
-use sha2::Digest;
 use std::{
     iter::FusedIterator,
     sync::{Arc, Mutex, MutexGuard},
@@ -163,6 +162,21 @@ impl PersistentState {
         }
     }
 
+    /// Lookup a key in the tree and return a copy of the value stored,
+    /// together with a proof of the value, or of the absence of a value, that
+    /// can be checked against the [hash](Self::hash) of the state using
+    /// [`Proof::verify`](low_level::Proof::verify).
+    pub fn prove(
+        &self,
+        loader: &mut impl BackingStoreLoad,
+        key: &[u8],
+    ) -> (Option<Value>, low_level::Proof) {
+        match self {
+            PersistentState::Empty => (None, low_level::Proof::default()),
+            PersistentState::Root(root) => root.get(loader).prove(loader, key),
+        }
+    }
+
     /// Derive a fresh mutable trie from the [`PersistentState`] using the given
     /// loader. In contrast to using [`thaw`](Self::thaw) and then using
     /// [`MutableState::get_inner`] this directly yields a mutable trie that
@@ -278,15 +292,44 @@ impl PersistentState {
     /// backing store using the provided loader.
     pub fn hash(&self, loader: &mut impl BackingStoreLoad) -> super::Hash {
         match self {
-            PersistentState::Empty => {
-                // hash of the node starts with either a 0 or 1 byte. This makes it distinct,
-                // but is otherwise an arbitrary choice.
-                super::Hash::from(<[u8; 32]>::from(sha2::Sha256::digest(b"empty contract state")))
-            }
+            PersistentState::Empty => low_level::empty_trie_hash(),
             PersistentState::Root(root) => root.hash(loader),
         }
     }
 
+    /// Compute the differences between this state and a newer state. Parts of
+    /// the states that are shared, as determined by their hashes, are not
+    /// traversed, so this is efficient for states that derive from each other
+    /// by a few modifications.
+    pub fn diff(&self, new: &PersistentState, loader: &mut impl BackingStoreLoad) -> StateDiff {
+        let mut get_root = |state: &PersistentState| match state {
+            PersistentState::Empty => None,
+            PersistentState::Root(root) => Some(root.get(loader).clone()),
+        };
+        let old_root = get_root(self);
+        let new_root = get_root(new);
+        let mut changes = Vec::new();
+        Hashed::<Node>::diff(old_root.as_ref(), new_root.as_ref(), loader, |key, old, new| {
+            let change = match (old, new) {
+                (None, Some(new)) => StateChange::Added {
+                    size: new.len(),
+                },
+                (Some(old), None) => StateChange::Removed {
+                    size: old.len(),
+                },
+                (Some(old), Some(new)) => StateChange::Changed {
+                    old_size: old.len(),
+                    new_size: new.len(),
+                },
+                (None, None) => unreachable!("Only keys with a value in one state are reported."),
+            };
+            changes.push((key, change))
+        });
+        StateDiff {
+            changes,
+        }
+    }
+
     /// Cache the state, that is, load the entire state into memory from the
     /// backing store. References to the backing store are retained.
     pub fn cache<F: BackingStoreLoad>(&mut self, loader: &mut F) {
@@ -307,6 +350,38 @@ impl PersistentState {
     }
 }
 
+/// A change of the value stored at a key, between two states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateChange {
+    /// The key only has a value in the new state.
+    Added {
+        size: usize,
+    },
+    /// The key only has a value in the old state.
+    Removed {
+        size: usize,
+    },
+    /// The key has a different value in the two states.
+    Changed {
+        old_size: usize,
+        new_size: usize,
+    },
+}
+
+/// The differences between two states, as computed by
+/// [`PersistentState::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Keys whose values differ, in increasing order, together with the
+    /// changes of their values.
+    pub changes: Vec<(Vec<u8>, StateChange)>,
+}
+
+impl StateDiff {
+    /// Whether the states are the same.
+    pub fn is_empty(&self) -> bool { self.changes.is_empty() }
+}
+
 /// Iterator over all (key, value) pairs stored in the persistent state.
 /// Values are returned in increasing order of keys.
 pub struct PersistentStateIterator<'a, L> {
@@ -0,0 +1,518 @@
+//! A file-backed implementation of [`BackingStoreStore`] and
+//! [`BackingStoreLoad`], intended for tools that maintain contract states
+//! outside of the node.
+//!
+//! The store is a single append-only file. It starts with a header consisting
+//! of a magic value and the committed length of the file, which is followed by
+//! records, each of which is an 8 byte big-endian length followed by the data.
+//! This is the same record format as is used by the [`BackingStoreStore`]
+//! instance for [`Vec<u8>`], and a [`Reference`] is the offset of a record in
+//! the file.
+//!
+//! Data is written through a buffer, and is only durable after
+//! [`sync`](FileStore::sync), which first syncs the records and only then
+//! updates the committed length in the header. When the store is opened any
+//! data beyond the committed length, e.g., a record that was torn by a crash,
+//! is truncated.
+//!
+//! Data is loaded via memory maps of the committed part of the file. Since the
+//! file is only ever appended to, a [`MmapLoader`] remains valid while the
+//! store is being written to, and it can be cloned and shared between threads.
+//!
+//! There must be at most one writer of a file at any time, i.e., one
+//! [`FileStore`] or one [`compact`]. On unix this is enforced by an exclusive
+//! advisory lock on the file that the writer holds for as long as it has the
+//! file open, and a second writer fails with [`FileStoreError::Locked`]. On
+//! other platforms the caller must ensure this.
+//!
+//! Since the store is append-only it accumulates nodes of states that are no
+//! longer needed. These are removed offline by [`compact`], which rewrites the
+//! nodes that are reachable from a given set of roots into a fresh file.
+use super::{api::PersistentState, types::*};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use memmap2::{Mmap, MmapOptions};
+use sha2::Digest;
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// Magic value at the start of every store file.
+const MAGIC: [u8; 8] = *b"CCDSTATE";
+
+/// Length of the header, which is the magic value followed by the committed
+/// length of the file.
+const HEADER_LEN: u64 = 16;
+
+#[derive(Debug, Error)]
+/// An error that may occur when opening, writing or compacting a file store.
+pub enum FileStoreError {
+    #[error("{0}")]
+    IOError(#[from] std::io::Error),
+    #[error("The file is not a state store.")]
+    IncorrectHeader,
+    #[error("The file has {actual} bytes, but {committed} bytes were committed.")]
+    MissingData {
+        committed: u64,
+        actual:    u64,
+    },
+    #[error("The store must be reopened after a failed write.")]
+    Failed,
+    #[error("The file is in use by another writer.")]
+    Locked,
+    #[error("Write error: {0}")]
+    Write(#[from] WriteError),
+    #[error("Load error: {0}")]
+    Load(#[from] LoadError),
+}
+
+impl From<LoadWriteError> for FileStoreError {
+    fn from(err: LoadWriteError) -> Self {
+        match err {
+            LoadWriteError::Write(e) => Self::Write(e),
+            LoadWriteError::Load(e) => Self::Load(e),
+        }
+    }
+}
+
+/// Result of operations on a file store.
+pub type FileStoreResult<A> = Result<A, FileStoreError>;
+
+/// Sync the directory containing the given path, so that creation and renaming
+/// of the file are durable.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+/// Directories cannot be synced on other platforms.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> std::io::Result<()> { Ok(()) }
+
+/// Take an exclusive advisory lock on the file, which was opened from the
+/// given path, and which is released when the file is closed. This fails
+/// instead of waiting if the lock is held by another writer.
+///
+/// This also fails with [`FileStoreError::Locked`] if the path no longer
+/// refers to the file once it is locked, which happens if the file was
+/// replaced by a [`compact`] that held the lock when the file was opened.
+#[cfg(unix)]
+fn lock_exclusive(file: &File, path: &Path) -> FileStoreResult<()> {
+    use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+    // Safety: The file descriptor is valid for as long as the file is open.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() == std::io::ErrorKind::WouldBlock {
+            return Err(FileStoreError::Locked);
+        } else {
+            return Err(err.into());
+        }
+    }
+    let locked = file.metadata()?;
+    match std::fs::metadata(path) {
+        Ok(current) if current.dev() == locked.dev() && current.ino() == locked.ino() => Ok(()),
+        Ok(_) => Err(FileStoreError::Locked),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(FileStoreError::Locked),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Files are not locked on other platforms.
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &File, _path: &Path) -> FileStoreResult<()> { Ok(()) }
+
+/// Read the header of a store file and return the committed length.
+fn read_header(file: &mut File) -> FileStoreResult<u64> {
+    let actual = file.metadata()?.len();
+    if actual < HEADER_LEN {
+        return Err(FileStoreError::IncorrectHeader);
+    }
+    file.seek(SeekFrom::Start(0))?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic)?;
+    let committed = file.read_u64::<BigEndian>()?;
+    if magic != MAGIC || committed < HEADER_LEN {
+        return Err(FileStoreError::IncorrectHeader);
+    }
+    if actual < committed {
+        return Err(FileStoreError::MissingData {
+            committed,
+            actual,
+        });
+    }
+    Ok(committed)
+}
+
+/// An append-only store of records in a file.
+#[derive(Debug)]
+pub struct FileStore {
+    path:      PathBuf,
+    writer:    BufWriter<File>,
+    /// Length of the file, including the data that is not yet committed.
+    len:       u64,
+    /// Length of the file that is durably stored and recorded in the header.
+    committed: u64,
+    /// Whether a write has failed. In that case the length is no longer
+    /// accurate, and the store must be reopened, which discards the data
+    /// that was not committed.
+    failed:    bool,
+}
+
+impl FileStore {
+    /// Open the store in the given file, creating the file if it does not
+    /// exist. Any data beyond the committed length of the file is
+    /// truncated. A file that is shorter than the header is assumed to be a
+    /// store whose creation was interrupted, and is initialized.
+    ///
+    /// The file is locked until the store is dropped, and this fails with
+    /// [`FileStoreError::Locked`] if the file is in use by another writer.
+    pub fn open(path: impl AsRef<Path>) -> FileStoreResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).write(true).create(true).open(&path)?;
+        lock_exclusive(&file, &path)?;
+        let committed = if file.metadata()?.len() < HEADER_LEN {
+            file.set_len(0)?;
+            file.write_all(&MAGIC)?;
+            file.write_u64::<BigEndian>(HEADER_LEN)?;
+            file.sync_all()?;
+            sync_parent(&path)?;
+            HEADER_LEN
+        } else {
+            let committed = read_header(&mut file)?;
+            if file.metadata()?.len() > committed {
+                file.set_len(committed)?;
+                file.sync_all()?;
+            }
+            committed
+        };
+        file.seek(SeekFrom::Start(committed))?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            len: committed,
+            committed,
+            failed: false,
+        })
+    }
+
+    /// The path of the file the store is in.
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// The size of the store in bytes, including data that is not yet
+    /// committed.
+    pub fn size(&self) -> u64 { self.len }
+
+    /// Make all the data written so far durable. The committed length in the
+    /// header is only updated after the data itself is synced, so that a crash
+    /// at any point leaves a file whose committed part is intact.
+    pub fn sync(&mut self) -> FileStoreResult<()> {
+        if self.failed {
+            return Err(FileStoreError::Failed);
+        }
+        if self.len == self.committed {
+            return Ok(());
+        }
+        let result = self.sync_worker();
+        self.failed = result.is_err();
+        result
+    }
+
+    fn sync_worker(&mut self) -> FileStoreResult<()> {
+        self.writer.flush()?;
+        let file = self.writer.get_mut();
+        file.sync_data()?;
+        file.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        file.write_u64::<BigEndian>(self.len)?;
+        file.sync_data()?;
+        file.seek(SeekFrom::Start(self.len))?;
+        self.committed = self.len;
+        Ok(())
+    }
+
+    /// Sync the store and construct a loader for all the data in it.
+    pub fn loader(&mut self) -> FileStoreResult<MmapLoader> {
+        self.sync()?;
+        MmapLoader::map(self.writer.get_ref(), self.committed)
+    }
+}
+
+impl BackingStoreStore for FileStore {
+    fn store_raw(&mut self, data: &[u8]) -> Result<Reference, WriteError> {
+        if self.failed {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                FileStoreError::Failed.to_string(),
+            )
+            .into());
+        }
+        let reference = self.len;
+        let result = self
+            .writer
+            .write_u64::<BigEndian>(data.len() as u64)
+            .and_then(|_| self.writer.write_all(data));
+        if let Err(e) = result {
+            self.failed = true;
+            return Err(e.into());
+        }
+        self.len += 8 + data.len() as u64;
+        Ok(reference.into())
+    }
+}
+
+/// A loader for the committed part of a store file. Cloning the loader is
+/// cheap, and all clones share the same memory map.
+#[derive(Debug, Clone)]
+pub struct MmapLoader {
+    map: Arc<Mmap>,
+}
+
+impl MmapLoader {
+    /// Map the committed part of the store in the given file.
+    pub fn open(path: impl AsRef<Path>) -> FileStoreResult<Self> {
+        let mut file = File::open(path)?;
+        let committed = read_header(&mut file)?;
+        Self::map(&file, committed)
+    }
+
+    fn map(file: &File, len: u64) -> FileStoreResult<Self> {
+        // Safety: The store only appends to the file beyond the committed length,
+        // and only truncates it when it is opened, so the mapped part is not
+        // modified while it is mapped, assuming that the file is only accessed
+        // through the store.
+        let map = unsafe { MmapOptions::new().len(len as usize).map(file)? };
+        Ok(Self {
+            map: Arc::new(map),
+        })
+    }
+}
+
+impl BackingStoreLoad for MmapLoader {
+    type R = tinyvec::TinyVec<[u8; 28]>;
+
+    #[inline]
+    fn load_raw(&mut self, location: Reference) -> LoadResult<Self::R> {
+        if location.reference < HEADER_LEN {
+            return Err(LoadError::OutOfBoundsRead);
+        }
+        Loader::new(&self.map[..]).load_raw(location)
+    }
+}
+
+/// A store that writes each distinct record only once. Since children are
+/// stored before their parents, this means that subtrees that are shared
+/// between states are only stored once.
+struct DedupStore<'a> {
+    store: &'a mut FileStore,
+    seen:  HashMap<[u8; 32], Reference>,
+}
+
+impl<'a> BackingStoreStore for DedupStore<'a> {
+    fn store_raw(&mut self, data: &[u8]) -> Result<Reference, WriteError> {
+        let key: [u8; 32] = sha2::Sha256::digest(data).into();
+        if let Some(reference) = self.seen.get(&key) {
+            return Ok(*reference);
+        }
+        let reference = self.store.store_raw(data)?;
+        self.seen.insert(key, reference);
+        Ok(reference)
+    }
+}
+
+/// Compact the store in the given file so that it only contains the states
+/// with the given roots, which are references returned by
+/// [`PersistentState::store_update`]. The states are written into a fresh file
+/// which then replaces the original one, and the new roots are returned in
+/// the order of the given roots.
+///
+/// The original file is locked for the duration of the compaction, including
+/// the rename of the fresh file over it, so this fails with
+/// [`FileStoreError::Locked`] if a [`FileStore`] for the same file is open, and
+/// a [`FileStore`] cannot be opened while the compaction runs. Since the lock
+/// is only enforced on unix, the caller must ensure exclusive access to the
+/// file on other platforms. Readers do not take the lock, so existing
+/// [`MmapLoader`]s remain valid, but they refer to the original file.
+pub fn compact(path: impl AsRef<Path>, roots: &[Reference]) -> FileStoreResult<Vec<Reference>> {
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+    lock_exclusive(&file, path)?;
+    let committed = read_header(&mut file)?;
+    let mut loader = MmapLoader::map(&file, committed)?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".compact");
+    let tmp_path = PathBuf::from(tmp_path);
+    // Remove any leftovers of an interrupted compaction.
+    if tmp_path.exists() {
+        std::fs::remove_file(&tmp_path)?;
+    }
+    let mut store = FileStore::open(&tmp_path)?;
+    let mut new_roots = Vec::with_capacity(roots.len());
+    {
+        let mut dedup = DedupStore {
+            store: &mut store,
+            seen:  HashMap::new(),
+        };
+        for root in roots {
+            let mut state = PersistentState::load_from_location(&mut loader, *root)?;
+            let mut migrated = state.migrate(&mut dedup, &mut loader)?;
+            new_roots.push(migrated.store_update(&mut dedup)?);
+        }
+    }
+    store.sync()?;
+    // Both the original and the fresh file stay locked until the fresh file
+    // has replaced the original one, so that no writer can open either of
+    // them in the meantime.
+    std::fs::rename(&tmp_path, path)?;
+    sync_parent(path)?;
+    drop(store);
+    drop(file);
+    Ok(new_roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("file-store-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn make_state(entries: &BTreeMap<Vec<u8>, Vec<u8>>) -> PersistentState {
+        PersistentState::from_iterator(entries.iter().map(|(k, v)| (k.as_slice(), v.clone())))
+    }
+
+    fn load_entries(loader: &mut MmapLoader, root: Reference) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        let state =
+            PersistentState::load_from_location(loader, root).expect("Root should be loadable.");
+        state.into_iterator(loader).collect()
+    }
+
+    fn entries(n: u8, value: u8) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        (0..n).map(|i| (vec![i, i / 3], vec![value; usize::from(i) + 1])).collect()
+    }
+
+    #[test]
+    fn test_store_reopen() {
+        let path = temp_file("reopen");
+        let expected = entries(100, 1);
+        let root = {
+            let mut store = FileStore::open(&path).expect("Store should open.");
+            let root = make_state(&expected).store_update(&mut store).expect("Store should work.");
+            store.sync().expect("Sync should work.");
+            root
+        };
+        let mut store = FileStore::open(&path).expect("Store should reopen.");
+        let mut loader = store.loader().expect("Loader should map the file.");
+        assert_eq!(load_entries(&mut loader, root), expected);
+        let mut loader = MmapLoader::open(&path).expect("Loader should open.");
+        assert_eq!(load_entries(&mut loader, root), expected);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_torn_tail() {
+        let path = temp_file("torn");
+        let expected = entries(50, 2);
+        let (root, committed) = {
+            let mut store = FileStore::open(&path).expect("Store should open.");
+            let root = make_state(&expected).store_update(&mut store).expect("Store should work.");
+            store.sync().expect("Sync should work.");
+            let committed = store.size();
+            // Data that is written but not synced is lost.
+            make_state(&entries(50, 3)).store_update(&mut store).expect("Store should work.");
+            (root, committed)
+        };
+        // Simulate a record that was torn by a crash.
+        {
+            let mut file = OpenOptions::new().append(true).open(&path).expect("File exists.");
+            file.write_all(&[0, 0, 0, 0, 0, 0, 1, 0, 17]).expect("Append should work.");
+        }
+        let mut store = FileStore::open(&path).expect("Store should recover.");
+        assert_eq!(store.size(), committed, "Uncommitted data should be truncated.");
+        let mut loader = store.loader().expect("Loader should map the file.");
+        assert_eq!(load_entries(&mut loader, root), expected);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_single_writer() {
+        let path = temp_file("single-writer");
+        let expected = entries(20, 6);
+        let root = {
+            let mut store = FileStore::open(&path).expect("Store should open.");
+            assert!(matches!(FileStore::open(&path), Err(FileStoreError::Locked)));
+            assert!(matches!(compact(&path, &[]), Err(FileStoreError::Locked)));
+            let root = make_state(&expected).store_update(&mut store).expect("Store should work.");
+            store.sync().expect("Sync should work.");
+            // Readers do not take the lock.
+            let mut loader = MmapLoader::open(&path).expect("Loader should open.");
+            assert_eq!(load_entries(&mut loader, root), expected);
+            root
+        };
+        // A writer that opened the file before it was replaced cannot lock it.
+        let replaced = File::open(&path).expect("File exists.");
+        let new_roots = compact(&path, &[root]).expect("The lock is released on drop.");
+        assert!(matches!(lock_exclusive(&replaced, &path), Err(FileStoreError::Locked)));
+        drop(replaced);
+        let mut store = FileStore::open(&path).expect("The lock is released after compaction.");
+        let mut loader = store.loader().expect("Loader should map the file.");
+        assert_eq!(load_entries(&mut loader, new_roots[0]), expected);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_compact() {
+        let path = temp_file("compact");
+        let first = entries(200, 4);
+        let mut second = first.clone();
+        second.insert(vec![0, 0], vec![5; 100]);
+        let (roots, size) = {
+            let mut store = FileStore::open(&path).expect("Store should open.");
+            let mut roots = Vec::new();
+            for version in 0..10u8 {
+                roots.push(
+                    make_state(&entries(200, version))
+                        .store_update(&mut store)
+                        .expect("Store should work."),
+                );
+            }
+            // The two remaining states are constructed independently, but share
+            // all but a few nodes.
+            for state in [&first, &second].iter() {
+                roots.push(make_state(state).store_update(&mut store).expect("Store should work."));
+            }
+            store.sync().expect("Sync should work.");
+            (roots, store.size())
+        };
+        let new_roots = compact(&path, &roots[10..]).expect("Compaction should work.");
+        let mut loader = MmapLoader::open(&path).expect("Loader should open.");
+        assert_eq!(load_entries(&mut loader, new_roots[0]), first);
+        assert_eq!(load_entries(&mut loader, new_roots[1]), second);
+        let single = {
+            let single_path = temp_file("compact-single");
+            let mut store = FileStore::open(&single_path).expect("Store should open.");
+            make_state(&first).store_update(&mut store).expect("Store should work.");
+            store.sync().expect("Sync should work.");
+            let _ = std::fs::remove_file(&single_path);
+            store.size()
+        };
+        let compacted = std::fs::metadata(&path).expect("File exists.").len();
+        assert!(compacted < size, "Compaction should remove unreachable states.");
+        assert!(
+            compacted < 2 * single,
+            "States that share most of their nodes should be stored once."
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -38,6 +38,7 @@
 //! Thus generations in effect achieve a persistent data structure, but in such
 //! a way that updates are still almost as efficient as for a mutable trie.
 use super::types::*;
+use crate::constants::MAX_KEY_SIZE;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 #[cfg(feature = "display-state")]
 use ptree::TreeBuilder;
@@ -1110,29 +1111,44 @@ where
 
 impl<Ctx: BackingStoreLoad> ToSHA256<Ctx> for Node {
     fn hash(&self, ctx: &mut Ctx) -> Hash {
-        let mut hasher = sha2::Sha256::new();
-        match &self.value {
-            Some(value) => {
-                hasher.update(&[1]);
-                hasher.update(value.borrow().hash(ctx));
-            }
-            None => hasher.update(&[0]),
-        }
+        let value_hash = self.value.as_ref().map(|value| value.borrow().hash(ctx));
         let (stem_len, stem_ref) = self.path.to_slice();
-        hasher.update((stem_len as u64).to_le_bytes());
-        hasher.update(stem_ref);
-        let mut child_hasher = sha2::Sha256::new();
-        child_hasher.update(&(self.children.len() as u16).to_be_bytes());
-        for child in self.children.iter() {
-            child_hasher.update(&[child.0.value]);
-            child_hasher.update(child.1.borrow().hash(ctx));
-        }
-        hasher.update(child_hasher.finalize());
-        let hash: [u8; 32] = hasher.finalize().into();
-        Hash::from(hash)
+        let children =
+            self.children.iter().map(|child| (child.0.value, child.1.borrow().hash(ctx)));
+        node_hash(value_hash.as_ref(), stem_len, stem_ref, children)
     }
 }
 
+/// Compute the hash of a node from the hash of its value, its stem, given as
+/// the number of chunks and the packed chunks, and the keys and hashes of its
+/// children in increasing order of keys.
+fn node_hash(
+    value_hash: Option<&Hash>,
+    stem_len: usize,
+    stem: &[u8],
+    children: impl ExactSizeIterator<Item = (u8, Hash)>,
+) -> Hash {
+    let mut hasher = sha2::Sha256::new();
+    match value_hash {
+        Some(value_hash) => {
+            hasher.update(&[1]);
+            hasher.update(value_hash);
+        }
+        None => hasher.update(&[0]),
+    }
+    hasher.update((stem_len as u64).to_le_bytes());
+    hasher.update(stem);
+    let mut child_hasher = sha2::Sha256::new();
+    child_hasher.update(&(children.len() as u16).to_be_bytes());
+    for (key, hash) in children {
+        child_hasher.update(&[key]);
+        child_hasher.update(hash);
+    }
+    hasher.update(child_hasher.finalize());
+    let hash: [u8; 32] = hasher.finalize().into();
+    Hash::from(hash)
+}
+
 #[derive(Debug, Clone)]
 struct MutableNode {
     generation: u32,
@@ -3471,6 +3487,486 @@ impl Node {
     }
 }
 
+/// The side of a comparison of two tries that an entry belongs to.
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+}
+
+/// A node in which part of the stem has already been matched against the
+/// other trie. The remaining chunks of the stem are stored explicitly.
+struct NodeView {
+    node: Hashed<Node>,
+    stem: Vec<u8>,
+}
+
+impl NodeView {
+    fn new(node: Hashed<Node>) -> Self {
+        let stem = stem_to_chunks(&node.data.path);
+        Self {
+            node,
+            stem,
+        }
+    }
+
+    fn load(loader: &mut impl BackingStoreLoad, child: &ChildLink) -> Self {
+        let node = child.borrow().get(loader).clone();
+        Self::new(node)
+    }
+}
+
+/// Unpack the chunks of a stem, one chunk per byte.
+fn stem_to_chunks(stem: &Stem) -> Vec<u8> {
+    let mut chunks = Vec::with_capacity(stem.len());
+    let mut iter = stem.iter();
+    while let Some(chunk) = iter.next() {
+        chunks.push(chunk.value);
+    }
+    chunks
+}
+
+/// Pack a sequence of 4-bit chunks into bytes. If the number of chunks is odd
+/// the last 4 bits of the result are 0. Paths that lead to values have an even
+/// number of chunks, and packing them gives the key.
+fn pack_chunks(path: &[u8]) -> Vec<u8> {
+    path.chunks(2).map(|c| (c[0] << 4) | c.get(1).copied().unwrap_or(0)).collect()
+}
+
+/// State of a traversal computing the differences between two tries.
+struct Differ<'a, L, F> {
+    loader: &'a mut L,
+    report: F,
+}
+
+impl<'a, L: BackingStoreLoad, F: FnMut(Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>)>
+    Differ<'a, L, F>
+{
+    fn value(&mut self, node: &Node) -> Option<Vec<u8>> {
+        node.value.as_ref().map(|v| v.borrow().get_copy(self.loader))
+    }
+
+    fn emit(&mut self, path: &[u8], side: Side, value: Vec<u8>) {
+        match side {
+            Side::Old => (self.report)(pack_chunks(path), Some(value), None),
+            Side::New => (self.report)(pack_chunks(path), None, Some(value)),
+        }
+    }
+
+    /// Report all the entries in the subtree as present only on the given
+    /// side.
+    fn all(&mut self, path: &mut Vec<u8>, view: NodeView, side: Side) {
+        let len = path.len();
+        path.extend_from_slice(&view.stem);
+        if let Some(value) = self.value(&view.node.data) {
+            self.emit(path, side, value);
+        }
+        for (key, child) in view.node.data.children.iter() {
+            path.push(key.value);
+            let child = NodeView::load(self.loader, child);
+            self.all(path, child, side);
+            path.pop();
+        }
+        path.truncate(len);
+    }
+
+    /// Compare two subtrees, both of which start at the given path.
+    fn diff(&mut self, path: &mut Vec<u8>, old: NodeView, new: NodeView) {
+        // The hash of a node covers its entire stem. If the hashes are equal and the
+        // remaining stems are equal then the parts of the stems that were
+        // matched are also equal, and so are the subtrees.
+        if old.stem == new.stem && old.node.hash == new.node.hash {
+            return;
+        }
+        let common = old.stem.iter().zip(new.stem.iter()).take_while(|(a, b)| a == b).count();
+        if common < old.stem.len() && common < new.stem.len() {
+            // The stems diverge, so the subtrees have no keys in common.
+            if old.stem[common] < new.stem[common] {
+                self.all(path, old, Side::Old);
+                self.all(path, new, Side::New);
+            } else {
+                self.all(path, new, Side::New);
+                self.all(path, old, Side::Old);
+            }
+        } else if common == old.stem.len() && common == new.stem.len() {
+            let len = path.len();
+            path.extend_from_slice(&old.stem);
+            let old_value = self.value(&old.node.data);
+            let new_value = self.value(&new.node.data);
+            if old_value != new_value {
+                (self.report)(pack_chunks(path), old_value, new_value);
+            }
+            let old_children = &old.node.data.children;
+            let new_children = &new.node.data.children;
+            let (mut i, mut j) = (0, 0);
+            while i < old_children.len() || j < new_children.len() {
+                let old_key = old_children.get(i).map(|c| c.0);
+                let new_key = new_children.get(j).map(|c| c.0);
+                match (old_key, new_key) {
+                    (Some(ok), Some(nk)) if ok == nk => {
+                        path.push(ok.value);
+                        let o = NodeView::load(self.loader, &old_children[i].1);
+                        let n = NodeView::load(self.loader, &new_children[j].1);
+                        self.diff(path, o, n);
+                        path.pop();
+                        i += 1;
+                        j += 1;
+                    }
+                    (Some(ok), nk) if nk.map_or(true, |nk| ok < nk) => {
+                        path.push(ok.value);
+                        let o = NodeView::load(self.loader, &old_children[i].1);
+                        self.all(path, o, Side::Old);
+                        path.pop();
+                        i += 1;
+                    }
+                    (_, Some(nk)) => {
+                        path.push(nk.value);
+                        let n = NodeView::load(self.loader, &new_children[j].1);
+                        self.all(path, n, Side::New);
+                        path.pop();
+                        j += 1;
+                    }
+                    (None, None) => unreachable!("Loop condition ensures one side has children."),
+                }
+            }
+            path.truncate(len);
+        } else if common == old.stem.len() {
+            self.diff_prefix(path, old, new, Side::Old);
+        } else {
+            self.diff_prefix(path, new, old, Side::New);
+        }
+    }
+
+    /// Compare two subtrees where the stem of `short` is a proper prefix of the
+    /// stem of `long`. The `short` subtree is on the given side of the
+    /// comparison, and `long` on the other.
+    fn diff_prefix(&mut self, path: &mut Vec<u8>, short: NodeView, long: NodeView, side: Side) {
+        let other = match side {
+            Side::Old => Side::New,
+            Side::New => Side::Old,
+        };
+        let len = path.len();
+        path.extend_from_slice(&short.stem);
+        // The long subtree has no value at the end of the short stem.
+        if let Some(value) = self.value(&short.node.data) {
+            self.emit(path, side, value);
+        }
+        let step = long.stem[short.stem.len()];
+        let mut long = Some(NodeView {
+            stem: long.stem[short.stem.len() + 1..].to_vec(),
+            node: long.node,
+        });
+        for (key, child) in short.node.data.children.iter() {
+            if key.value > step {
+                if let Some(long) = long.take() {
+                    path.push(step);
+                    self.all(path, long, other);
+                    path.pop();
+                }
+            }
+            path.push(key.value);
+            let child = NodeView::load(self.loader, child);
+            if key.value == step {
+                let long = long.take().expect("Children have distinct keys.");
+                match side {
+                    Side::Old => self.diff(path, child, long),
+                    Side::New => self.diff(path, long, child),
+                }
+            } else {
+                self.all(path, child, side);
+            }
+            path.pop();
+        }
+        if let Some(long) = long {
+            path.push(step);
+            self.all(path, long, other);
+            path.pop();
+        }
+        path.truncate(len);
+    }
+}
+
+impl Hashed<Node> {
+    /// Compute the differences between two tries. The function `report` is
+    /// called with the key, the old value, and the new value of each key whose
+    /// value differs between the tries, in increasing order of keys. A missing
+    /// value is [`None`].
+    ///
+    /// Subtrees that are the same in both tries, as determined by their hashes,
+    /// are skipped without being traversed.
+    pub fn diff(
+        old: Option<&Self>,
+        new: Option<&Self>,
+        loader: &mut impl BackingStoreLoad,
+        report: impl FnMut(Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>),
+    ) {
+        let mut differ = Differ {
+            loader,
+            report,
+        };
+        let mut path = Vec::new();
+        match (old, new) {
+            (None, None) => (),
+            (Some(old), None) => differ.all(&mut path, NodeView::new(old.clone()), Side::Old),
+            (None, Some(new)) => differ.all(&mut path, NodeView::new(new.clone()), Side::New),
+            (Some(old), Some(new)) => {
+                differ.diff(&mut path, NodeView::new(old.clone()), NodeView::new(new.clone()))
+            }
+        }
+    }
+}
+
+/// Hash of the empty trie. Hashes of nodes start with either a 0 or a 1 byte,
+/// which makes this distinct from the hash of any node, but it is otherwise an
+/// arbitrary choice.
+pub fn empty_trie_hash() -> Hash {
+    Hash::from(<[u8; 32]>::from(sha2::Sha256::digest(b"empty contract state")))
+}
+
+/// Split a key into 4-bit chunks, most significant bits first.
+fn key_to_chunks(key: &[u8]) -> Vec<u8> {
+    let mut chunks = Vec::with_capacity(2 * key.len());
+    for b in key {
+        chunks.push(b >> 4);
+        chunks.push(b & 0x0f);
+    }
+    chunks
+}
+
+/// A node on the path from the root of a trie to a key, with just enough
+/// information to recompute its hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofNode {
+    /// Hash of the value at the node, if the node has a value. For the last
+    /// node of an inclusion proof this is [`None`], since the value is
+    /// supplied to the verifier.
+    value:    Option<Hash>,
+    /// The stem of the node, one chunk per byte.
+    stem:     Vec<u8>,
+    /// Keys and hashes of the children, in increasing order of keys. For all
+    /// but the last node this omits the child that the path continues with,
+    /// since its hash is determined by the remaining nodes.
+    children: Vec<(u8, Hash)>,
+}
+
+/// A proof that a key either has a given value in a trie, or that it has no
+/// value. The proof consists of the nodes on the path from the root to the
+/// node where the lookup of the key ends, and can be checked against the root
+/// hash of the trie without access to the backing store.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Proof {
+    /// Nodes on the path, starting at the root. This is empty for proofs about
+    /// the empty trie.
+    nodes: Vec<ProofNode>,
+}
+
+impl Proof {
+    /// Check the proof against the root hash of a trie. If `value` is
+    /// [`Some`] this checks that the key has the given value, and otherwise it
+    /// checks that the key has no value in the trie.
+    pub fn verify(&self, root: &Hash, key: &[u8], value: Option<&[u8]>) -> bool {
+        let (last, path) = match self.nodes.split_last() {
+            Some(x) => x,
+            None => return value.is_none() && *root == empty_trie_hash(),
+        };
+        let key = key_to_chunks(key);
+        let mut rest = &key[..];
+        let well_formed = |node: &ProofNode| {
+            node.stem.iter().all(|c| *c < 16)
+                && node.children.iter().all(|(k, _)| *k < 16)
+                && node.children.windows(2).all(|w| w[0].0 < w[1].0)
+        };
+        // Check that the nodes on the path follow the key, and record the keys
+        // of the children that the path continues with.
+        let mut steps = Vec::with_capacity(path.len());
+        for node in path {
+            if !well_formed(node) || !rest.starts_with(&node.stem) || rest.len() == node.stem.len()
+            {
+                return false;
+            }
+            let step = rest[node.stem.len()];
+            if node.children.iter().any(|(k, _)| *k == step) {
+                return false;
+            }
+            steps.push(step);
+            rest = &rest[node.stem.len() + 1..];
+        }
+        if !well_formed(last) {
+            return false;
+        }
+        let value_hash = match value {
+            Some(value) => {
+                if rest != last.stem.as_slice() || last.value.is_some() {
+                    return false;
+                }
+                Some(value.hash(&mut ()))
+            }
+            None => {
+                let absent = if !rest.starts_with(&last.stem) {
+                    true
+                } else if rest.len() == last.stem.len() {
+                    last.value.is_none()
+                } else {
+                    let step = rest[last.stem.len()];
+                    last.children.iter().all(|(k, _)| *k != step)
+                };
+                if !absent {
+                    return false;
+                }
+                last.value
+            }
+        };
+        let stem = pack_chunks(&last.stem);
+        let mut hash =
+            node_hash(value_hash.as_ref(), last.stem.len(), &stem, last.children.iter().copied());
+        for (node, step) in path.iter().zip(steps).rev() {
+            let mut children = node.children.clone();
+            let pos = children.iter().position(|(k, _)| *k > step).unwrap_or(children.len());
+            children.insert(pos, (step, hash));
+            let stem = pack_chunks(&node.stem);
+            hash = node_hash(node.value.as_ref(), node.stem.len(), &stem, children.into_iter());
+        }
+        hash == *root
+    }
+
+    /// Serialize the proof. The stems are stored packed, two chunks per byte.
+    pub fn serialize(&self, out: &mut impl Write) -> anyhow::Result<()> {
+        out.write_u32::<BigEndian>(self.nodes.len() as u32)?;
+        for node in self.nodes.iter() {
+            match &node.value {
+                Some(hash) => {
+                    out.write_u8(1)?;
+                    out.write_all(hash.as_ref())?;
+                }
+                None => out.write_u8(0)?,
+            }
+            out.write_u32::<BigEndian>(node.stem.len() as u32)?;
+            out.write_all(&pack_chunks(&node.stem))?;
+            // There can be at most 16 children, this is safe.
+            out.write_u8(node.children.len() as u8)?;
+            for (key, hash) in node.children.iter() {
+                out.write_u8(*key)?;
+                out.write_all(hash.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The inverse of [serialize](Self::serialize). This does not check that
+    /// the proof is well-formed, that is done by [verify](Self::verify).
+    /// The stems on the path are parts of the key, so proofs whose stems are
+    /// together longer than the maximum key size are rejected, and the input
+    /// determines how much memory is allocated.
+    pub fn deserialize(source: &mut impl Read) -> anyhow::Result<Self> {
+        let num_nodes = source.read_u32::<BigEndian>()?;
+        let mut nodes = Vec::new();
+        // Total number of chunks of the key covered by the stems so far.
+        let mut total_stem_len = 0usize;
+        for _ in 0..num_nodes {
+            let value = match source.read_u8()? {
+                0 => None,
+                1 => Some(Hash::read(source)?),
+                tag => anyhow::bail!("Unexpected value tag {}.", tag),
+            };
+            let stem_len = source.read_u32::<BigEndian>()? as usize;
+            anyhow::ensure!(
+                stem_len <= 2 * MAX_KEY_SIZE - total_stem_len,
+                "The stems of the proof are longer than the maximum key size."
+            );
+            total_stem_len += stem_len;
+            let packed_len = stem_len / 2 + stem_len % 2;
+            let mut packed = Vec::new();
+            source.by_ref().take(packed_len as u64).read_to_end(&mut packed)?;
+            anyhow::ensure!(packed.len() == packed_len, "Unexpected end of input in a stem.");
+            let mut stem = key_to_chunks(&packed);
+            stem.truncate(stem_len);
+            let num_children = source.read_u8()?;
+            anyhow::ensure!(num_children <= 16, "A node has at most 16 children.");
+            let mut children = Vec::with_capacity(num_children.into());
+            for _ in 0..num_children {
+                let key = source.read_u8()?;
+                children.push((key, Hash::read(source)?));
+            }
+            nodes.push(ProofNode {
+                value,
+                stem,
+                children,
+            });
+        }
+        Ok(Self {
+            nodes,
+        })
+    }
+}
+
+/// Keys and hashes of the children of a node, except the child with the given
+/// key, if any.
+fn child_hashes(
+    node: &Node,
+    loader: &mut impl BackingStoreLoad,
+    skip: Option<u8>,
+) -> Vec<(u8, Hash)> {
+    node.children
+        .iter()
+        .filter(|(key, _)| Some(key.value) != skip)
+        .map(|(key, child)| (key.value, child.borrow().hash(loader)))
+        .collect()
+}
+
+impl Hashed<Node> {
+    /// Construct a proof of the value of the key in the trie. The value is
+    /// returned together with the proof. If the key has a value the proof is
+    /// an inclusion proof of that value, and otherwise it is a proof that the
+    /// key has no value.
+    pub fn prove(
+        &self,
+        loader: &mut impl BackingStoreLoad,
+        key: &[u8],
+    ) -> (Option<Vec<u8>>, Proof) {
+        let key = key_to_chunks(key);
+        let mut rest = &key[..];
+        let mut nodes = Vec::new();
+        let mut node = self.clone();
+        loop {
+            let stem = stem_to_chunks(&node.data.path);
+            let value_hash = node.data.value.as_ref().map(|v| v.borrow().hash(loader));
+            if rest.starts_with(&stem) && rest.len() > stem.len() {
+                let step = rest[stem.len()];
+                let next = node
+                    .data
+                    .children
+                    .iter()
+                    .find(|(key, _)| key.value == step)
+                    .map(|(_, child)| child.borrow().get(loader).clone());
+                if let Some(next) = next {
+                    rest = &rest[stem.len() + 1..];
+                    nodes.push(ProofNode {
+                        value: value_hash,
+                        stem,
+                        children: child_hashes(&node.data, loader, Some(step)),
+                    });
+                    node = next;
+                    continue;
+                }
+            }
+            let value = if rest == stem.as_slice() {
+                node.data.value.as_ref().map(|v| v.borrow().get_copy(loader))
+            } else {
+                None
+            };
+            nodes.push(ProofNode {
+                // The value of an inclusion proof is supplied to the verifier.
+                value: value_hash.filter(|_| value.is_none()),
+                stem,
+                children: child_hashes(&node.data, loader, None),
+            });
+            return (value, Proof {
+                nodes,
+            });
+        }
+    }
+}
+
 #[cfg(test)]
 /// Tests for the prefix map.
 mod prefix_map_tests {
@@ -6,6 +6,7 @@ mod tests;
 
 mod api;
 pub use api::*;
+pub mod file_store;
 pub use low_level::Iterator;
 // We need this in some integration with the node, but we don't want to
 // advertise it.
@@ -1,7 +1,7 @@
 use super::{low_level::*, *};
 use anyhow::{bail, ensure, Context};
 use quickcheck::*;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 const NUM_TESTS: u64 = 100000;
 
@@ -333,6 +333,130 @@ fn prop_storing_preseves_hash() {
     QuickCheck::new().tests(NUM_TESTS).quickcheck(prop as fn(Vec<_>) -> anyhow::Result<()>);
 }
 
+#[test]
+/// Check that the diff of two states matches the diff of the reference maps.
+fn prop_diff_matches_reference() {
+    let prop = |inputs: Vec<(Vec<u8>, Value)>,
+                updates: Vec<(Vec<u8>, Option<Value>)>|
+     -> anyhow::Result<()> {
+        let old_reference = inputs.iter().cloned().collect::<BTreeMap<_, _>>();
+        let mut new_reference = old_reference.clone();
+        for (key, value) in updates {
+            match value {
+                Some(value) => new_reference.insert(key, value),
+                None => new_reference.remove(&key),
+            };
+        }
+        let old = PersistentState::from_iterator(
+            old_reference.iter().map(|(k, v)| (k.as_slice(), v.clone())),
+        );
+        let new = PersistentState::from_iterator(
+            new_reference.iter().map(|(k, v)| (k.as_slice(), v.clone())),
+        );
+        let mut expected = Vec::new();
+        let keys = old_reference.keys().chain(new_reference.keys()).collect::<BTreeSet<_>>();
+        for key in keys {
+            let change = match (old_reference.get(key), new_reference.get(key)) {
+                (Some(o), Some(n)) if o == n => continue,
+                (Some(o), Some(n)) => StateChange::Changed {
+                    old_size: o.len(),
+                    new_size: n.len(),
+                },
+                (Some(o), None) => StateChange::Removed {
+                    size: o.len(),
+                },
+                (None, Some(n)) => StateChange::Added {
+                    size: n.len(),
+                },
+                (None, None) => unreachable!("Key is in one of the maps."),
+            };
+            expected.push((key.clone(), change));
+        }
+        let mut loader = Loader {
+            inner: Vec::<u8>::new(),
+        };
+        let diff = old.diff(&new, &mut loader);
+        ensure!(diff.changes == expected, "Diff {:?} does not match {:?}.", diff, expected);
+        ensure!(old.diff(&old, &mut loader).is_empty(), "A state differs from itself.");
+        Ok(())
+    };
+    QuickCheck::new().tests(NUM_TESTS).quickcheck(prop as fn(Vec<_>, Vec<_>) -> anyhow::Result<()>);
+}
+
+#[test]
+/// Check that proofs of values and of the absence of values verify against the
+/// hash of the state, survive serialization, and do not verify other claims.
+fn prop_proofs_verify() {
+    let prop = |inputs: Vec<(Vec<u8>, Value)>, others: Vec<Vec<u8>>| -> anyhow::Result<()> {
+        let reference = inputs.iter().cloned().collect::<BTreeMap<_, _>>();
+        let state = PersistentState::from_iterator(
+            reference.iter().map(|(k, v)| (k.as_slice(), v.clone())),
+        );
+        let mut loader = Loader {
+            inner: Vec::<u8>::new(),
+        };
+        let root = state.hash(&mut loader);
+        for key in reference.keys().chain(others.iter()) {
+            let expected = reference.get(key);
+            let (value, proof) = state.prove(&mut loader, key);
+            ensure!(value.as_ref() == expected, "Proved value does not match the reference.");
+            ensure!(proof.verify(&root, key, value.as_deref()), "Proof does not verify.");
+            let mut out = Vec::new();
+            proof.serialize(&mut out)?;
+            let deserialized = Proof::deserialize(&mut std::io::Cursor::new(&out))?;
+            ensure!(deserialized == proof, "Proof does not survive serialization.");
+            match value {
+                Some(mut value) => {
+                    ensure!(!proof.verify(&root, key, None), "Proof of a value proves absence.");
+                    value.push(0);
+                    ensure!(!proof.verify(&root, key, Some(&value[..])), "Proof of a wrong value.");
+                }
+                None => {
+                    ensure!(
+                        !proof.verify(&root, key, Some(&[][..])),
+                        "Proof of absence proves a value."
+                    )
+                }
+            }
+        }
+        Ok(())
+    };
+    QuickCheck::new().tests(NUM_TESTS).quickcheck(prop as fn(Vec<_>, Vec<_>) -> anyhow::Result<()>);
+}
+
+#[test]
+/// Check that deserialization of proofs rejects stems that are longer than
+/// the maximum key size, or longer than the input, without allocating them.
+fn test_proof_deserialize_long_stem() {
+    let proof = |stem_lens: &[u32]| {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(stem_lens.len() as u32).to_be_bytes());
+        for stem_len in stem_lens {
+            out.push(0);
+            out.extend_from_slice(&stem_len.to_be_bytes());
+        }
+        out
+    };
+    assert!(Proof::deserialize(&mut std::io::Cursor::new(proof(&[u32::MAX]))).is_err());
+    let max_stem_len = 2 * crate::constants::MAX_KEY_SIZE as u32;
+    assert!(Proof::deserialize(&mut std::io::Cursor::new(proof(&[max_stem_len + 1]))).is_err());
+    // The stems are bounded in total, since together they are a part of the key.
+    let mut two_nodes = proof(&[1]);
+    two_nodes.extend_from_slice(&[0x10, 0]);
+    two_nodes.extend_from_slice(&proof(&[max_stem_len])[4..]);
+    two_nodes[3] = 2;
+    assert!(Proof::deserialize(&mut std::io::Cursor::new(two_nodes)).is_err());
+    // The stem is within bounds, but the input ends before it.
+    assert!(Proof::deserialize(&mut std::io::Cursor::new(proof(&[max_stem_len]))).is_err());
+    let mut valid = proof(&[3]);
+    valid.extend_from_slice(&[0x12, 0x30, 0]);
+    let deserialized = Proof::deserialize(&mut std::io::Cursor::new(valid))
+        .expect("A short stem should deserialize.");
+    let mut out = Vec::new();
+    deserialized.serialize(&mut out).expect("Serialization into a vector succeeds.");
+    assert_eq!(out, [&proof(&[3])[..], &[0x12, 0x30, 0][..]].concat());
+}
+
 #[test]
 /// Check that the hash of the tree is independent of the order of insertions,
 /// provided there are no duplicates.
@@ -0,0 +1,36 @@
+(module
+
+  ;; The module that instances of the contract in simulator-test.wat are
+  ;; upgraded to in the tests of the simulator. It reads the state in the same
+  ;; way and has an additional entrypoint.
+
+  (import "concordium" "write_output" (func $write_output (param $start i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "state_lookup_entry" (func $state_lookup_entry (param $key_start i32) (param $key_length i32) (result i64)))
+  (import "concordium" "state_entry_size" (func $state_entry_size (param $entry i64) (result i32)))
+  (import "concordium" "state_entry_read" (func $state_entry_read (param $entry i64) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+
+  (func (export "init_test") (param i64) (result i32)
+    (i32.const 0) ;; Successful init
+  )
+
+  ;; Return the value in the state. The return value is empty if there is no
+  ;; value.
+  (func (export "test.get") (param i64) (result i32)
+    (local $entry i64)
+    (local $size i32)
+    (local.set $entry (call $state_lookup_entry (i32.const 0) (i32.const 0)))
+    (if (i64.ge_s (local.get $entry) (i64.const 0))
+      (then
+        (local.set $size (call $state_entry_size (local.get $entry)))
+        (drop (call $state_entry_read (local.get $entry) (i32.const 0) (local.get $size) (i32.const 0)))
+        (drop (call $write_output (i32.const 0) (local.get $size) (i32.const 0)))))
+    (i32.const 0))
+
+  ;; Return the byte 2, the version of the module.
+  (func (export "test.version") (param i64) (result i32)
+    (i32.store8 (i32.const 0) (i32.const 2))
+    (drop (call $write_output (i32.const 0) (i32.const 1) (i32.const 0)))
+    (i32.const 0))
+
+  (memory 1)
+)
@@ -0,0 +1,81 @@
+(module
+
+  ;; This module is used to test the simulator. Each instance of the contract
+  ;; keeps a single value in its state, under the empty key.
+
+  (import "concordium" "get_parameter_size" (func $get_parameter_size (param $index i32) (result i32)))
+  (import "concordium" "get_parameter_section" (func $get_parameter_section (param $index i32) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "write_output" (func $write_output (param $start i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "log_event" (func $log_event (param $start i32) (param $length i32) (result i32)))
+  (import "concordium" "invoke" (func $invoke (param $tag i32) (param $start i32) (param $length i32) (result i64)))
+  (import "concordium" "upgrade" (func $upgrade (param $module_ptr i32) (result i64)))
+  (import "concordium" "state_lookup_entry" (func $state_lookup_entry (param $key_start i32) (param $key_length i32) (result i64)))
+  (import "concordium" "state_create_entry" (func $state_create_entry (param $key_start i32) (param $key_length i32) (result i64)))
+  (import "concordium" "state_entry_size" (func $state_entry_size (param $entry i64) (result i32)))
+  (import "concordium" "state_entry_read" (func $state_entry_read (param $entry i64) (param $write_location i32) (param $length i32) (param $offset i32) (result i32)))
+  (import "concordium" "state_entry_write" (func $state_entry_write (param $entry i64) (param $read_location i32) (param $length i32) (param $offset i32) (result i32)))
+
+  ;; Read the parameter to the start of memory and return its length.
+  (func $read_parameter (result i32)
+    (local $size i32)
+    (local.set $size (call $get_parameter_size (i32.const 0)))
+    (drop (call $get_parameter_section (i32.const 0) (i32.const 0) (local.get $size) (i32.const 0)))
+    (local.get $size))
+
+  ;; Replace the value in the state with the given bytes.
+  (func $store (param $start i32) (param $length i32)
+    (local $entry i64)
+    (local.set $entry (call $state_create_entry (i32.const 0) (i32.const 0)))
+    (drop (call $state_entry_write (local.get $entry) (local.get $start) (local.get $length) (i32.const 0))))
+
+  (func (export "init_test") (param i64) (result i32)
+    (i32.const 0) ;; Successful init
+  )
+
+  ;; Store the parameter in the state.
+  (func (export "test.set") (param i64) (result i32)
+    (call $store (i32.const 0) (call $read_parameter))
+    (i32.const 0))
+
+  ;; Return the value in the state. The return value is empty if there is no
+  ;; value.
+  (func (export "test.get") (param i64) (result i32)
+    (local $entry i64)
+    (local $size i32)
+    (local.set $entry (call $state_lookup_entry (i32.const 0) (i32.const 0)))
+    (if (i64.ge_s (local.get $entry) (i64.const 0))
+      (then
+        (local.set $size (call $state_entry_size (local.get $entry)))
+        (drop (call $state_entry_read (local.get $entry) (i32.const 0) (local.get $size) (i32.const 0)))
+        (drop (call $write_output (i32.const 0) (local.get $size) (i32.const 0)))))
+    (i32.const 0))
+
+  ;; Store the parameter in the state, and then reject.
+  (func (export "test.fail") (param i64) (result i32)
+    (call $store (i32.const 0) (call $read_parameter))
+    (i32.const -1))
+
+  ;; Store the byte 1 in the state and log it, and then call another contract.
+  ;; The parameter is the payload of the call, and the response of `invoke`
+  ;; is returned as 8 little-endian bytes. The call succeeds regardless of the
+  ;; response.
+  (func (export "test.forward") (param i64) (result i32)
+    (local $size i32)
+    (local.set $size (call $read_parameter))
+    (i32.store8 (i32.const 1024) (i32.const 1))
+    (call $store (i32.const 1024) (i32.const 1))
+    (drop (call $log_event (i32.const 1024) (i32.const 1)))
+    (i64.store (i32.const 1024) (call $invoke (i32.const 1) (i32.const 0) (local.get $size)))
+    (drop (call $write_output (i32.const 1024) (i32.const 8) (i32.const 0)))
+    (i32.const 0))
+
+  ;; Upgrade to the module whose reference is the parameter. The response of
+  ;; `upgrade` is returned as 8 little-endian bytes.
+  (func (export "test.upgrade") (param i64) (result i32)
+    (drop (call $read_parameter))
+    (i64.store (i32.const 1024) (call $upgrade (i32.const 0)))
+    (drop (call $write_output (i32.const 1024) (i32.const 8) (i32.const 0)))
+    (i32.const 0))
+
+  (memory 1)
+)
@@ -430,7 +430,7 @@ impl<'a, ImportFunc> From<BorrowedArtifact<'a, ImportFunc>> for Arc<OwnedArtifac
 /// instructions resolved to jumps in the instruction sequence, and function
 /// calls processed.
 #[repr(u8)]
-#[derive(Debug, num_enum::TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, num_enum::TryFromPrimitive)]
 pub enum InternalOpcode {
     // Control instructions
     Unreachable = 0u8,
@@ -546,6 +546,16 @@ pub enum InternalOpcode {
     I32WrapI64,
     I64ExtendI32S,
     I64ExtendI32U,
+
+    // The following opcodes were added after the original set. They are placed
+    // at the end so that the encoding of existing artifacts is unchanged.
+    I32Extend8S,
+    I32Extend16S,
+    I64Extend8S,
+    I64Extend16S,
+    I64Extend32S,
+    MemoryCopy,
+    MemoryFill,
 }
 
 /// Result of compilation. Either Ok(_) or an error indicating the reason.
@@ -1117,6 +1127,27 @@ impl Handler<&OpCode> for BackPatch {
             OpCode::I64ExtendI32U => {
                 self.out.push(I64ExtendI32U);
             }
+            OpCode::I32Extend8S => {
+                self.out.push(I32Extend8S);
+            }
+            OpCode::I32Extend16S => {
+                self.out.push(I32Extend16S);
+            }
+            OpCode::I64Extend8S => {
+                self.out.push(I64Extend8S);
+            }
+            OpCode::I64Extend16S => {
+                self.out.push(I64Extend16S);
+            }
+            OpCode::I64Extend32S => {
+                self.out.push(I64Extend32S);
+            }
+            OpCode::MemoryCopy => {
+                self.out.push(MemoryCopy);
+            }
+            OpCode::MemoryFill => {
+                self.out.push(MemoryFill);
+            }
         }
         Ok(())
     }
@@ -6,9 +6,16 @@ pub mod machine;
 pub mod metering_transformation;
 pub mod output;
 pub mod parse;
+pub mod trace;
 pub mod types;
 pub mod utils;
 pub mod validate;
 
 #[cfg(test)]
 mod metering_transformation_test;
+#[cfg(test)]
+mod output_test;
+#[cfg(test)]
+mod trace_test;
+#[cfg(test)]
+mod validation_config_test;
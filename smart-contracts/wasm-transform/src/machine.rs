@@ -3,6 +3,7 @@
 use crate::{
     artifact::{StackValue, *},
     constants::{MAX_NUM_PAGES, PAGE_SIZE},
+    trace::{NoTracer, StackFrame, Tracer},
     types::*,
 };
 use anyhow::{anyhow, bail, ensure};
@@ -112,6 +113,10 @@ struct FunctionState {
     return_type:      BlockType,
 }
 
+/// Size of the `Call` and `CallIndirect` instructions in compiled code, i.e.,
+/// the opcode followed by a 4 byte index.
+const CALL_INSTRUCTION_SIZE: usize = 5;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 /// A Wasm typed value. The values are not inherently signed or unsigned,
 /// but we choose signed integers as the representation type.
@@ -397,6 +402,35 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
         name: &Q,
         args: &[Value],
     ) -> RunResult<ExecutionOutcome<H::Interrupt>>
+    where
+        Name: std::borrow::Borrow<Q>, {
+        let config = self.initial_config(host, name, args)?;
+        self.run_config(host, config)
+    }
+
+    /// Like [`run`](Self::run), but additionally inform the given tracer of
+    /// all the steps of execution.
+    pub fn run_with_tracer<Q: std::fmt::Display + Ord + ?Sized, H: Host<I>, T: Tracer>(
+        &self,
+        host: &mut H,
+        name: &Q,
+        args: &[Value],
+        tracer: &mut T,
+    ) -> RunResult<ExecutionOutcome<H::Interrupt>>
+    where
+        Name: std::borrow::Borrow<Q>, {
+        let config = self.initial_config(host, name, args)?;
+        self.run_config_with_tracer(host, config, tracer)
+    }
+
+    /// Construct the configuration for executing the given entrypoint with the
+    /// given arguments. This charges the host for the initial memory.
+    fn initial_config<Q: std::fmt::Display + Ord + ?Sized, H: Host<I>>(
+        &self,
+        host: &mut H,
+        name: &Q,
+        args: &[Value],
+    ) -> RunResult<RunConfig>
     where
         Name: std::borrow::Borrow<Q>, {
         let start = *self.get_entrypoint_index(name)?;
@@ -465,7 +499,7 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
         let return_type = outer_function.return_type();
         let locals_base = 0;
 
-        let config = RunConfig {
+        Ok(RunConfig {
             pc,
             instructions_idx,
             function_frames,
@@ -475,8 +509,7 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
             locals_base,
             globals,
             max_memory,
-        };
-        self.run_config(host, config)
+        })
     }
 
     /// Returns the index of the given entrypoint if it exists.
@@ -501,6 +534,19 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
         &self,
         host: &mut H,
         config: RunConfig,
+    ) -> RunResult<ExecutionOutcome<H::Interrupt>> {
+        self.run_config_with_tracer(host, config, &mut NoTracer)
+    }
+
+    /// Like [`run_config`](Self::run_config), but additionally inform the given
+    /// tracer of all the steps of execution. If execution fails the tracer
+    /// has observed the last instruction that was started, which is typically
+    /// the one that caused the failure.
+    pub fn run_config_with_tracer<H: Host<I>, T: Tracer>(
+        &self,
+        host: &mut H,
+        config: RunConfig,
+        tracer: &mut T,
     ) -> RunResult<ExecutionOutcome<H::Interrupt>> {
         // we deliberately deconstruct the struct here instead of having mutable
         // references to fields here to improve performance. On some benchmarks
@@ -521,11 +567,37 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
         // the use of get_unchecked here is safe if the caller constructs the Runconfig
         // in a protocol compliant way.
         // The only way to construct a RunConfig is in this module (since all the fields
-        // are private), and the only place it is constructed is in the `run`
+        // are private), and the only place it is constructed is in the `initial_config`
         // method above, where the precondition is checked.
         let mut instructions = unsafe { self.code.get_unchecked(instructions_idx).code() };
+        let num_imports = self.imports.len();
+        if T::ACTIVE {
+            let mut call_stack = function_frames
+                .iter()
+                .map(|frame| StackFrame {
+                    function: (frame.instructions_idx + num_imports) as FuncIndex,
+                    // The saved program counter is the return address, which
+                    // is right after the call instruction.
+                    pc:       frame.pc - CALL_INSTRUCTION_SIZE,
+                })
+                .collect::<Vec<_>>();
+            call_stack.push(StackFrame {
+                function: (instructions_idx + num_imports) as FuncIndex,
+                pc,
+            });
+            tracer.start(&call_stack);
+        }
         'outer: loop {
             let instr = instructions[pc];
+            if T::ACTIVE {
+                tracer.instruction(
+                    (instructions_idx + num_imports) as FuncIndex,
+                    pc,
+                    // This is safe for the same reason as the transmute below.
+                    unsafe { std::mem::transmute(instr) },
+                    stack.size(),
+                );
+            }
             pc += 1;
             // FIXME: The unsafe here is a bit wrong, but it is much faster than using
             // InternalOpcode::try_from(instr). About 25% faster on a fibonacci test.
@@ -636,6 +708,9 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
                         instructions = unsafe { self.code.get_unchecked(instructions_idx).code() };
                         return_type = top_frame.return_type;
                         locals_base = top_frame.locals_base;
+                        if T::ACTIVE {
+                            tracer.exit();
+                        }
                     } else {
                         break 'outer;
                     }
@@ -695,6 +770,9 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
                         instructions_idx = local_idx;
                         pc = 0;
                         return_type = f.return_type();
+                        if T::ACTIVE {
+                            tracer.enter(idx);
+                        }
                     }
                 }
                 InternalOpcode::CallIndirect => {
@@ -763,6 +841,9 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
                             instructions_idx = *f_idx as usize - self.imports.len();
                             pc = 0;
                             return_type = f.return_type();
+                            if T::ACTIVE {
+                                tracer.enter(*f_idx);
+                            }
                         }
                     } else {
                         bail!("Calling undefined function {}.", idx) // trap
@@ -1146,6 +1227,39 @@ impl<I: TryFromImport, R: RunnableCode> Artifact<I, R> {
                     // and then extend, making it so that it is extended with 0's.
                     top.long = unsafe { top.short } as u32 as i64;
                 }
+                InternalOpcode::I32Extend8S => {
+                    unary_i32(&mut stack, |x| x as i8 as i32);
+                }
+                InternalOpcode::I32Extend16S => {
+                    unary_i32(&mut stack, |x| x as i16 as i32);
+                }
+                InternalOpcode::I64Extend8S => {
+                    unary_i64(&mut stack, |x| x as i8 as i64);
+                }
+                InternalOpcode::I64Extend16S => {
+                    unary_i64(&mut stack, |x| x as i16 as i64);
+                }
+                InternalOpcode::I64Extend32S => {
+                    unary_i64(&mut stack, |x| x as i32 as i64);
+                }
+                InternalOpcode::MemoryCopy => {
+                    let n = unsafe { stack.pop().short } as u32 as usize;
+                    let src = unsafe { stack.pop().short } as u32 as usize;
+                    let dest = unsafe { stack.pop().short } as u32 as usize;
+                    // Both ranges must be in bounds, even if nothing is copied.
+                    ensure!(
+                        src + n <= memory.len() && dest + n <= memory.len(),
+                        "Illegal memory access."
+                    );
+                    memory.copy_within(src..src + n, dest);
+                }
+                InternalOpcode::MemoryFill => {
+                    let n = unsafe { stack.pop().short } as u32 as usize;
+                    let val = unsafe { stack.pop().short } as u8;
+                    let dest = unsafe { stack.pop().short } as u32 as usize;
+                    ensure!(dest + n <= memory.len(), "Illegal memory access.");
+                    memory[dest..dest + n].fill(val);
+                }
             }
         }
 
@@ -45,6 +45,11 @@ fn lookup_label(labels: &[BlockType], idx: LabelIndex) -> TransformationResult<u
     }
 }
 
+/// Version of the costs defined in [`cost`]. This must be incremented whenever
+/// the costs, or the way they are charged, change, since it is used to detect
+/// precompiled artifacts that are out of date.
+pub const COST_TABLE_VERSION: u16 = 1;
+
 /// Definition of energy costs of instructions.
 pub mod cost {
     pub type Energy = u64;
@@ -125,6 +130,13 @@ pub mod cost {
     /// Constant part for the memory grow instruction. The variable part is
     /// charged for by the host function.
     pub const MEMGROW: Energy = read_stack(1) + write_stack(1) + 8;
+    /// Constant part of the bulk memory instructions `memory.copy` and
+    /// `memory.fill`, which check the bounds of their ranges. The variable part
+    /// is charged per byte, see [`MEMORY_BULK_PER_BYTE`].
+    pub const MEMORY_BULK: Energy = 2 * BOUNDS + read_stack(3);
+    /// Cost per byte of copying or filling memory. This is charged dynamically
+    /// before the instruction is executed.
+    pub const MEMORY_BULK_PER_BYTE: Energy = 1;
 
     /// Control instructions
     ///
@@ -257,6 +269,8 @@ pub mod cost {
             I64Store32(_) => BOUNDS + 2 + 2 + 4,
             MemorySize => MEMSIZE,
             MemoryGrow => MEMGROW,
+            MemoryCopy => MEMORY_BULK,
+            MemoryFill => MEMORY_BULK,
 
             // Numeric instructions
             I32Const(_) => CONST,
@@ -325,6 +339,11 @@ pub mod cost {
             I32WrapI64 => SIMPLE_UNOP,
             I64ExtendI32S => SIMPLE_UNOP,
             I64ExtendI32U => SIMPLE_UNOP,
+            I32Extend8S => SIMPLE_UNOP,
+            I32Extend16S => SIMPLE_UNOP,
+            I64Extend8S => SIMPLE_UNOP,
+            I64Extend16S => SIMPLE_UNOP,
+            I64Extend32S => SIMPLE_UNOP,
         };
         Ok(res)
     }
@@ -353,6 +372,10 @@ struct InstrSeqTransformer<'a, C> {
     /// Pending instructions that are going to be inserted after the energy
     /// charging instruction. This is a temporary cache.
     pending_instructions: InstrSeq,
+    /// An i32 local added to the function that is used to hold the length
+    /// argument of bulk memory instructions while it is charged for. This is
+    /// only present if the function uses such instructions.
+    scratch_local:        Option<LocalIndex>,
 }
 
 impl<'b, C: HasTransformationContext> InstrSeqTransformer<'b, C> {
@@ -396,6 +419,26 @@ impl<'b, C: HasTransformationContext> InstrSeqTransformer<'b, C> {
         self.add_to_new(instr);
     }
 
+    /// Account for all the pending energy, then charge for the number of bytes
+    /// affected by a bulk memory instruction, and finally push the
+    /// instruction. The number of bytes is the top of the stack when the
+    /// instruction is executed.
+    fn add_bulk_memory_account_energy(&mut self, instr: &OpCode) -> TransformationResult<()> {
+        use crate::types::OpCode::*;
+        let scratch = self.scratch_local.ok_or_else(|| {
+            anyhow!("Invariant violation, bulk memory instruction without a scratch local.")
+        })?;
+        self.account_energy_push_pending();
+        self.new_seq.push(LocalTee(scratch));
+        self.new_seq.push(I64ExtendI32U);
+        self.new_seq.push(I64Const(cost::MEMORY_BULK_PER_BYTE as i64));
+        self.new_seq.push(I64Mul);
+        self.new_seq.push(Call(FN_IDX_ACCOUNT_ENERGY));
+        self.new_seq.push(LocalGet(scratch));
+        self.add_to_new(instr);
+        Ok(())
+    }
+
     /// Add the OpCode to the pending sequence.
     fn add_to_pending(&mut self, instr: &OpCode) { self.pending_instructions.push(instr.clone()); }
 
@@ -481,6 +524,7 @@ impl<'b, C: HasTransformationContext> InstrSeqTransformer<'b, C> {
                     self.add_to_pending(&Call(FN_IDX_MEMORY_ALLOC));
                     self.add_to_pending(instr);
                 }
+                MemoryCopy | MemoryFill => self.add_bulk_memory_account_energy(instr)?,
                 Unreachable => self.add_instr_account_energy(instr),
                 Br(_) => {
                     self.add_instr_account_energy(instr);
@@ -651,6 +695,29 @@ pub fn inject_accounting<C: HasTransformationContext>(
             )
         })?);
 
+    // Charging for bulk memory instructions needs an additional local, which is
+    // added after all the existing ones.
+    let mut num_locals = function.num_locals;
+    let mut locals = function.locals.clone();
+    let uses_bulk_memory = function
+        .expr
+        .instrs
+        .iter()
+        .any(|instr| matches!(instr, OpCode::MemoryCopy | OpCode::MemoryFill));
+    let (scratch_local, energy) = if uses_bulk_memory {
+        let scratch = num_locals;
+        num_locals = num_locals
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Too many locals to add a scratch local."))?;
+        locals.push(Local {
+            multiplicity: 1,
+            ty:           ValueType::I32,
+        });
+        (Some(scratch), energy + cost::invoke_after(1))
+    } else {
+        (None, energy)
+    };
+
     let labels = vec![BlockType::from(function.ty.result)];
     let mut transformer = InstrSeqTransformer {
         module,
@@ -658,6 +725,7 @@ pub fn inject_accounting<C: HasTransformationContext>(
         new_seq: InstrSeq::new(),
         energy,
         pending_instructions: Vec::new(),
+        scratch_local,
     };
 
     transformer.run(function.expr.instrs.iter())?;
@@ -665,7 +733,8 @@ pub fn inject_accounting<C: HasTransformationContext>(
     Ok(Code {
         ty: function.ty.clone(),
         expr: Expression::from(transformer.new_seq),
-        locals: function.locals.clone(),
+        locals,
+        num_locals,
         ..*function
     })
 }
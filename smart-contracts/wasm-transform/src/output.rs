@@ -3,11 +3,12 @@
 use crate::{
     constants::{MAGIC_HASH, VERSION},
     parse::{Byte, SectionId, Skeleton, UnparsedSection},
-    types::{BlockType, CustomSection, FunctionType, Name, ValueType},
+    types::*,
 };
 use std::{
     convert::{TryFrom, TryInto},
     io::Write,
+    rc::Rc,
 };
 
 pub type OutResult<A> = anyhow::Result<A>;
@@ -176,6 +177,402 @@ impl<'a> Output for Skeleton<'a> {
     }
 }
 
+impl<A: Output> Output for Rc<A> {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> { self.as_ref().output(out) }
+}
+
+impl Output for Limits {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        match self.max {
+            None => {
+                0x00u8.output(out)?;
+                self.min.output(out)
+            }
+            Some(max) => {
+                0x01u8.output(out)?;
+                self.min.output(out)?;
+                max.output(out)
+            }
+        }
+    }
+}
+
+impl Output for TableType {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        0x70u8.output(out)?;
+        self.limits.output(out)
+    }
+}
+
+impl Output for MemoryType {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> { self.limits.output(out) }
+}
+
+impl Output for Import {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        self.mod_name.output(out)?;
+        self.item_name.output(out)?;
+        match self.description {
+            ImportDescription::Func {
+                type_idx,
+            } => {
+                0x00u8.output(out)?;
+                type_idx.output(out)
+            }
+        }
+    }
+}
+
+impl Output for Export {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        self.name.output(out)?;
+        match self.description {
+            ExportDescription::Func {
+                index,
+            } => {
+                0x00u8.output(out)?;
+                index.output(out)
+            }
+            ExportDescription::Table => {
+                0x01u8.output(out)?;
+                0u32.output(out)
+            }
+            ExportDescription::Memory => {
+                0x02u8.output(out)?;
+                0u32.output(out)
+            }
+            ExportDescription::Global {
+                index,
+            } => {
+                0x03u8.output(out)?;
+                index.output(out)
+            }
+        }
+    }
+}
+
+/// Output a constant expression as a single constant instruction followed by
+/// the end of the expression. References to globals in constant expressions
+/// are resolved when parsing, so the resulting module is equivalent, but not
+/// necessarily identical, to the one that was parsed.
+fn output_constant_expr(out: &mut impl Write, init: GlobalInit) -> OutResult<()> {
+    match init {
+        GlobalInit::I32(n) => OpCode::I32Const(n).output(out)?,
+        GlobalInit::I64(n) => OpCode::I64Const(n).output(out)?,
+    }
+    OpCode::End.output(out)
+}
+
+impl Output for Global {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        self.init.ty().output(out)?;
+        u8::from(self.mutable).output(out)?;
+        output_constant_expr(out, self.init)
+    }
+}
+
+impl Output for Element {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        // The table index.
+        0u32.output(out)?;
+        output_constant_expr(out, GlobalInit::I32(self.offset))?;
+        self.inits.output(out)
+    }
+}
+
+impl Output for Data {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        // The memory index.
+        0u32.output(out)?;
+        output_constant_expr(out, GlobalInit::I32(self.offset))?;
+        self.init.output(out)
+    }
+}
+
+impl Output for Local {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        self.multiplicity.output(out)?;
+        self.ty.output(out)
+    }
+}
+
+impl Output for MemArg {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        self.align.output(out)?;
+        self.offset.output(out)
+    }
+}
+
+/// Output a single instruction in the binary format. This is the inverse of
+/// [`decode_opcode`](crate::parse::decode_opcode).
+impl Output for OpCode {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        use OpCode::*;
+        let opcode: Byte = match self {
+            End => 0x0B,
+            Nop => 0x01,
+            Unreachable => 0x00,
+            Block(bt) => {
+                0x02u8.output(out)?;
+                return bt.output(out);
+            }
+            Loop(bt) => {
+                0x03u8.output(out)?;
+                return bt.output(out);
+            }
+            If {
+                ty,
+            } => {
+                0x04u8.output(out)?;
+                return ty.output(out);
+            }
+            Else => 0x05,
+            Br(l) => {
+                0x0Cu8.output(out)?;
+                return l.output(out);
+            }
+            BrIf(l) => {
+                0x0Du8.output(out)?;
+                return l.output(out);
+            }
+            BrTable {
+                labels,
+                default,
+            } => {
+                0x0Eu8.output(out)?;
+                labels.output(out)?;
+                return default.output(out);
+            }
+            Return => 0x0F,
+            Call(idx) => {
+                0x10u8.output(out)?;
+                return idx.output(out);
+            }
+            CallIndirect(ty) => {
+                0x11u8.output(out)?;
+                ty.output(out)?;
+                // The table index.
+                return 0x00u8.output(out);
+            }
+            Drop => 0x1A,
+            Select => 0x1B,
+            LocalGet(idx) => {
+                0x20u8.output(out)?;
+                return idx.output(out);
+            }
+            LocalSet(idx) => {
+                0x21u8.output(out)?;
+                return idx.output(out);
+            }
+            LocalTee(idx) => {
+                0x22u8.output(out)?;
+                return idx.output(out);
+            }
+            GlobalGet(idx) => {
+                0x23u8.output(out)?;
+                return idx.output(out);
+            }
+            GlobalSet(idx) => {
+                0x24u8.output(out)?;
+                return idx.output(out);
+            }
+            I32Load(memarg) => return output_memory_instruction(out, 0x28, memarg),
+            I64Load(memarg) => return output_memory_instruction(out, 0x29, memarg),
+            I32Load8S(memarg) => return output_memory_instruction(out, 0x2C, memarg),
+            I32Load8U(memarg) => return output_memory_instruction(out, 0x2D, memarg),
+            I32Load16S(memarg) => return output_memory_instruction(out, 0x2E, memarg),
+            I32Load16U(memarg) => return output_memory_instruction(out, 0x2F, memarg),
+            I64Load8S(memarg) => return output_memory_instruction(out, 0x30, memarg),
+            I64Load8U(memarg) => return output_memory_instruction(out, 0x31, memarg),
+            I64Load16S(memarg) => return output_memory_instruction(out, 0x32, memarg),
+            I64Load16U(memarg) => return output_memory_instruction(out, 0x33, memarg),
+            I64Load32S(memarg) => return output_memory_instruction(out, 0x34, memarg),
+            I64Load32U(memarg) => return output_memory_instruction(out, 0x35, memarg),
+            I32Store(memarg) => return output_memory_instruction(out, 0x36, memarg),
+            I64Store(memarg) => return output_memory_instruction(out, 0x37, memarg),
+            I32Store8(memarg) => return output_memory_instruction(out, 0x3A, memarg),
+            I32Store16(memarg) => return output_memory_instruction(out, 0x3B, memarg),
+            I64Store8(memarg) => return output_memory_instruction(out, 0x3C, memarg),
+            I64Store16(memarg) => return output_memory_instruction(out, 0x3D, memarg),
+            I64Store32(memarg) => return output_memory_instruction(out, 0x3E, memarg),
+            MemorySize => {
+                // The memory index.
+                return out.write_all(&[0x3F, 0x00]).map_err(Into::into);
+            }
+            MemoryGrow => {
+                // The memory index.
+                return out.write_all(&[0x40, 0x00]).map_err(Into::into);
+            }
+            I32Const(n) => {
+                0x41u8.output(out)?;
+                return n.output(out);
+            }
+            I64Const(n) => {
+                0x42u8.output(out)?;
+                return n.output(out);
+            }
+            I32Eqz => 0x45,
+            I32Eq => 0x46,
+            I32Ne => 0x47,
+            I32LtS => 0x48,
+            I32LtU => 0x49,
+            I32GtS => 0x4A,
+            I32GtU => 0x4B,
+            I32LeS => 0x4C,
+            I32LeU => 0x4D,
+            I32GeS => 0x4E,
+            I32GeU => 0x4F,
+            I64Eqz => 0x50,
+            I64Eq => 0x51,
+            I64Ne => 0x52,
+            I64LtS => 0x53,
+            I64LtU => 0x54,
+            I64GtS => 0x55,
+            I64GtU => 0x56,
+            I64LeS => 0x57,
+            I64LeU => 0x58,
+            I64GeS => 0x59,
+            I64GeU => 0x5A,
+            I32Clz => 0x67,
+            I32Ctz => 0x68,
+            I32Popcnt => 0x69,
+            I32Add => 0x6A,
+            I32Sub => 0x6B,
+            I32Mul => 0x6C,
+            I32DivS => 0x6D,
+            I32DivU => 0x6E,
+            I32RemS => 0x6F,
+            I32RemU => 0x70,
+            I32And => 0x71,
+            I32Or => 0x72,
+            I32Xor => 0x73,
+            I32Shl => 0x74,
+            I32ShrS => 0x75,
+            I32ShrU => 0x76,
+            I32Rotl => 0x77,
+            I32Rotr => 0x78,
+            I64Clz => 0x79,
+            I64Ctz => 0x7A,
+            I64Popcnt => 0x7B,
+            I64Add => 0x7C,
+            I64Sub => 0x7D,
+            I64Mul => 0x7E,
+            I64DivS => 0x7F,
+            I64DivU => 0x80,
+            I64RemS => 0x81,
+            I64RemU => 0x82,
+            I64And => 0x83,
+            I64Or => 0x84,
+            I64Xor => 0x85,
+            I64Shl => 0x86,
+            I64ShrS => 0x87,
+            I64ShrU => 0x88,
+            I64Rotl => 0x89,
+            I64Rotr => 0x8A,
+            I32WrapI64 => 0xA7,
+            I64ExtendI32S => 0xAC,
+            I64ExtendI32U => 0xAD,
+            I32Extend8S => 0xC0,
+            I32Extend16S => 0xC1,
+            I64Extend8S => 0xC2,
+            I64Extend16S => 0xC3,
+            I64Extend32S => 0xC4,
+            MemoryCopy => {
+                0xFCu8.output(out)?;
+                10u32.output(out)?;
+                // The source and destination memory indices.
+                return out.write_all(&[0x00, 0x00]).map_err(Into::into);
+            }
+            MemoryFill => {
+                0xFCu8.output(out)?;
+                11u32.output(out)?;
+                // The memory index.
+                return 0x00u8.output(out);
+            }
+        };
+        opcode.output(out)
+    }
+}
+
+fn output_memory_instruction(out: &mut impl Write, opcode: Byte, memarg: &MemArg) -> OutResult<()> {
+    opcode.output(out)?;
+    memarg.output(out)
+}
+
+/// The body of a function is prefixed by its size.
+impl Output for Code {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        let mut body = Vec::new();
+        self.locals.output(&mut body)?;
+        for instr in self.expr.instrs.iter() {
+            instr.output(&mut body)?;
+        }
+        body.as_slice().output(out)
+    }
+}
+
+/// Output a section with the given id whose contents are written by the
+/// given function. The contents are prefixed by their size.
+fn output_section(
+    out: &mut impl Write,
+    section_id: SectionId,
+    contents: impl FnOnce(&mut Vec<u8>) -> OutResult<()>,
+) -> OutResult<()> {
+    let mut bytes = Vec::new();
+    contents(&mut bytes)?;
+    UnparsedSection {
+        section_id,
+        bytes: &bytes,
+    }
+    .output(out)
+}
+
+/// Write out the module in the binary format. Sections that are empty are
+/// omitted. Since the module does not retain custom sections, nor the way
+/// constant expressions were written in the original module, parsing and
+/// validating the output yields an equivalent, but not necessarily identical,
+/// module.
+impl Output for Module {
+    fn output(&self, out: &mut impl Write) -> OutResult<()> {
+        out.write_all(&MAGIC_HASH)?;
+        out.write_all(&VERSION)?;
+        if !self.ty.types.is_empty() {
+            output_section(out, SectionId::Type, |b| self.ty.types.output(b))?;
+        }
+        if !self.import.imports.is_empty() {
+            output_section(out, SectionId::Import, |b| self.import.imports.output(b))?;
+        }
+        if !self.func.types.is_empty() {
+            output_section(out, SectionId::Function, |b| self.func.types.output(b))?;
+        }
+        if let Some(table_type) = self.table.table_type.as_ref() {
+            output_section(out, SectionId::Table, |b| std::slice::from_ref(table_type).output(b))?;
+        }
+        if let Some(memory_type) = self.memory.memory_type.as_ref() {
+            output_section(out, SectionId::Memory, |b| {
+                std::slice::from_ref(memory_type).output(b)
+            })?;
+        }
+        if !self.global.globals.is_empty() {
+            output_section(out, SectionId::Global, |b| self.global.globals.output(b))?;
+        }
+        if !self.export.exports.is_empty() {
+            output_section(out, SectionId::Export, |b| self.export.exports.output(b))?;
+        }
+        // Start functions are not supported, so the start section is always empty.
+        if !self.element.elements.is_empty() {
+            output_section(out, SectionId::Element, |b| self.element.elements.output(b))?;
+        }
+        if !self.code.impls.is_empty() {
+            output_section(out, SectionId::Code, |b| self.code.impls.output(b))?;
+        }
+        if !self.data.sections.is_empty() {
+            output_section(out, SectionId::Data, |b| self.data.sections.output(b))?;
+        }
+        Ok(())
+    }
+}
+
 /// Output a custom section into the given writer.
 pub fn write_custom_section(out: &mut impl Write, cs: &CustomSection) -> OutResult<()> {
     out.write_all(&[SectionId::Custom as u8])?;
@@ -0,0 +1,85 @@
+//! Tests of writing out validated modules.
+use crate::{
+    output::Output,
+    parse::parse_skeleton,
+    types::{FunctionType, Name},
+    validate::{validate_module_with_config, ValidateImportExport, ValidationConfig},
+};
+
+/// The module
+/// ```wasm
+/// (module
+///   (type (func (param i32 i64) (result i64)))
+///   (import "m" "h" (func (type 0)))
+///   (table 2 funcref)
+///   (memory 1 2)
+///   (global (mut i64) (i64.const -5))
+///   (global i32 (i32.const 7))
+///   (export "f" (func 1))
+///   (export "t" (table 0))
+///   (export "m" (memory 0))
+///   (export "g" (global 0))
+///   (elem (i32.const 1) 1)
+///   (data (i32.const 3) "ab")
+///   (func (type 0) (local i32 i32) (local i64)
+///     (block
+///       (loop
+///         (br_table 0 1 (local.get 0))))
+///     (if (result i64) (local.get 0) (then (i64.const 1)) (else (global.get 0)))
+///     (drop)
+///     (i64.store32 offset=8 (i32.const 0) (i64.load8_u offset=300 align=1 (i32.const 1)))
+///     (memory.fill (i32.const 0) (i32.const 0) (i32.const 1))
+///     (memory.copy (i32.const 0) (i32.const 1) (i32.const 1))
+///     (local.set 2 (i32.extend8_s (memory.grow (memory.size))))
+///     (drop (call_indirect (type 0) (i32.const -1) (i64.extend32_s (local.get 1)) (i32.const 0)))
+///     (call 0
+///       (i32.wrap_i64 (i64.const 0x7fffffffffff))
+///       (select
+///         (i64.const 0)
+///         (i64.rotr (local.get 4) (i64.const 2))
+///         (local.tee 3 (i32.const 1))))))
+/// ```
+const MODULE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7e, 0x01, 0x7e, // type section
+    0x02, 0x07, 0x01, 0x01, b'm', 0x01, b'h', 0x00, 0x00, // import section
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x04, 0x04, 0x01, 0x70, 0x00, 0x02, // table section
+    0x05, 0x04, 0x01, 0x01, 0x01, 0x02, // memory section
+    0x06, 0x0b, 0x02, 0x7e, 0x01, 0x42, 0x7b, 0x0b, 0x7f, 0x00, 0x41, 0x07,
+    0x0b, // global section
+    0x07, 0x11, 0x04, 0x01, b'f', 0x00, 0x01, 0x01, b't', 0x01, 0x00, 0x01, b'm', 0x02, 0x00, 0x01,
+    b'g', 0x03, 0x00, // export section
+    0x09, 0x07, 0x01, 0x00, 0x41, 0x01, 0x0b, 0x01, 0x01, // element section
+    0x0a, 0x66, 0x01, 0x64, 0x02, 0x02, 0x7f, 0x01, 0x7e, 0x02, 0x40, 0x03, 0x40, 0x20, 0x00, 0x0e,
+    0x01, 0x00, 0x01, 0x0b, 0x0b, 0x20, 0x00, 0x04, 0x7e, 0x42, 0x01, 0x05, 0x23, 0x00, 0x0b, 0x1a,
+    0x41, 0x00, 0x41, 0x01, 0x31, 0x00, 0xac, 0x02, 0x3e, 0x02, 0x08, 0x41, 0x00, 0x41, 0x00, 0x41,
+    0x01, 0xfc, 0x0b, 0x00, 0x41, 0x00, 0x41, 0x01, 0x41, 0x01, 0xfc, 0x0a, 0x00, 0x00, 0x3f, 0x00,
+    0x40, 0x00, 0xc0, 0x21, 0x02, 0x41, 0x7f, 0x20, 0x01, 0xc4, 0x41, 0x00, 0x11, 0x00, 0x00, 0x1a,
+    0x42, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x1f, 0xa7, 0x42, 0x00, 0x20, 0x04, 0x42, 0x02, 0x8a,
+    0x41, 0x01, 0x22, 0x03, 0x1b, 0x10, 0x00, 0x0b, // code section
+    0x0b, 0x08, 0x01, 0x00, 0x41, 0x03, 0x0b, 0x02, b'a', b'b', // data section
+];
+
+struct AllowAll;
+
+impl ValidateImportExport for AllowAll {
+    fn validate_import_function(&self, _: bool, _: &Name, _: &Name, _: &FunctionType) -> bool {
+        true
+    }
+
+    fn validate_export_function(&self, _: &Name, _: &FunctionType) -> bool { true }
+}
+
+#[test]
+/// Writing out a module that is encoded in the canonical way, i.e., without
+/// custom sections and with the shortest encodings of integers, reproduces the
+/// original bytes.
+fn test_output_module() {
+    let skeleton = parse_skeleton(MODULE).expect("Module should parse.");
+    let module = validate_module_with_config(&ValidationConfig::V1, &AllowAll, &skeleton)
+        .expect("Module should be valid.");
+    let mut out = Vec::new();
+    module.output(&mut out).expect("Writing to a vector does not fail.");
+    assert_eq!(out, MODULE);
+}
@@ -832,6 +832,10 @@ pub enum ParseError {
     UnsupportedInstruction {
         opcode: Byte,
     },
+    UnsupportedPrefixedInstruction {
+        prefix: Byte,
+        opcode: u32,
+    },
     UnsupportedValueType {
         byte: Byte,
     },
@@ -851,6 +855,10 @@ impl std::fmt::Display for ParseError {
             ParseError::UnsupportedInstruction {
                 opcode,
             } => write!(f, "Unsupported instruction {:#04x}", opcode),
+            ParseError::UnsupportedPrefixedInstruction {
+                prefix,
+                opcode,
+            } => write!(f, "Unsupported instruction {:#04x} {}", prefix, opcode),
             ParseError::UnsupportedValueType {
                 byte,
             } => write!(f, "Unknown value type byte {:#04x}", byte),
@@ -1104,6 +1112,33 @@ pub fn decode_opcode(cursor: &mut Cursor<&[u8]>) -> ParseResult<OpCode> {
 
         0xAC => Ok(OpCode::I64ExtendI32S),
         0xAD => Ok(OpCode::I64ExtendI32U),
+
+        0xC0 => Ok(OpCode::I32Extend8S),
+        0xC1 => Ok(OpCode::I32Extend16S),
+        0xC2 => Ok(OpCode::I64Extend8S),
+        0xC3 => Ok(OpCode::I64Extend16S),
+        0xC4 => Ok(OpCode::I64Extend32S),
+
+        0xFC => {
+            let opcode: u32 = cursor.next(EMPTY_CTX)?;
+            match opcode {
+                // memory.copy has two memory indices, both of which must be 0
+                // since there is only one memory.
+                10 => {
+                    expect_byte(cursor, 0x00)?;
+                    expect_byte(cursor, 0x00)?;
+                    Ok(OpCode::MemoryCopy)
+                }
+                11 => {
+                    expect_byte(cursor, 0x00)?;
+                    Ok(OpCode::MemoryFill)
+                }
+                opcode => bail!(ParseError::UnsupportedPrefixedInstruction {
+                    prefix: 0xFC,
+                    opcode,
+                }),
+            }
+        }
         byte => bail!(ParseError::UnsupportedInstruction {
             opcode: byte,
         }),
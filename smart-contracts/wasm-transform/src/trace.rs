@@ -0,0 +1,240 @@
+//! Support for observing execution of artifacts.
+//!
+//! The interpreter in [`machine`](crate::machine) can be given a [`Tracer`]
+//! which is informed of every instruction that is executed, as well as of
+//! function entry and exit. This is intended for debugging and profiling
+//! contracts, and the default tracer used by
+//! [`Artifact::run`](crate::artifact::Artifact::run) does nothing and is
+//! optimized away completely.
+//!
+//! The [`CallStackTracer`] maintains a shadow call stack during execution,
+//! which allows constructing a [`TrapReport`] with a backtrace if execution
+//! fails. Function names in the backtrace are taken from the custom `name`
+//! section of the original module, if it is present, see [`FunctionNames`].
+use crate::{
+    artifact::InternalOpcode,
+    parse::{parse_custom, GetParseable, ParseResult, Skeleton, EMPTY_CTX},
+    types::FuncIndex,
+};
+use anyhow::{bail, ensure};
+use std::{collections::BTreeMap, io::Cursor};
+
+/// An observer of execution of the interpreter.
+///
+/// Function indices are indices in the original module, i.e., they include
+/// imported functions. Program counters are offsets in the compiled code of the
+/// function, which is what the interpreter executes.
+pub trait Tracer {
+    /// Whether the tracer observes anything. If this is `false` the
+    /// interpreter skips all the tracing work. This should only be set by
+    /// tracers that ignore all events.
+    const ACTIVE: bool = true;
+
+    /// Called when execution of a configuration starts or resumes, with the
+    /// call stack at that point. The first element is the outermost frame,
+    /// the last element is the function that is about to be executed.
+    fn start(&mut self, _call_stack: &[StackFrame]) {}
+
+    /// Called before each instruction is executed. The stack height is the
+    /// height of the runtime stack, which includes the locals of all the
+    /// active functions.
+    fn instruction(
+        &mut self,
+        function: FuncIndex,
+        pc: usize,
+        opcode: InternalOpcode,
+        stack_height: usize,
+    );
+
+    /// Called when a function defined in the module is entered as a result of
+    /// a direct or indirect call. Calls to imported functions are handled by
+    /// the host and do not produce this event.
+    fn enter(&mut self, _function: FuncIndex) {}
+
+    /// Called when a function returns to its caller. This is not called when
+    /// the outermost function returns.
+    fn exit(&mut self) {}
+}
+
+/// A tracer that ignores all events. This is used when no tracing is
+/// requested.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTracer;
+
+impl Tracer for NoTracer {
+    const ACTIVE: bool = false;
+
+    #[cfg_attr(not(feature = "fuzz-coverage"), inline(always))]
+    fn instruction(&mut self, _: FuncIndex, _: usize, _: InternalOpcode, _: usize) {}
+}
+
+/// A frame of the call stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Index of the function in the original module.
+    pub function: FuncIndex,
+    /// Program counter in the function. For the innermost frame this is the
+    /// position of the last instruction that was started, for all other
+    /// frames it is the position of the call instruction.
+    pub pc:       usize,
+}
+
+/// A tracer that maintains the call stack of execution. After execution fails
+/// the tracer can be used to construct a [`TrapReport`] that records where the
+/// failure occurred.
+#[derive(Debug, Clone, Default)]
+pub struct CallStackTracer {
+    frames: Vec<StackFrame>,
+}
+
+impl CallStackTracer {
+    pub fn new() -> Self { Self::default() }
+
+    /// The current call stack, outermost frame first.
+    pub fn call_stack(&self) -> &[StackFrame] { &self.frames }
+
+    /// Construct a report of a trap that occurred during execution traced by
+    /// this tracer. If `names` are supplied they are used to name the
+    /// functions in the backtrace.
+    pub fn trap_report(&self, error: anyhow::Error, names: Option<&FunctionNames>) -> TrapReport {
+        let backtrace = self
+            .frames
+            .iter()
+            .rev()
+            .map(|frame| BacktraceFrame {
+                function: frame.function,
+                pc:       frame.pc,
+                name:     names.and_then(|names| names.get(frame.function)).map(String::from),
+            })
+            .collect();
+        TrapReport {
+            error,
+            backtrace,
+        }
+    }
+}
+
+impl Tracer for CallStackTracer {
+    fn start(&mut self, call_stack: &[StackFrame]) {
+        self.frames.clear();
+        self.frames.extend_from_slice(call_stack);
+    }
+
+    fn instruction(&mut self, function: FuncIndex, pc: usize, _: InternalOpcode, _: usize) {
+        if let Some(top) = self.frames.last_mut() {
+            top.function = function;
+            top.pc = pc;
+        } else {
+            self.frames.push(StackFrame {
+                function,
+                pc,
+            })
+        }
+    }
+
+    fn enter(&mut self, function: FuncIndex) {
+        self.frames.push(StackFrame {
+            function,
+            pc: 0,
+        })
+    }
+
+    fn exit(&mut self) { self.frames.pop(); }
+}
+
+/// A frame of a backtrace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktraceFrame {
+    /// Index of the function in the original module.
+    pub function: FuncIndex,
+    /// Program counter in the compiled code of the function.
+    pub pc:       usize,
+    /// Name of the function, if known.
+    pub name:     Option<String>,
+}
+
+/// A structured report of a failed execution.
+#[derive(Debug)]
+pub struct TrapReport {
+    /// The error that caused execution to fail.
+    pub error:     anyhow::Error,
+    /// The call stack at the point of failure, innermost frame first.
+    pub backtrace: Vec<BacktraceFrame>,
+}
+
+impl std::fmt::Display for TrapReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Execution failed: {:#}", self.error)?;
+        for (i, frame) in self.backtrace.iter().enumerate() {
+            match frame.name.as_ref() {
+                Some(name) => writeln!(
+                    f,
+                    "  {}: function {} ({}) at pc {}",
+                    i, frame.function, name, frame.pc
+                )?,
+                None => writeln!(f, "  {}: function {} at pc {}", i, frame.function, frame.pc)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names of functions as recorded in the custom `name` section of a module.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionNames {
+    names: BTreeMap<FuncIndex, String>,
+}
+
+/// Name of the custom section that contains names of functions.
+const NAME_SECTION: &str = "name";
+
+/// Identifier of the subsection of the `name` section that contains function
+/// names.
+const FUNCTION_NAMES_SUBSECTION: u8 = 1;
+
+impl FunctionNames {
+    /// Look up the name of the function with the given index.
+    pub fn get(&self, function: FuncIndex) -> Option<&str> {
+        self.names.get(&function).map(String::as_str)
+    }
+
+    /// Extract function names from the custom `name` section of the module, if
+    /// it has one. Other subsections of the `name` section are ignored.
+    pub fn from_skeleton(skeleton: &Skeleton) -> ParseResult<Option<Self>> {
+        for section in skeleton.custom.iter() {
+            let cs = parse_custom(section)?;
+            if cs.name.name == NAME_SECTION {
+                return Self::parse_name_section(cs.contents).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_name_section(contents: &[u8]) -> ParseResult<Self> {
+        let mut cursor = Cursor::new(contents);
+        let mut names = BTreeMap::new();
+        while (cursor.position() as usize) < contents.len() {
+            let id: u8 = cursor.next(EMPTY_CTX)?;
+            let subsection: &[u8] = cursor.next(EMPTY_CTX)?;
+            if id != FUNCTION_NAMES_SUBSECTION {
+                continue;
+            }
+            let mut sub_cursor = Cursor::new(subsection);
+            let len: u32 = sub_cursor.next(EMPTY_CTX)?;
+            for _ in 0..len {
+                let idx: FuncIndex = sub_cursor.next(EMPTY_CTX)?;
+                let name: &[u8] = sub_cursor.next(EMPTY_CTX)?;
+                if names.insert(idx, String::from_utf8_lossy(name).into_owned()).is_some() {
+                    bail!("Duplicate function name for function {}.", idx);
+                }
+            }
+            ensure!(
+                sub_cursor.position() as usize == subsection.len(),
+                "Malformed function names subsection."
+            );
+        }
+        Ok(Self {
+            names,
+        })
+    }
+}
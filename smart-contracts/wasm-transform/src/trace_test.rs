@@ -0,0 +1,200 @@
+//! Tests of execution tracing and trap reports.
+use crate::{
+    artifact::{ArtifactNamedImport, InternalOpcode},
+    machine::{ExecutionOutcome, Host, NoInterrupt, RunResult, RuntimeStack},
+    parse::parse_skeleton,
+    trace::*,
+    types::{FuncIndex, FunctionType, Name},
+    validate::{validate_module, ValidateImportExport},
+};
+
+/// The module
+/// ```wasm
+/// (module
+///   (func $trap unreachable)
+///   (func $entry (export "f") call $trap))
+/// ```
+/// with a `name` section naming both functions.
+const MODULE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section
+    0x03, 0x03, 0x02, 0x00, 0x00, // function section
+    0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x01, // export section
+    0x0a, 0x0a, 0x02, 0x03, 0x00, 0x00, 0x0b, 0x04, 0x00, 0x10, 0x00, 0x0b, // code section
+    0x00, 0x15, 0x04, b'n', b'a', b'm', b'e', 0x01, 0x0e, 0x02, 0x00, 0x04, b't', b'r', b'a', b'p',
+    0x01, 0x05, b'e', b'n', b't', b'r', b'y', // name section
+];
+
+/// The module
+/// ```wasm
+/// (module
+///   (import "m" "h" (func))
+///   (func call 0 unreachable)
+///   (func (export "f") call 1))
+/// ```
+/// where the imported function interrupts execution.
+const INTERRUPT_MODULE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section
+    0x02, 0x07, 0x01, 0x01, b'm', 0x01, b'h', 0x00, 0x00, // import section
+    0x03, 0x03, 0x02, 0x00, 0x00, // function section
+    0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x02, // export section
+    0x0a, 0x0c, 0x02, 0x05, 0x00, 0x10, 0x00, 0x00, 0x0b, 0x04, 0x00, 0x10, 0x01,
+    0x0b, // code section
+];
+
+struct AllowAll;
+
+impl ValidateImportExport for AllowAll {
+    fn validate_import_function(&self, _: bool, _: &Name, _: &Name, _: &FunctionType) -> bool {
+        true
+    }
+
+    fn validate_export_function(&self, _: &Name, _: &FunctionType) -> bool { true }
+}
+
+struct NoHost;
+
+impl Host<ArtifactNamedImport> for NoHost {
+    type Interrupt = NoInterrupt;
+
+    fn tick_initial_memory(&mut self, _: u32) -> RunResult<()> { Ok(()) }
+
+    fn call(
+        &mut self,
+        _: &ArtifactNamedImport,
+        _: &mut Vec<u8>,
+        _: &mut RuntimeStack,
+    ) -> RunResult<Option<Self::Interrupt>> {
+        anyhow::bail!("The module has no imports.")
+    }
+}
+
+/// A host that interrupts execution on every call of an imported function.
+struct InterruptingHost;
+
+impl Host<ArtifactNamedImport> for InterruptingHost {
+    type Interrupt = ();
+
+    fn tick_initial_memory(&mut self, _: u32) -> RunResult<()> { Ok(()) }
+
+    fn call(
+        &mut self,
+        _: &ArtifactNamedImport,
+        _: &mut Vec<u8>,
+        _: &mut RuntimeStack,
+    ) -> RunResult<Option<Self::Interrupt>> {
+        Ok(Some(()))
+    }
+}
+
+#[derive(Default)]
+struct CountingTracer {
+    instructions: Vec<(FuncIndex, usize)>,
+    entered:      Vec<FuncIndex>,
+}
+
+impl Tracer for CountingTracer {
+    fn instruction(&mut self, function: FuncIndex, pc: usize, _: InternalOpcode, _: usize) {
+        self.instructions.push((function, pc))
+    }
+
+    fn enter(&mut self, function: FuncIndex) { self.entered.push(function) }
+}
+
+#[test]
+fn test_function_names() {
+    let skeleton = parse_skeleton(MODULE).expect("Module should parse.");
+    let names = FunctionNames::from_skeleton(&skeleton)
+        .expect("Name section should parse.")
+        .expect("Name section is present.");
+    assert_eq!(names.get(0), Some("trap"));
+    assert_eq!(names.get(1), Some("entry"));
+    assert_eq!(names.get(2), None);
+}
+
+#[test]
+fn test_trap_backtrace() {
+    let skeleton = parse_skeleton(MODULE).expect("Module should parse.");
+    let names = FunctionNames::from_skeleton(&skeleton).expect("Name section should parse.");
+    let artifact = validate_module(&AllowAll, &skeleton)
+        .expect("Module should be valid.")
+        .compile::<ArtifactNamedImport>()
+        .expect("Module should compile.");
+
+    let mut counting = CountingTracer::default();
+    assert!(artifact.run_with_tracer(&mut NoHost, "f", &[], &mut counting).is_err());
+    assert_eq!(counting.instructions, vec![(1, 0), (0, 0)], "Call followed by unreachable.");
+    assert_eq!(counting.entered, vec![0]);
+
+    let mut tracer = CallStackTracer::new();
+    let error = artifact
+        .run_with_tracer(&mut NoHost, "f", &[], &mut tracer)
+        .expect_err("Execution should trap.");
+    let report = tracer.trap_report(error, names.as_ref());
+    assert_eq!(report.backtrace, vec![
+        BacktraceFrame {
+            function: 0,
+            pc:       0,
+            name:     Some("trap".into()),
+        },
+        BacktraceFrame {
+            function: 1,
+            pc:       0,
+            name:     Some("entry".into()),
+        },
+    ]);
+}
+
+#[test]
+fn test_trap_backtrace_after_resume() {
+    let skeleton = parse_skeleton(INTERRUPT_MODULE).expect("Module should parse.");
+    let artifact = validate_module(&AllowAll, &skeleton)
+        .expect("Module should be valid.")
+        .compile::<ArtifactNamedImport>()
+        .expect("Module should compile.");
+
+    let mut tracer = CallStackTracer::new();
+    let config = match artifact
+        .run_with_tracer(&mut InterruptingHost, "f", &[], &mut tracer)
+        .expect("Execution should be interrupted.")
+    {
+        ExecutionOutcome::Interrupted {
+            config,
+            ..
+        } => config,
+        ExecutionOutcome::Success {
+            ..
+        } => panic!("Execution should be interrupted."),
+    };
+    let before = tracer.call_stack().to_vec();
+    assert_eq!(before, vec![
+        StackFrame {
+            function: 2,
+            pc:       0,
+        },
+        StackFrame {
+            function: 1,
+            pc:       0,
+        },
+    ]);
+
+    // On resumption the outer frame must still point at the call instruction,
+    // and not at the return address saved by the interpreter.
+    let error = artifact
+        .run_config_with_tracer(&mut InterruptingHost, config, &mut tracer)
+        .expect_err("Execution should trap.");
+    let report = tracer.trap_report(error, None);
+    assert_eq!(report.backtrace, vec![
+        BacktraceFrame {
+            function: 1,
+            pc:       5,
+            name:     None,
+        },
+        BacktraceFrame {
+            function: 2,
+            pc:       0,
+            name:     None,
+        },
+    ]);
+}
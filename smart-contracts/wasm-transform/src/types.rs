@@ -559,4 +559,17 @@ pub enum OpCode {
     I32WrapI64,
     I64ExtendI32S,
     I64ExtendI32U,
+
+    // Sign extension instructions. These are only allowed if enabled by the
+    // validation configuration.
+    I32Extend8S,
+    I32Extend16S,
+    I64Extend8S,
+    I64Extend16S,
+    I64Extend32S,
+
+    // Bulk memory instructions. These are only allowed if enabled by the
+    // validation configuration.
+    MemoryCopy,
+    MemoryFill,
 }
@@ -4,7 +4,9 @@
 use crate::{
     artifact::{Artifact, CompiledFunction, CompiledFunctionBytes, TryFromImport},
     parse::{parse_skeleton, GetParseable, Parseable, Skeleton},
-    validate::{validate_module, ValidateImportExport},
+    validate::{
+        validate_module, validate_module_with_config, ValidateImportExport, ValidationConfig,
+    },
 };
 
 /// Strip the custom sections from the module.
@@ -28,6 +30,28 @@ pub fn instantiate_with_metering<I: TryFromImport, VI: ValidateImportExport>(
     module.compile()
 }
 
+/// Same as [`instantiate`], but allow the features enabled by the given
+/// validation configuration.
+pub fn instantiate_with_config<I: TryFromImport, VI: ValidateImportExport>(
+    config: &ValidationConfig,
+    imp: &VI,
+    bytes: &[u8],
+) -> anyhow::Result<Artifact<I, CompiledFunction>> {
+    validate_module_with_config(config, imp, &parse_skeleton(bytes)?)?.compile()
+}
+
+/// Same as [`instantiate_with_metering`], but allow the features enabled by
+/// the given validation configuration.
+pub fn instantiate_with_metering_and_config<I: TryFromImport, VI: ValidateImportExport>(
+    config: &ValidationConfig,
+    imp: &VI,
+    bytes: &[u8],
+) -> anyhow::Result<Artifact<I, CompiledFunction>> {
+    let mut module = validate_module_with_config(config, imp, &parse_skeleton(bytes)?)?;
+    module.inject_metering()?;
+    module.compile()
+}
+
 #[cfg_attr(not(feature = "fuzz-coverage"), inline)]
 /// Parse an artifact from an array of bytes. This does as much zero-copy
 /// deserialization as possible. In particular the function bodies are not
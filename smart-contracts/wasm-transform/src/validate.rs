@@ -831,6 +831,20 @@ pub fn validate<O: Borrow<OpCode>, H: Handler<O>>(
                 state.pop_expect_opd(Known(ValueType::I32))?;
                 state.push_opd(Known(ValueType::I64));
             }
+            OpCode::I32Extend8S | OpCode::I32Extend16S => {
+                state.pop_expect_opd(Known(ValueType::I32))?;
+                state.push_opd(Known(ValueType::I32));
+            }
+            OpCode::I64Extend8S | OpCode::I64Extend16S | OpCode::I64Extend32S => {
+                state.pop_expect_opd(Known(ValueType::I64))?;
+                state.push_opd(Known(ValueType::I64));
+            }
+            OpCode::MemoryCopy | OpCode::MemoryFill => {
+                ensure!(context.memory_exists(), "Memory should exist.");
+                state.pop_expect_opd(Known(ValueType::I32))?;
+                state.pop_expect_opd(Known(ValueType::I32))?;
+                state.pop_expect_opd(Known(ValueType::I32))?;
+            }
         }
         handler.handle_opcode(&state, old_stack_height, next_opcode)?;
     }
@@ -858,11 +872,79 @@ pub trait ValidateImportExport {
     fn validate_export_function(&self, item_name: &Name, ty: &FunctionType) -> bool;
 }
 
+/// Configuration of which Wasm features are allowed by validation. Features
+/// are enabled in new protocol versions, and modules that use them are
+/// rejected in earlier protocol versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationConfig {
+    /// Allow the sign extension instructions `i32.extend8_s`,
+    /// `i32.extend16_s`, `i64.extend8_s`, `i64.extend16_s`, and
+    /// `i64.extend32_s`.
+    pub allow_sign_extension: bool,
+    /// Allow the bulk memory instructions `memory.copy` and `memory.fill`.
+    /// Other bulk memory instructions are not supported.
+    pub allow_bulk_memory:    bool,
+}
+
+impl ValidationConfig {
+    /// The configuration in effect for protocol versions 1 to 5. None of the
+    /// optional features are enabled.
+    pub const V0: Self = Self {
+        allow_sign_extension: false,
+        allow_bulk_memory:    false,
+    };
+    /// The configuration in effect for protocol version 6 and later.
+    pub const V1: Self = Self {
+        allow_sign_extension: true,
+        allow_bulk_memory:    true,
+    };
+
+    /// The configuration in effect in the given protocol version.
+    pub fn for_protocol_version(protocol_version: u8) -> Self {
+        if protocol_version >= 6 {
+            Self::V1
+        } else {
+            Self::V0
+        }
+    }
+
+    /// Ensure that the opcode is allowed by the configuration.
+    fn check_opcode(&self, opcode: &OpCode) -> ValidateResult<()> {
+        match opcode {
+            OpCode::I32Extend8S
+            | OpCode::I32Extend16S
+            | OpCode::I64Extend8S
+            | OpCode::I64Extend16S
+            | OpCode::I64Extend32S => {
+                ensure!(self.allow_sign_extension, "Sign extension instructions are not allowed.")
+            }
+            OpCode::MemoryCopy | OpCode::MemoryFill => {
+                ensure!(self.allow_bulk_memory, "Bulk memory instructions are not allowed.")
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
 /// Validate the module. This function parses and validates the module at the
 /// same time, failing at the first encountered error.
+///
+/// Only the instructions of the original Wasm specification are allowed, i.e.,
+/// this uses the [`ValidationConfig::V0`] configuration.
 pub fn validate_module<'a>(
     imp: &impl ValidateImportExport,
     skeleton: &Skeleton<'a>,
+) -> ValidateResult<Module> {
+    validate_module_with_config(&ValidationConfig::V0, imp, skeleton)
+}
+
+/// Validate the module, allowing the features enabled by the given
+/// configuration. See [`validate_module`] for details.
+pub fn validate_module_with_config<'a>(
+    config: &ValidationConfig,
+    imp: &impl ValidateImportExport,
+    skeleton: &Skeleton<'a>,
 ) -> ValidateResult<Module> {
     // This is a technicality, but we need to parse the custom sections to ensure
     // that they are valid. Validity consists only of checking that the name part
@@ -963,10 +1045,21 @@ pub fn validate_module<'a>(
                     memory: memory.memory_type.is_some(),
                     table: table.table_type.is_some(),
                 };
-                let (opcodes, max_height) =
-                    validate(&ctx, &mut OpCodeIterator::new(c.expr_bytes), Vec::new())?;
+                let opcodes = OpCodeIterator::new(c.expr_bytes)
+                    .map(|opcode| opcode.and_then(|op| config.check_opcode(&op).map(|_| op)));
+                let (opcodes, max_height) = validate(&ctx, opcodes, Vec::new())?;
+                // Metering adds a scratch local to functions that use bulk memory
+                // instructions. It is counted here so that the limits also hold for
+                // the metered function.
+                let uses_bulk_memory =
+                    opcodes.iter().any(|op| matches!(op, OpCode::MemoryCopy | OpCode::MemoryFill));
+                let metered_num_locals = num_locals + u32::from(uses_bulk_memory);
+                ensure!(metered_num_locals <= ALLOWED_LOCALS, ValidationError::TooManyLocals {
+                    actual: metered_num_locals,
+                    max:    ALLOWED_LOCALS,
+                });
                 ensure!(
-                    num_locals as usize + max_height <= MAX_ALLOWED_STACK_HEIGHT,
+                    metered_num_locals as usize + max_height <= MAX_ALLOWED_STACK_HEIGHT,
                     "Stack height would exceed allowed limits."
                 );
 
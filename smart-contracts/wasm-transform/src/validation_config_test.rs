@@ -0,0 +1,176 @@
+//! Tests of instructions that are only allowed by some validation
+//! configurations.
+use crate::{
+    artifact::{Artifact, ArtifactNamedImport, CompiledFunction},
+    constants::MAX_ALLOWED_STACK_HEIGHT,
+    machine::{ExecutionOutcome, Host, NoInterrupt, RunResult, RuntimeStack, Value},
+    parse::parse_skeleton,
+    types::{FunctionType, Name},
+    utils,
+    validate::{
+        validate_module, validate_module_with_config, ValidateImportExport, ValidationConfig,
+    },
+};
+
+/// The module
+/// ```wasm
+/// (module
+///   (func (export "f") (result i32)
+///     i32.const 128
+///     i32.extend8_s))
+/// ```
+const SIGN_EXTENSION: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, // type section
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x00, // export section
+    0x0a, 0x08, 0x01, 0x06, 0x00, 0x41, 0x80, 0x01, 0xc0, 0x0b, // code section
+];
+
+/// The module
+/// ```wasm
+/// (module
+///   (memory 1)
+///   (func (export "f") (result i32)
+///     (memory.fill (i32.const 0) (i32.const 7) (i32.const 4))
+///     (memory.copy (i32.const 4) (i32.const 0) (i32.const 4))
+///     (i32.load (i32.const 4))))
+/// ```
+const BULK_MEMORY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+    0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, // type section
+    0x03, 0x02, 0x01, 0x00, // function section
+    0x05, 0x03, 0x01, 0x00, 0x01, // memory section
+    0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x00, // export section
+    0x0a, 0x1c, 0x01, 0x1a, 0x00, 0x41, 0x00, 0x41, 0x07, 0x41, 0x04, 0xfc, 0x0b, 0x00, 0x41, 0x04,
+    0x41, 0x00, 0x41, 0x04, 0xfc, 0x0a, 0x00, 0x00, 0x41, 0x04, 0x28, 0x02, 0x00,
+    0x0b, // code section
+];
+
+/// The module
+/// ```wasm
+/// (module
+///   (memory 1)
+///   (func (local i32 ... i32)
+///     (memory.fill (i32.const 0) (i32.const 0) (i32.const 0))))
+/// ```
+/// with `num_locals` locals, which must be between 2^7 and 2^14 so that the
+/// number is encoded in two bytes.
+fn bulk_memory_with_locals(num_locals: u32) -> Vec<u8> {
+    assert!((1 << 7..1 << 14).contains(&num_locals));
+    let mut module = vec![
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section
+        0x03, 0x02, 0x01, 0x00, // function section
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory section
+        0x0a, 0x10, 0x01, 0x0e, 0x01, // code section up to the number of locals
+    ];
+    module.push(0x80 | (num_locals & 0x7f) as u8);
+    module.push((num_locals >> 7) as u8);
+    module.extend_from_slice(&[0x7f, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0xfc, 0x0b, 0x00, 0x0b]);
+    module
+}
+
+struct AllowAll;
+
+impl ValidateImportExport for AllowAll {
+    fn validate_import_function(&self, _: bool, _: &Name, _: &Name, _: &FunctionType) -> bool {
+        true
+    }
+
+    fn validate_export_function(&self, _: &Name, _: &FunctionType) -> bool { true }
+}
+
+struct NoHost;
+
+impl Host<ArtifactNamedImport> for NoHost {
+    type Interrupt = NoInterrupt;
+
+    fn tick_initial_memory(&mut self, _: u32) -> RunResult<()> { Ok(()) }
+
+    fn call(
+        &mut self,
+        _: &ArtifactNamedImport,
+        _: &mut Vec<u8>,
+        _: &mut RuntimeStack,
+    ) -> RunResult<Option<Self::Interrupt>> {
+        anyhow::bail!("The module has no imports.")
+    }
+}
+
+fn run_f(bytes: &[u8]) -> Option<Value> {
+    let artifact: Artifact<ArtifactNamedImport, CompiledFunction> =
+        utils::instantiate_with_config(&ValidationConfig::V1, &AllowAll, bytes)
+            .expect("Module should be valid.");
+    match artifact.run(&mut NoHost, "f", &[]).expect("Execution should succeed.") {
+        ExecutionOutcome::Success {
+            result,
+            ..
+        } => result,
+        ExecutionOutcome::Interrupted {
+            ..
+        } => panic!("Execution should not be interrupted."),
+    }
+}
+
+#[test]
+fn test_rejected_by_default() {
+    for module in [SIGN_EXTENSION, BULK_MEMORY] {
+        let skeleton = parse_skeleton(module).expect("Module should parse.");
+        assert!(validate_module(&AllowAll, &skeleton).is_err());
+        assert!(validate_module_with_config(&ValidationConfig::V0, &AllowAll, &skeleton).is_err());
+        assert!(validate_module_with_config(&ValidationConfig::V1, &AllowAll, &skeleton).is_ok());
+    }
+}
+
+#[test]
+fn test_for_protocol_version() {
+    for protocol_version in 1..=5 {
+        assert_eq!(ValidationConfig::for_protocol_version(protocol_version), ValidationConfig::V0);
+    }
+    assert_eq!(ValidationConfig::for_protocol_version(6), ValidationConfig::V1);
+    let skeleton = parse_skeleton(SIGN_EXTENSION).expect("Module should parse.");
+    let p5 = ValidationConfig::for_protocol_version(5);
+    assert!(validate_module_with_config(&p5, &AllowAll, &skeleton).is_err());
+}
+
+#[test]
+fn test_sign_extension() {
+    assert_eq!(run_f(SIGN_EXTENSION), Some(Value::I32(-128)));
+}
+
+#[test]
+fn test_bulk_memory() {
+    assert_eq!(run_f(BULK_MEMORY), Some(Value::I32(0x0707_0707)));
+}
+
+#[test]
+fn test_bulk_memory_metering() {
+    let skeleton = parse_skeleton(BULK_MEMORY).expect("Module should parse.");
+    let mut module = validate_module_with_config(&ValidationConfig::V1, &AllowAll, &skeleton)
+        .expect("Module should be valid.");
+    module.inject_metering().expect("Metering should succeed.");
+    let code = &module.code.impls[0];
+    assert_eq!(code.num_locals, 1, "A scratch local should be added.");
+    module.compile::<ArtifactNamedImport>().expect("Metered module should compile.");
+}
+
+#[test]
+fn test_bulk_memory_metering_limits() {
+    // The operand stack has height 3 at the memory.fill, and metering adds a
+    // local, so this is the largest number of locals that is allowed.
+    let num_locals = MAX_ALLOWED_STACK_HEIGHT as u32 - 4;
+    let bytes = bulk_memory_with_locals(num_locals);
+    let skeleton = parse_skeleton(&bytes).expect("Module should parse.");
+    let mut module = validate_module_with_config(&ValidationConfig::V1, &AllowAll, &skeleton)
+        .expect("Module should be valid.");
+    module.inject_metering().expect("Metering should succeed.");
+    assert_eq!(module.code.impls[0].num_locals, num_locals + 1);
+
+    let bytes = bulk_memory_with_locals(num_locals + 1);
+    let skeleton = parse_skeleton(&bytes).expect("Module should parse.");
+    assert!(
+        validate_module_with_config(&ValidationConfig::V1, &AllowAll, &skeleton).is_err(),
+        "The scratch local of metering should be counted in the limits."
+    );
+}